@@ -1,9 +1,34 @@
 select
     *
 from
-    circle_events;
+    events;
 
 select
     *
 from
-    circle_snapshots;
\ No newline at end of file
+    snapshots;
+
+select
+    *
+from
+    event_sequence;
+
+select
+    *
+from
+    subscription_checkpoints;
+
+select
+    *
+from
+    circle_names;
+
+select
+    *
+from
+    idempotency_keys;
+
+select
+    *
+from
+    schema_migrations;
\ No newline at end of file
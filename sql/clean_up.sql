@@ -1,5 +1,19 @@
 DELETE FROM
-    circle_events;
+    events;
 
 DELETE FROM
-    circle_snapshots;
\ No newline at end of file
+    snapshots;
+
+UPDATE
+    event_sequence
+SET
+    last_sequence = 0;
+
+DELETE FROM
+    subscription_checkpoints;
+
+DELETE FROM
+    circle_names;
+
+DELETE FROM
+    idempotency_keys;
\ No newline at end of file
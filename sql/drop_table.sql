@@ -1,3 +1,13 @@
-DROP TABLE IF EXISTS circle_events;
+DROP TABLE IF EXISTS events;
 
-DROP TABLE IF EXISTS circle_snapshots;
\ No newline at end of file
+DROP TABLE IF EXISTS snapshots;
+
+DROP TABLE IF EXISTS event_sequence;
+
+DROP TABLE IF EXISTS subscription_checkpoints;
+
+DROP TABLE IF EXISTS circle_names;
+
+DROP TABLE IF EXISTS idempotency_keys;
+
+DROP TABLE IF EXISTS schema_migrations;
\ No newline at end of file
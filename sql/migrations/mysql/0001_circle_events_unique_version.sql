@@ -0,0 +1,7 @@
+-- Guarantee that a circle stream cannot contain two events with the same version.
+-- Fails if duplicates already exist; resolve them before applying.
+-- Safe to run again, as migrate cannot tell from the columns whether it was applied.
+ALTER TABLE circle_events DROP INDEX IF EXISTS idx_circle_version;
+
+ALTER TABLE circle_events
+    ADD UNIQUE KEY IF NOT EXISTS uq_circle_events_circle_version (circle_id, version);
@@ -0,0 +1,12 @@
+-- Outbox written in the same transaction as circle_events and drained by OutboxRelay.
+CREATE TABLE IF NOT EXISTS circle_event_outbox (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    event_id CHAR(36) NOT NULL,
+    circle_id CHAR(36) NOT NULL,
+    version INT NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    dispatched_at DATETIME NULL,
+    UNIQUE KEY uq_circle_event_outbox_event (event_id),
+    INDEX idx_circle_event_outbox_pending (dispatched_at, id)
+);
@@ -0,0 +1,26 @@
+-- Name reservations of active circles, maintained in the same transaction as circle_events.
+CREATE TABLE IF NOT EXISTS circle_names (
+    name VARCHAR(255) NOT NULL PRIMARY KEY,
+    circle_id CHAR(36) NOT NULL,
+    UNIQUE KEY uq_circle_names_circle (circle_id)
+);
+
+-- Backfill from the latest name of every circle that is not disbanded.
+-- If existing circles already share a name, only the first one keeps the reservation.
+INSERT IGNORE INTO circle_names (name, circle_id)
+SELECT JSON_UNQUOTE(JSON_EXTRACT(e.payload, '$.name')), e.circle_id
+FROM circle_events e
+WHERE JSON_TYPE(JSON_EXTRACT(e.payload, '$.name')) = 'STRING'
+  AND e.version = (
+      SELECT MAX(n.version)
+      FROM circle_events n
+      WHERE n.circle_id = e.circle_id
+        AND JSON_TYPE(JSON_EXTRACT(n.payload, '$.name')) = 'STRING'
+  )
+  AND NOT EXISTS (
+      SELECT 1
+      FROM circle_events d
+      WHERE d.circle_id = e.circle_id
+        AND d.event_type = 'circle_disbanded'
+  )
+ORDER BY e.occurred_at ASC;
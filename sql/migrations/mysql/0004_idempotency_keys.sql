@@ -0,0 +1,13 @@
+-- Responses of command requests sent with an Idempotency-Key header.
+-- A row without response_status is a claim on a request that is still running.
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    scope VARCHAR(100) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash CHAR(64) NOT NULL,
+    response_status SMALLINT NULL,
+    response_headers JSON NULL,
+    response_body LONGBLOB NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at DATETIME NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
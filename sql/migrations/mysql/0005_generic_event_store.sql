@@ -0,0 +1,33 @@
+-- Share one events table, one snapshots table and one outbox between all aggregate types.
+-- Existing rows belong to circles; they keep their ids and are tagged 'circle'.
+RENAME TABLE
+    circle_events TO events,
+    circle_snapshots TO snapshots,
+    circle_event_outbox TO event_outbox;
+
+ALTER TABLE events
+    DROP INDEX uq_circle_events_circle_version,
+    CHANGE circle_id aggregate_id VARCHAR(64) NOT NULL,
+    ADD COLUMN aggregate_type VARCHAR(100) NOT NULL DEFAULT 'circle' AFTER id,
+    ADD CONSTRAINT uq_events_stream_version UNIQUE (aggregate_type, aggregate_id, version);
+
+ALTER TABLE events ALTER COLUMN aggregate_type DROP DEFAULT;
+
+ALTER TABLE snapshots
+    DROP INDEX idx_circle_version,
+    CHANGE circle_id aggregate_id VARCHAR(64) NOT NULL,
+    ADD COLUMN aggregate_type VARCHAR(100) NOT NULL DEFAULT 'circle' AFTER id,
+    ADD INDEX idx_snapshots_stream_version (aggregate_type, aggregate_id, version DESC);
+
+ALTER TABLE snapshots ALTER COLUMN aggregate_type DROP DEFAULT;
+
+-- The outbox indexes are dropped and added again, as RENAME INDEX needs MariaDB 10.5.2.
+ALTER TABLE event_outbox
+    CHANGE circle_id aggregate_id VARCHAR(64) NOT NULL,
+    ADD COLUMN aggregate_type VARCHAR(100) NOT NULL DEFAULT 'circle' AFTER event_id,
+    DROP INDEX uq_circle_event_outbox_event,
+    ADD UNIQUE KEY uq_event_outbox_event (event_id),
+    DROP INDEX idx_circle_event_outbox_pending,
+    ADD INDEX idx_event_outbox_pending (dispatched_at, id);
+
+ALTER TABLE event_outbox ALTER COLUMN aggregate_type DROP DEFAULT;
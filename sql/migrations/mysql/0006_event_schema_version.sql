@@ -0,0 +1,4 @@
+-- Shape of each payload, so old events can be upcast when they are read.
+-- Rows written before this column existed use the first schema.
+ALTER TABLE events
+    ADD COLUMN schema_version INT NOT NULL DEFAULT 1 AFTER event_type;
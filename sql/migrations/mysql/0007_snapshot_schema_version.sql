@@ -0,0 +1,5 @@
+-- Shape of each snapshot state. Existing snapshots predate members, owner and status
+-- in some cases, so they are left at 1 and ignored until regenerated:
+--   cargo run --bin main -- regenerate-snapshots
+ALTER TABLE snapshots
+    ADD COLUMN schema_version INT NOT NULL DEFAULT 1 AFTER version;
@@ -0,0 +1,4 @@
+-- Context of the command that produced each event (actor, correlation and causation
+-- ids, client IP, user agent). Events written before this column existed have none.
+ALTER TABLE events
+    ADD COLUMN metadata JSON NULL AFTER payload;
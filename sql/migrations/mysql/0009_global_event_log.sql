@@ -0,0 +1,33 @@
+-- Position of every event in a single global log, read by catch-up subscriptions.
+-- Existing events are numbered in the order they occurred.
+ALTER TABLE events
+    ADD COLUMN sequence BIGINT NULL AFTER id;
+
+SET @sequence := 0;
+UPDATE events
+    SET sequence = (@sequence := @sequence + 1)
+    ORDER BY occurred_at, aggregate_type, aggregate_id, version;
+
+ALTER TABLE events
+    MODIFY COLUMN sequence BIGINT NOT NULL,
+    ADD CONSTRAINT uq_events_sequence UNIQUE (sequence);
+
+-- Last allocated position. Appends lock this row until commit, so positions become
+-- visible in order.
+CREATE TABLE IF NOT EXISTS event_sequence (
+    id TINYINT NOT NULL PRIMARY KEY,
+    last_sequence BIGINT NOT NULL
+);
+INSERT INTO event_sequence (id, last_sequence)
+    SELECT 1, COALESCE(MAX(sequence), 0) FROM events;
+
+-- Position of the last event handled by each subscription.
+CREATE TABLE IF NOT EXISTS subscription_checkpoints (
+    name VARCHAR(100) NOT NULL PRIMARY KEY,
+    position BIGINT NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Subscriptions replace the outbox. The projection starts from position 0 and skips
+-- the events it already applied, so undispatched rows are not lost.
+DROP TABLE IF EXISTS event_outbox;
@@ -0,0 +1,58 @@
+-- The whole schema, for an empty database, which skips 0001 to 0009. A database
+-- upgraded by them already has these tables, so there it changes nothing.
+CREATE TABLE IF NOT EXISTS events (
+    id CHAR(36) NOT NULL PRIMARY KEY,
+    sequence BIGINT NOT NULL,
+    aggregate_type VARCHAR(100) NOT NULL,
+    aggregate_id VARCHAR(64) NOT NULL,
+    version INT NOT NULL,
+    event_type VARCHAR(100) NOT NULL,
+    schema_version INT NOT NULL DEFAULT 1,
+    payload JSON NOT NULL,
+    metadata JSON NULL,
+    occurred_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uq_events_stream_version UNIQUE (aggregate_type, aggregate_id, version),
+    CONSTRAINT uq_events_sequence UNIQUE (sequence)
+);
+
+CREATE TABLE IF NOT EXISTS event_sequence (
+    id TINYINT NOT NULL PRIMARY KEY,
+    last_sequence BIGINT NOT NULL
+);
+
+INSERT IGNORE INTO event_sequence (id, last_sequence) VALUES (1, 0);
+
+CREATE TABLE IF NOT EXISTS snapshots (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    aggregate_type VARCHAR(100) NOT NULL,
+    aggregate_id VARCHAR(64) NOT NULL,
+    version INT NOT NULL,
+    schema_version INT NOT NULL DEFAULT 1,
+    state JSON NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_snapshots_stream_version (aggregate_type, aggregate_id, version DESC)
+);
+
+CREATE TABLE IF NOT EXISTS subscription_checkpoints (
+    name VARCHAR(100) NOT NULL PRIMARY KEY,
+    position BIGINT NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS circle_names (
+    name VARCHAR(255) NOT NULL PRIMARY KEY,
+    circle_id CHAR(36) NOT NULL,
+    UNIQUE KEY uq_circle_names_circle (circle_id)
+);
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    scope VARCHAR(100) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash CHAR(64) NOT NULL,
+    response_status SMALLINT NULL,
+    response_headers JSON NULL,
+    response_body LONGBLOB NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at DATETIME NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
@@ -0,0 +1,10 @@
+-- Circle names are reserved under their trimmed, lower-cased form, so the key no longer
+-- depends on the collation of circle_names. Where existing circles collide once
+-- trimmed, the one with the smallest id keeps the reservation.
+DELETE n
+FROM circle_names n
+JOIN circle_names k
+  ON LOWER(TRIM(k.name)) = LOWER(TRIM(n.name))
+ AND k.circle_id < n.circle_id;
+
+UPDATE circle_names SET name = LOWER(TRIM(name));
@@ -0,0 +1,63 @@
+-- The MySQL schema of mysql/0010_create_event_store.sql for PostgreSQL.
+-- Timestamps are UTC without a time zone, like the DATETIME columns, and ids are
+-- VARCHAR as CHAR would come back padded.
+CREATE TABLE IF NOT EXISTS events (
+    id VARCHAR(36) NOT NULL PRIMARY KEY,
+    sequence BIGINT NOT NULL,
+    aggregate_type VARCHAR(100) NOT NULL,
+    aggregate_id VARCHAR(64) NOT NULL,
+    version INT NOT NULL,
+    event_type VARCHAR(100) NOT NULL,
+    schema_version INT NOT NULL DEFAULT 1,
+    payload JSONB NOT NULL,
+    metadata JSONB NULL,
+    occurred_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC'),
+    CONSTRAINT uq_events_stream_version UNIQUE (aggregate_type, aggregate_id, version),
+    CONSTRAINT uq_events_sequence UNIQUE (sequence)
+);
+
+-- Last allocated position. Appends lock this row until commit, so positions become
+-- visible in order.
+CREATE TABLE IF NOT EXISTS event_sequence (
+    id SMALLINT NOT NULL PRIMARY KEY,
+    last_sequence BIGINT NOT NULL
+);
+
+INSERT INTO event_sequence (id, last_sequence) VALUES (1, 0) ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    aggregate_type VARCHAR(100) NOT NULL,
+    aggregate_id VARCHAR(64) NOT NULL,
+    version INT NOT NULL,
+    schema_version INT NOT NULL DEFAULT 1,
+    state JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC')
+);
+
+CREATE INDEX IF NOT EXISTS idx_snapshots_stream_version
+    ON snapshots (aggregate_type, aggregate_id, version DESC);
+
+CREATE TABLE IF NOT EXISTS subscription_checkpoints (
+    name VARCHAR(100) NOT NULL PRIMARY KEY,
+    position BIGINT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC')
+);
+
+CREATE TABLE IF NOT EXISTS circle_names (
+    name VARCHAR(255) NOT NULL PRIMARY KEY,
+    circle_id VARCHAR(64) NOT NULL,
+    CONSTRAINT uq_circle_names_circle UNIQUE (circle_id)
+);
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    scope VARCHAR(100) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash VARCHAR(64) NOT NULL,
+    response_status INT NULL,
+    response_headers JSONB NULL,
+    response_body BYTEA NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC'),
+    completed_at TIMESTAMP NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
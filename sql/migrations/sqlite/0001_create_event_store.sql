@@ -0,0 +1,63 @@
+-- The MySQL schema of mysql/0010_create_event_store.sql for a single SQLite file.
+-- JSON columns are TEXT and DATETIME columns hold 'YYYY-MM-DD HH:MM:SS' text, which
+-- compares in time order.
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT NOT NULL PRIMARY KEY,
+    sequence INTEGER NOT NULL,
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    schema_version INTEGER NOT NULL DEFAULT 1,
+    payload TEXT NOT NULL,
+    metadata TEXT NULL,
+    occurred_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT uq_events_stream_version UNIQUE (aggregate_type, aggregate_id, version),
+    CONSTRAINT uq_events_sequence UNIQUE (sequence)
+);
+
+-- Last allocated position. Appends update this row first, which takes the write lock
+-- of the database, so positions become visible in order.
+CREATE TABLE IF NOT EXISTS event_sequence (
+    id INTEGER NOT NULL PRIMARY KEY,
+    last_sequence INTEGER NOT NULL
+);
+
+INSERT OR IGNORE INTO event_sequence (id, last_sequence) VALUES (1, 0);
+
+CREATE TABLE IF NOT EXISTS snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    schema_version INTEGER NOT NULL DEFAULT 1,
+    state TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_snapshots_stream_version
+    ON snapshots (aggregate_type, aggregate_id, version DESC);
+
+CREATE TABLE IF NOT EXISTS subscription_checkpoints (
+    name TEXT NOT NULL PRIMARY KEY,
+    position INTEGER NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS circle_names (
+    name TEXT NOT NULL PRIMARY KEY,
+    circle_id TEXT NOT NULL,
+    CONSTRAINT uq_circle_names_circle UNIQUE (circle_id)
+);
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    scope TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_hash TEXT NOT NULL,
+    response_status INTEGER NULL,
+    response_headers TEXT NULL,
+    response_body BLOB NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TEXT NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
@@ -0,0 +1,5 @@
+-- Reads across the streams of one aggregate type: catch-up subscriptions follow the
+-- global position, and streams are listed by when their events occurred.
+-- Reads of a single stream are served by uq_events_stream_version.
+CREATE INDEX IF NOT EXISTS idx_events_type_sequence ON events (aggregate_type, sequence);
+CREATE INDEX IF NOT EXISTS idx_events_type_occurred_at ON events (aggregate_type, occurred_at);
@@ -0,0 +1,13 @@
+-- Circle names are reserved under their trimmed, lower-cased form, so names differing
+-- only in case are taken as the same. Where existing circles already collide, the one
+-- with the smallest id keeps the reservation.
+-- LOWER folds ASCII letters only here; other names keep their case until renamed.
+DELETE FROM circle_names
+WHERE EXISTS (
+    SELECT 1
+    FROM circle_names k
+    WHERE LOWER(TRIM(k.name)) = LOWER(TRIM(circle_names.name))
+      AND k.circle_id < circle_names.circle_id
+);
+
+UPDATE circle_names SET name = LOWER(TRIM(name));
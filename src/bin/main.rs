@@ -1,6 +1,21 @@
-use main::app::run;
+use main::app::{migrate, rebuild_projection, regenerate_snapshots, run};
+
+const USAGE: &str = "usage: app [serve | migrate | rebuild-projection | regenerate-snapshots]";
 
 #[tokio::main]
 async fn main() {
-    run().await.expect("Failed to run the app");
+    match std::env::args().nth(1).as_deref() {
+        None | Some("serve") => run().await.expect("Failed to run the app"),
+        Some("migrate") => migrate().await.expect("Failed to migrate the database"),
+        Some("rebuild-projection") => rebuild_projection()
+            .await
+            .expect("Failed to rebuild the projection"),
+        Some("regenerate-snapshots") => regenerate_snapshots()
+            .await
+            .expect("Failed to regenerate snapshots"),
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    }
 }
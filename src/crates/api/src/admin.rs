@@ -0,0 +1,121 @@
+//! Access to the `/admin` endpoints.
+//!
+//! They are disabled unless an [`AdminToken`] is configured, and then require it as
+//! `Authorization: Bearer <token>`.
+
+use std::fmt;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts, StatusCode},
+};
+
+use crate::error::ApiError;
+
+/// Secret the `/admin` endpoints require, or `None` to disable them.
+#[derive(Clone, Default)]
+pub struct AdminToken(Option<String>);
+
+impl AdminToken {
+    /// An empty token disables the endpoints like a missing one.
+    pub fn new(token: Option<String>) -> Self {
+        Self(token.filter(|token| !token.is_empty()))
+    }
+
+    fn matches(&self, presented: &str) -> bool {
+        let Some(token) = &self.0 else {
+            return false;
+        };
+        // compares every byte, so the time taken does not tell how much of it matched
+        token.len() == presented.len()
+            && token
+                .bytes()
+                .zip(presented.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+impl fmt::Debug for AdminToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let token = self.0.as_ref().map(|_| "<redacted>");
+        f.debug_tuple("AdminToken").field(&token).finish()
+    }
+}
+
+/// Proof that the request carries the [`AdminToken`]. Rejected with 404 while no token
+/// is configured, and with 401 when it is missing or wrong.
+#[derive(Clone, Copy, Debug)]
+pub struct Admin;
+
+impl<S: Send + Sync> FromRequestParts<S> for Admin
+where
+    AdminToken: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let token = AdminToken::from_ref(state);
+        if token.0.is_none() {
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "not_found"));
+        }
+        let presented = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match presented {
+            Some(presented) if token.matches(presented.trim()) => Ok(Admin),
+            _ => Err(ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized")
+                .with_detail("send the admin token as Authorization: Bearer <token>")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    async fn authorize(token: AdminToken, authorization: Option<&str>) -> Result<Admin, ApiError> {
+        let mut request = Request::builder();
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        let (mut parts, _) = request
+            .body(())
+            .map_err(|_| ApiError::internal())?
+            .into_parts();
+        Admin::from_request_parts(&mut parts, &token).await
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints_are_disabled_without_a_token() {
+        for token in [AdminToken::default(), AdminToken::new(Some(String::new()))] {
+            let rejection = authorize(token, Some("Bearer ")).await.err();
+            assert_eq!(rejection.map(|e| e.status()), Some(StatusCode::NOT_FOUND));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admin_token_is_required() {
+        let token = AdminToken::new(Some("secret".to_string()));
+        for authorization in [
+            None,
+            Some("Bearer wrong"),
+            Some("secret"),
+            Some("Bearer secre"),
+        ] {
+            let rejection = authorize(token.clone(), authorization).await.err();
+            assert_eq!(rejection.map(|e| e.code()), Some("unauthorized"));
+        }
+        assert!(authorize(token, Some("Bearer secret")).await.is_ok());
+    }
+
+    #[test]
+    fn test_debug_hides_the_token() {
+        let token = AdminToken::new(Some("secret".to_string()));
+        assert!(!format!("{:?}", token).contains("secret"));
+    }
+}
@@ -1,24 +1,63 @@
 use std::sync::Arc;
 
+use axum::extract::FromRef;
 use command::command_handler::{CommandHandler, HasCommandHandler};
+use domain::interface::query::projection_rebuilder_interface::{
+    HasProjectionRebuilder, ProjectionRebuilderInterface,
+};
 use query::query_handler::{HasQueryHandler, QueryHandler};
 
+use crate::{admin::AdminToken, metadata::TrustedProxies};
+
 #[derive(Clone)]
 pub struct AppState {
     pub command_handler: Arc<dyn CommandHandler + Send + Sync>,
     pub query_handler: Arc<dyn QueryHandler + Send + Sync>,
+    pub projection_rebuilder: Arc<dyn ProjectionRebuilderInterface + Send + Sync>,
+    pub trusted_proxies: TrustedProxies,
+    pub admin_token: AdminToken,
 }
 
 impl AppState {
     pub fn new(
         command_handler: Arc<dyn CommandHandler + Send + Sync>,
         query_handler: Arc<dyn QueryHandler + Send + Sync>,
+        projection_rebuilder: Arc<dyn ProjectionRebuilderInterface + Send + Sync>,
     ) -> Self {
         Self {
             command_handler,
             query_handler,
+            projection_rebuilder,
+            trusted_proxies: TrustedProxies::default(),
+            admin_token: AdminToken::default(),
         }
     }
+
+    /// Believes `X-Forwarded-For` of requests from these proxies; no one is trusted by
+    /// default.
+    pub fn with_trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    /// Enables the `/admin` endpoints for requests carrying this token; they are
+    /// disabled by default.
+    pub fn with_admin_token(mut self, admin_token: AdminToken) -> Self {
+        self.admin_token = admin_token;
+        self
+    }
+}
+
+impl FromRef<AppState> for AdminToken {
+    fn from_ref(state: &AppState) -> Self {
+        state.admin_token.clone()
+    }
+}
+
+impl FromRef<AppState> for TrustedProxies {
+    fn from_ref(state: &AppState) -> Self {
+        state.trusted_proxies.clone()
+    }
 }
 
 impl HasCommandHandler for AppState {
@@ -32,3 +71,9 @@ impl HasQueryHandler for AppState {
         self.query_handler.clone()
     }
 }
+
+impl HasProjectionRebuilder for AppState {
+    fn projection_rebuilder(&self) -> Arc<dyn ProjectionRebuilderInterface + Send + Sync> {
+        self.projection_rebuilder.clone()
+    }
+}
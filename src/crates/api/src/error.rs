@@ -0,0 +1,333 @@
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use command::command::{
+    create_circle, disband_circle, join_circle, leave_circle, transfer_ownership, update_circle,
+};
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// `application/problem+json` body (RFC 7807).
+///
+/// `code` is stable across releases and is what clients should branch on;
+/// `title` and `detail` are for humans.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalid_params: Vec<InvalidParam>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct InvalidParam {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    detail: Option<String>,
+    invalid_params: Vec<InvalidParam>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str) -> Self {
+        Self {
+            status,
+            code,
+            detail: None,
+            invalid_params: vec![],
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn with_invalid_param(mut self, name: &str, reason: &str) -> Self {
+        self.invalid_params.push(InvalidParam {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        });
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    pub fn invalid_input(name: &str, reason: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_input").with_invalid_param(name, reason)
+    }
+
+    pub fn circle_not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "circle_not_found")
+    }
+
+    pub fn internal() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error")
+    }
+
+    /// The `If-Match` version is not the current one.
+    pub fn precondition_failed() -> Self {
+        Self::new(StatusCode::PRECONDITION_FAILED, "precondition_failed")
+            .with_detail("the circle was modified by another request")
+    }
+
+    /// Neither `If-Match` nor a `version` field was given.
+    pub fn precondition_required() -> Self {
+        Self::new(StatusCode::PRECONDITION_REQUIRED, "precondition_required")
+            .with_detail("send If-Match with the ETag of the circle, or a version field")
+    }
+
+    fn version_mismatch() -> Self {
+        Self::new(StatusCode::CONFLICT, "version_mismatch")
+            .with_detail("the circle was modified by another request")
+    }
+
+    fn disbanded() -> Self {
+        Self::new(StatusCode::GONE, "circle_disbanded")
+    }
+
+    fn invalid_capacity() -> Self {
+        Self::invalid_input("capacity", "must be at least 3")
+    }
+
+    /// The circle exists but the named member is not in it, so the request
+    /// is unprocessable rather than pointing at a missing resource.
+    fn not_member(name: &str) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, "not_member")
+            .with_invalid_param(name, "is not a member of the circle")
+    }
+
+    fn duplicate_name() -> Self {
+        Self::new(StatusCode::CONFLICT, "duplicate_circle_name")
+            .with_invalid_param("circle_name", "is already taken by another circle")
+    }
+
+    fn to_problem(&self) -> ProblemDetails {
+        ProblemDetails {
+            problem_type: format!("/problems/{}", self.code),
+            title: self
+                .status
+                .canonical_reason()
+                .unwrap_or("Error")
+                .to_string(),
+            status: self.status.as_u16(),
+            code: self.code.to_string(),
+            detail: self.detail.clone(),
+            invalid_params: self.invalid_params.clone(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            [(CONTENT_TYPE, PROBLEM_JSON)],
+            Json(self.to_problem()),
+        )
+            .into_response()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::new(rejection.status(), "malformed_body").with_detail(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "malformed_query").with_detail(rejection.body_text())
+    }
+}
+
+impl From<create_circle::Error> for ApiError {
+    fn from(e: create_circle::Error) -> Self {
+        match e {
+            create_circle::Error::Circle => Self::internal(),
+            create_circle::Error::Duplicate => Self::duplicate_name(),
+            create_circle::Error::InvalidCapacity => Self::invalid_capacity(),
+            create_circle::Error::InvalidOwnerId => {
+                Self::invalid_input("owner_id", "must not be empty")
+            }
+            create_circle::Error::InvalidOwnerName => {
+                Self::invalid_input("owner_name", "must not be empty")
+            }
+        }
+    }
+}
+
+impl From<update_circle::Error> for ApiError {
+    fn from(e: update_circle::Error) -> Self {
+        match e {
+            update_circle::Error::CapacityBelowMembers => Self::invalid_input(
+                "capacity",
+                "must not be below the current number of members",
+            ),
+            update_circle::Error::Circle => Self::internal(),
+            update_circle::Error::Disbanded => Self::disbanded(),
+            update_circle::Error::Duplicate => Self::duplicate_name(),
+            update_circle::Error::InvalidCapacity => Self::invalid_capacity(),
+            update_circle::Error::InvalidInput => {
+                Self::invalid_input("circle_id", "is not a valid circle id")
+            }
+            update_circle::Error::NotFound => Self::circle_not_found(),
+            update_circle::Error::VersionMismatch => Self::version_mismatch(),
+        }
+    }
+}
+
+impl From<disband_circle::Error> for ApiError {
+    fn from(e: disband_circle::Error) -> Self {
+        match e {
+            disband_circle::Error::Circle => Self::internal(),
+            disband_circle::Error::Disbanded => Self::disbanded(),
+            disband_circle::Error::InvalidInput => {
+                Self::invalid_input("circle_id", "is not a valid circle id")
+            }
+            disband_circle::Error::NotFound => Self::circle_not_found(),
+            disband_circle::Error::VersionMismatch => Self::version_mismatch(),
+        }
+    }
+}
+
+impl From<join_circle::Error> for ApiError {
+    fn from(e: join_circle::Error) -> Self {
+        match e {
+            join_circle::Error::AlreadyMember => Self::new(StatusCode::CONFLICT, "already_member")
+                .with_invalid_param("member_id", "is already a member of the circle"),
+            join_circle::Error::Circle => Self::internal(),
+            join_circle::Error::CircleFull => Self::new(StatusCode::CONFLICT, "circle_full"),
+            join_circle::Error::Disbanded => Self::disbanded(),
+            join_circle::Error::InvalidInput => {
+                Self::invalid_input("member_id", "must not be empty")
+                    .with_invalid_param("member_name", "must not be empty")
+            }
+            join_circle::Error::NotFound => Self::circle_not_found(),
+            join_circle::Error::VersionMismatch => Self::version_mismatch(),
+        }
+    }
+}
+
+impl From<leave_circle::Error> for ApiError {
+    fn from(e: leave_circle::Error) -> Self {
+        match e {
+            leave_circle::Error::Circle => Self::internal(),
+            leave_circle::Error::Disbanded => Self::disbanded(),
+            leave_circle::Error::InvalidInput => {
+                Self::invalid_input("member_id", "must not be empty")
+            }
+            leave_circle::Error::NotFound => Self::circle_not_found(),
+            leave_circle::Error::NotMember => Self::not_member("member_id"),
+            leave_circle::Error::OwnerCannotLeave => {
+                Self::new(StatusCode::CONFLICT, "owner_cannot_leave")
+                    .with_detail("transfer ownership before leaving the circle")
+            }
+            leave_circle::Error::VersionMismatch => Self::version_mismatch(),
+        }
+    }
+}
+
+impl From<transfer_ownership::Error> for ApiError {
+    fn from(e: transfer_ownership::Error) -> Self {
+        match e {
+            transfer_ownership::Error::AlreadyOwner => {
+                Self::new(StatusCode::CONFLICT, "already_owner")
+                    .with_invalid_param("new_owner_id", "already owns the circle")
+            }
+            transfer_ownership::Error::Circle => Self::internal(),
+            transfer_ownership::Error::Disbanded => Self::disbanded(),
+            transfer_ownership::Error::InvalidInput => {
+                Self::invalid_input("new_owner_id", "must not be empty")
+            }
+            transfer_ownership::Error::NotFound => Self::circle_not_found(),
+            transfer_ownership::Error::NotMember => Self::not_member("new_owner_id"),
+            transfer_ownership::Error::VersionMismatch => Self::version_mismatch(),
+        }
+    }
+}
+
+/// Query modules fail with `anyhow::Error`; anything not recognised is an internal error.
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        tracing::error!("error: {:?}", e);
+        Self::internal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_problem_json_response() -> anyhow::Result<()> {
+        let response = ApiError::from(create_circle::Error::InvalidCapacity).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).map(|v| v.as_bytes()),
+            Some(PROBLEM_JSON.as_bytes())
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let problem: ProblemDetails = serde_json::from_slice(&body)?;
+        assert_eq!(problem.code, "invalid_input");
+        assert_eq!(problem.status, 400);
+        assert_eq!(problem.problem_type, "/problems/invalid_input");
+        assert_eq!(
+            problem.invalid_params,
+            vec![InvalidParam {
+                name: "capacity".to_string(),
+                reason: "must be at least 3".to_string(),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_input_names_the_field() {
+        for error in [
+            ApiError::from(update_circle::Error::InvalidInput),
+            ApiError::from(disband_circle::Error::InvalidInput),
+            ApiError::from(join_circle::Error::InvalidInput),
+        ] {
+            assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+            assert!(!error.invalid_params.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_not_member_is_unprocessable() {
+        for error in [
+            ApiError::from(leave_circle::Error::NotMember),
+            ApiError::from(transfer_ownership::Error::NotMember),
+        ] {
+            assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(error.code(), "not_member");
+        }
+    }
+
+    #[test]
+    fn test_missing_circle_is_not_found() {
+        let error = ApiError::from(update_circle::Error::NotFound);
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        assert_eq!(error.code(), "circle_not_found");
+    }
+}
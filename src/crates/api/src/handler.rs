@@ -1,11 +1,31 @@
 use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        Json, Path, Query, State,
+    },
+    http::{header::ETAG, StatusCode},
+    response::{IntoResponse, Response},
 };
 
-use crate::app_state::AppState;
-use command::command::{create_circle, update_circle};
-use query::query::get_circle;
+use crate::{
+    admin::Admin,
+    app_state::AppState,
+    error::ApiError,
+    idempotency::IdempotencyKey,
+    metadata::RequestMetadata,
+    precondition::{entity_tag, IfMatch, IfNoneMatch},
+};
+use command::command::{
+    create_circle, disband_circle, join_circle, leave_circle, transfer_ownership, update_circle,
+};
+use domain::aggregate::{circle::Circle, value_object::event_metadata::EventMetadata};
+use domain::interface::command::circle_repository_interface::CircleNotFound;
+use domain::interface::query::{
+    circle_event_reader_interface::AsOf,
+    circle_reader_interface::{CircleSortKey, InvalidCursor, SortOrder},
+    projection_rebuilder_interface::{RebuildInProgress, RebuildProgress},
+};
+use query::query::{get_circle, get_circle_as_of, list_circle_events, list_circles};
 use serde::Deserialize;
 use std::env;
 
@@ -18,6 +38,8 @@ pub async fn handle_get_version() -> String {
 pub struct CreateCircleRequestBody {
     pub circle_name: String,
     pub capacity: i16,
+    pub owner_id: String,
+    pub owner_name: String,
 }
 
 impl std::convert::From<CreateCircleRequestBody> for create_circle::Input {
@@ -25,11 +47,16 @@ impl std::convert::From<CreateCircleRequestBody> for create_circle::Input {
         CreateCircleRequestBody {
             circle_name,
             capacity,
+            owner_id,
+            owner_name,
         }: CreateCircleRequestBody,
     ) -> Self {
         create_circle::Input {
             circle_name,
             capacity,
+            owner_id,
+            owner_name,
+            metadata: EventMetadata::default(),
         }
     }
 }
@@ -47,25 +74,60 @@ impl std::convert::From<create_circle::Output> for CreateCircleResponseBody {
 
 pub async fn handle_create_circle(
     State(state): State<AppState>,
-    Json(body): Json<CreateCircleRequestBody>,
-) -> Result<Json<CreateCircleResponseBody>, StatusCode> {
-    match state.command_handler.create_circle(body.into()).await {
-        Ok(output) => Ok(Json(CreateCircleResponseBody::from(output))),
-        Err(e) => {
-            tracing::error!("error: {:?}", e);
-            match e {
-                create_circle::Error::InvalidInput => Err(StatusCode::BAD_REQUEST),
-                create_circle::Error::Duplicate => Err(StatusCode::CONFLICT),
-                create_circle::Error::Circle => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    idempotency_key: IdempotencyKey,
+    RequestMetadata(metadata): RequestMetadata,
+    body: Result<Json<CreateCircleRequestBody>, JsonRejection>,
+) -> Result<Response, ApiError> {
+    let Json(body) = body?;
+    let request = serde_json::to_vec(&body).map_err(anyhow::Error::from)?;
+    let command_handler = state.command_handler.clone();
+    let create = async move {
+        let input = create_circle::Input {
+            metadata,
+            ..body.into()
+        };
+        match command_handler.create_circle(input).await {
+            Ok(output) => Json(CreateCircleResponseBody::from(output)).into_response(),
+            Err(e) => {
+                tracing::error!("error: {:?}", e);
+                ApiError::from(e).into_response()
             }
         }
-    }
+    };
+    idempotency_key
+        .run(
+            state.command_handler.idempotency_store(),
+            "POST /circle",
+            &request,
+            create,
+        )
+        .await
 }
 
 // fetch
 #[derive(Debug, Deserialize)]
 pub struct FetchCircleInputParam {
-    id: Option<String>,
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FetchCircleQuery {
+    pub as_of_version: Option<u32>,
+    pub as_of: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+impl FetchCircleQuery {
+    fn as_of(&self) -> Result<Option<AsOf>, ApiError> {
+        match (self.as_of_version, self.as_of) {
+            (None, None) => Ok(None),
+            (Some(version), None) => Ok(Some(AsOf::Version(version.into()))),
+            (None, Some(time)) => Ok(Some(AsOf::Time(time.naive_utc()))),
+            (Some(_), Some(_)) => Err(ApiError::invalid_input(
+                "as_of",
+                "cannot be combined with as_of_version",
+            )),
+        }
+    }
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -73,68 +135,220 @@ pub struct FetcheCircleResponseBody {
     pub circle_id: String,
     pub circle_name: String,
     pub capacity: i16,
+    pub owner_id: Option<String>,
+    pub members: Vec<MemberResponseBody>,
+    pub status: String,
 }
 
-impl std::convert::From<get_circle::Output> for FetcheCircleResponseBody {
-    fn from(output: get_circle::Output) -> Self {
-        match output.0 {
-            Some(circle) => FetcheCircleResponseBody {
-                circle_id: circle.id.to_string(),
-                circle_name: circle.name,
-                capacity: circle.capacity,
-            },
-            // TODO: None の場合の処理
-            None => FetcheCircleResponseBody {
-                circle_id: "".to_string(),
-                circle_name: "".to_string(),
-                capacity: 0,
-            },
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct MemberResponseBody {
+    pub member_id: String,
+    pub member_name: String,
+}
+
+impl std::convert::From<Circle> for FetcheCircleResponseBody {
+    fn from(circle: Circle) -> Self {
+        FetcheCircleResponseBody {
+            circle_id: circle.id.to_string(),
+            circle_name: circle.name,
+            capacity: circle.capacity,
+            owner_id: circle.owner_id.map(|owner_id| owner_id.to_string()),
+            members: circle
+                .members
+                .into_iter()
+                .map(|member| MemberResponseBody {
+                    member_id: member.id.to_string(),
+                    member_name: member.name,
+                })
+                .collect(),
+            status: circle.status.as_str().to_string(),
         }
     }
 }
 
-// TODO: impl From<list_circles::Output> for FetcheCircleResponseBody
-
 pub async fn handle_fetch_circle(
     State(state): State<AppState>,
     Path(param): Path<FetchCircleInputParam>,
-) -> Result<Json<Vec<FetcheCircleResponseBody>>, String> {
-    match param.id {
-        Some(id) => {
-            match state
+    if_none_match: IfNoneMatch,
+    query: Result<Query<FetchCircleQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let Query(query) = query?;
+    let circle = match query.as_of()? {
+        Some(as_of) => {
+            state
+                .query_handler
+                .get_circle_as_of(get_circle_as_of::Input {
+                    circle_id: param.id,
+                    as_of,
+                })
+                .await?
+                .0
+        }
+        None => {
+            state
                 .query_handler
                 .get_circle(get_circle::Input {
-                    circle_id: id.clone(),
+                    circle_id: param.id,
                 })
-                .await
-            {
-                Ok(output) => Ok(Json(vec![FetcheCircleResponseBody::from(output)])),
-                Err(e) => {
-                    tracing::error!("error: {:?}", e);
-                    Err("error".to_string())
-                }
-            }
+                .await?
+                .0
         }
-        None => match state.query_handler.list_circles().await {
-            Ok(output) => {
-                let circles = output.0;
-                Ok(Json(
-                    circles
-                        .into_iter()
-                        .map(|circle| {
-                            FetcheCircleResponseBody::from(get_circle::Output(Some(circle)))
-                        })
-                        .collect(),
-                ))
-            }
-            Err(e) => {
-                tracing::error!("error: {:?}", e);
-                Err("error".to_string())
+    };
+    let circle = circle.ok_or_else(ApiError::circle_not_found)?;
+
+    let etag = entity_tag(circle.version);
+    if if_none_match.matches(circle.version) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+    }
+    Ok((
+        [(ETAG, etag)],
+        Json(vec![FetcheCircleResponseBody::from(circle)]),
+    )
+        .into_response())
+}
+
+// list
+#[derive(Debug, Deserialize)]
+pub struct ListCirclesQuery {
+    pub sort: Option<CircleSortKey>,
+    pub order: Option<SortOrder>,
+    pub name_prefix: Option<String>,
+    pub min_capacity: Option<i16>,
+    pub max_capacity: Option<i16>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+impl ListCirclesQuery {
+    fn into_input(self) -> Result<list_circles::Input, ApiError> {
+        if let (Some(min), Some(max)) = (self.min_capacity, self.max_capacity) {
+            if min > max {
+                return Err(ApiError::invalid_input(
+                    "min_capacity",
+                    "must not be greater than max_capacity",
+                ));
             }
-        },
+        }
+        Ok(list_circles::Input {
+            sort_key: self.sort.unwrap_or_default(),
+            order: self.order.unwrap_or_default(),
+            name_prefix: self.name_prefix,
+            min_capacity: self.min_capacity,
+            max_capacity: self.max_capacity,
+            limit: self.limit,
+            cursor: self.cursor,
+        })
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct ListCirclesResponseBody {
+    pub circles: Vec<FetcheCircleResponseBody>,
+    pub next_cursor: Option<String>,
+}
+
+impl std::convert::From<list_circles::Output> for ListCirclesResponseBody {
+    fn from(output: list_circles::Output) -> Self {
+        ListCirclesResponseBody {
+            circles: output
+                .circles
+                .into_iter()
+                .map(FetcheCircleResponseBody::from)
+                .collect(),
+            next_cursor: output.next_cursor,
+        }
+    }
+}
+
+pub async fn handle_list_circles(
+    State(state): State<AppState>,
+    query: Result<Query<ListCirclesQuery>, QueryRejection>,
+) -> Result<Json<ListCirclesResponseBody>, ApiError> {
+    let Query(query) = query?;
+    match state.query_handler.list_circles(query.into_input()?).await {
+        Ok(output) => Ok(Json(ListCirclesResponseBody::from(output))),
+        Err(e) if e.is::<InvalidCursor>() => Err(ApiError::invalid_input(
+            "cursor",
+            "is not a cursor returned by this endpoint",
+        )),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// events
+#[derive(Debug, Deserialize)]
+pub struct FetchCircleEventsInputParam {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FetchCircleEventsQuery {
+    pub from_version: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct CircleEventResponseBody {
+    pub event_id: String,
+    pub version: u32,
+    pub event_type: String,
+    pub occurred_at: String,
+    pub payload: serde_json::Value,
+    pub metadata: EventMetadata,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct FetchCircleEventsResponseBody {
+    pub events: Vec<CircleEventResponseBody>,
+    pub next_from_version: Option<u32>,
+}
+
+impl std::convert::TryFrom<list_circle_events::Output> for FetchCircleEventsResponseBody {
+    type Error = serde_json::Error;
+
+    fn try_from(output: list_circle_events::Output) -> Result<Self, Self::Error> {
+        let events = output
+            .events
+            .into_iter()
+            .map(|event| {
+                Ok(CircleEventResponseBody {
+                    event_id: event.id.to_string(),
+                    version: u32::from(event.version),
+                    event_type: event.data.event_type().to_string(),
+                    occurred_at: event.occurred_at.and_utc().to_rfc3339(),
+                    payload: serde_json::to_value(&event.data)?,
+                    metadata: event.metadata,
+                })
+            })
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        Ok(FetchCircleEventsResponseBody {
+            events,
+            next_from_version: output.next_from_version,
+        })
     }
 }
 
+pub async fn handle_fetch_circle_events(
+    State(state): State<AppState>,
+    Path(path): Path<FetchCircleEventsInputParam>,
+    query: Result<Query<FetchCircleEventsQuery>, QueryRejection>,
+) -> Result<Json<FetchCircleEventsResponseBody>, ApiError> {
+    let Query(query) = query?;
+    let input = list_circle_events::Input {
+        circle_id: path.id,
+        from_version: query.from_version,
+        limit: query.limit,
+    };
+    let output = match state.query_handler.list_circle_events(input).await {
+        Ok(output) => output,
+        Err(e) if e.is::<CircleNotFound>() => return Err(ApiError::circle_not_found()),
+        Err(e) => return Err(e.into()),
+    };
+    FetchCircleEventsResponseBody::try_from(output)
+        .map(Json)
+        .map_err(|e| ApiError::from(anyhow::Error::from(e)))
+}
+
 // update
 #[derive(Debug, Deserialize)]
 pub struct UpdateCircleInputParam {
@@ -145,16 +359,24 @@ pub struct UpdateCircleInputParam {
 pub struct UpdateCircleRequestBody {
     pub circle_name: Option<String>,
     pub capacity: Option<i16>,
-    pub version: u32,
+    /// Ignored when the request carries `If-Match`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
 }
 
 impl UpdateCircleRequestBody {
-    pub fn into_to_input(self, id: String) -> update_circle::Input {
+    pub fn into_to_input(
+        self,
+        id: String,
+        version: u32,
+        metadata: EventMetadata,
+    ) -> update_circle::Input {
         update_circle::Input {
             circle_id: id,
             circle_name: self.circle_name,
             capacity: self.capacity,
-            version: self.version,
+            version,
+            metadata,
         }
     }
 }
@@ -175,20 +397,305 @@ impl std::convert::From<update_circle::Output> for UpdateCircleResponseBody {
 pub async fn handle_update_circle(
     State(state): State<AppState>,
     Path(path): Path<UpdateCircleInputParam>,
-    Json(body): Json<UpdateCircleRequestBody>,
-) -> Result<Json<UpdateCircleResponseBody>, StatusCode> {
+    if_match: IfMatch,
+    idempotency_key: IdempotencyKey,
+    RequestMetadata(metadata): RequestMetadata,
+    body: Result<Json<UpdateCircleRequestBody>, JsonRejection>,
+) -> Result<Response, ApiError> {
+    let Json(body) = body?;
     tracing::info!("update circle: {:?}", body);
-    let input = body.into_to_input(path.id);
-    match state.command_handler.update_circle(input).await {
-        Ok(output) => Ok(Json(UpdateCircleResponseBody::from(output))),
+    let version = match (if_match.0, body.version) {
+        (Some(version), _) => u32::from(version),
+        (None, Some(version)) => version,
+        (None, None) => return Err(ApiError::precondition_required()),
+    };
+    let scope = format!("PUT /circle/{}", path.id);
+    let request = serde_json::to_vec(&(&body.circle_name, body.capacity, version))
+        .map_err(anyhow::Error::from)?;
+    let input = body.into_to_input(path.id, version, metadata);
+    let command_handler = state.command_handler.clone();
+    let update = async move {
+        match command_handler.update_circle(input).await {
+            Ok(output) => {
+                let etag = entity_tag(output.version.into());
+                ([(ETAG, etag)], Json(UpdateCircleResponseBody::from(output))).into_response()
+            }
+            Err(e) => {
+                tracing::error!("error: {:?}", e);
+                match e {
+                    update_circle::Error::VersionMismatch if if_match.0.is_some() => {
+                        ApiError::precondition_failed().into_response()
+                    }
+                    e => ApiError::from(e).into_response(),
+                }
+            }
+        }
+    };
+    idempotency_key
+        .run(
+            state.command_handler.idempotency_store(),
+            &scope,
+            &request,
+            update,
+        )
+        .await
+}
+
+// disband
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct DisbandCircleRequestBody {
+    pub version: u32,
+}
+
+impl DisbandCircleRequestBody {
+    pub fn into_to_input(self, id: String, metadata: EventMetadata) -> disband_circle::Input {
+        disband_circle::Input {
+            circle_id: id,
+            version: self.version,
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct DisbandCircleResponseBody {
+    pub circle_id: String,
+}
+
+impl std::convert::From<disband_circle::Output> for DisbandCircleResponseBody {
+    fn from(output: disband_circle::Output) -> Self {
+        DisbandCircleResponseBody {
+            circle_id: output.circle_id,
+        }
+    }
+}
+
+pub async fn handle_disband_circle(
+    State(state): State<AppState>,
+    Path(path): Path<UpdateCircleInputParam>,
+    RequestMetadata(metadata): RequestMetadata,
+    body: Result<Json<DisbandCircleRequestBody>, JsonRejection>,
+) -> Result<Json<DisbandCircleResponseBody>, ApiError> {
+    let Json(body) = body?;
+    let input = body.into_to_input(path.id, metadata);
+    match state.command_handler.disband_circle(input).await {
+        Ok(output) => Ok(Json(DisbandCircleResponseBody::from(output))),
+        Err(e) => {
+            tracing::error!("error: {:?}", e);
+            Err(e.into())
+        }
+    }
+}
+
+// join
+#[derive(Debug, Deserialize)]
+pub struct CircleMembersInputParam {
+    id: String,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct JoinCircleRequestBody {
+    pub member_id: String,
+    pub member_name: String,
+    pub version: u32,
+}
+
+impl JoinCircleRequestBody {
+    pub fn into_to_input(self, id: String, metadata: EventMetadata) -> join_circle::Input {
+        join_circle::Input {
+            circle_id: id,
+            member_id: self.member_id,
+            member_name: self.member_name,
+            version: self.version,
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct JoinCircleResponseBody {
+    pub circle_id: String,
+    pub member_id: String,
+}
+
+impl std::convert::From<join_circle::Output> for JoinCircleResponseBody {
+    fn from(
+        join_circle::Output {
+            circle_id,
+            member_id,
+        }: join_circle::Output,
+    ) -> Self {
+        JoinCircleResponseBody {
+            circle_id,
+            member_id,
+        }
+    }
+}
+
+pub async fn handle_join_circle(
+    State(state): State<AppState>,
+    Path(path): Path<CircleMembersInputParam>,
+    RequestMetadata(metadata): RequestMetadata,
+    body: Result<Json<JoinCircleRequestBody>, JsonRejection>,
+) -> Result<Json<JoinCircleResponseBody>, ApiError> {
+    let Json(body) = body?;
+    let input = body.into_to_input(path.id, metadata);
+    match state.command_handler.join_circle(input).await {
+        Ok(output) => Ok(Json(JoinCircleResponseBody::from(output))),
+        Err(e) => {
+            tracing::error!("error: {:?}", e);
+            Err(e.into())
+        }
+    }
+}
+
+// leave
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct LeaveCircleRequestBody {
+    pub member_id: String,
+    pub version: u32,
+}
+
+impl LeaveCircleRequestBody {
+    pub fn into_to_input(self, id: String, metadata: EventMetadata) -> leave_circle::Input {
+        leave_circle::Input {
+            circle_id: id,
+            member_id: self.member_id,
+            version: self.version,
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct LeaveCircleResponseBody {
+    pub circle_id: String,
+}
+
+impl std::convert::From<leave_circle::Output> for LeaveCircleResponseBody {
+    fn from(output: leave_circle::Output) -> Self {
+        LeaveCircleResponseBody {
+            circle_id: output.circle_id,
+        }
+    }
+}
+
+pub async fn handle_leave_circle(
+    State(state): State<AppState>,
+    Path(path): Path<CircleMembersInputParam>,
+    RequestMetadata(metadata): RequestMetadata,
+    body: Result<Json<LeaveCircleRequestBody>, JsonRejection>,
+) -> Result<Json<LeaveCircleResponseBody>, ApiError> {
+    let Json(body) = body?;
+    let input = body.into_to_input(path.id, metadata);
+    match state.command_handler.leave_circle(input).await {
+        Ok(output) => Ok(Json(LeaveCircleResponseBody::from(output))),
         Err(e) => {
             tracing::error!("error: {:?}", e);
-            match e {
-                update_circle::Error::InvalidInput => Err(StatusCode::BAD_REQUEST),
-                update_circle::Error::Duplicate => Err(StatusCode::BAD_REQUEST),
-                update_circle::Error::Circle => Err(StatusCode::INTERNAL_SERVER_ERROR),
-                update_circle::Error::VersionMismatch => Err(StatusCode::CONFLICT),
+            Err(e.into())
+        }
+    }
+}
+
+// transfer ownership
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct TransferOwnershipRequestBody {
+    pub new_owner_id: String,
+    pub version: u32,
+}
+
+impl TransferOwnershipRequestBody {
+    pub fn into_to_input(self, id: String, metadata: EventMetadata) -> transfer_ownership::Input {
+        transfer_ownership::Input {
+            circle_id: id,
+            new_owner_id: self.new_owner_id,
+            version: self.version,
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct TransferOwnershipResponseBody {
+    pub circle_id: String,
+}
+
+impl std::convert::From<transfer_ownership::Output> for TransferOwnershipResponseBody {
+    fn from(output: transfer_ownership::Output) -> Self {
+        TransferOwnershipResponseBody {
+            circle_id: output.circle_id,
+        }
+    }
+}
+
+pub async fn handle_transfer_ownership(
+    State(state): State<AppState>,
+    Path(path): Path<CircleMembersInputParam>,
+    RequestMetadata(metadata): RequestMetadata,
+    body: Result<Json<TransferOwnershipRequestBody>, JsonRejection>,
+) -> Result<Json<TransferOwnershipResponseBody>, ApiError> {
+    let Json(body) = body?;
+    let input = body.into_to_input(path.id, metadata);
+    match state.command_handler.transfer_ownership(input).await {
+        Ok(output) => Ok(Json(TransferOwnershipResponseBody::from(output))),
+        Err(e) => {
+            tracing::error!("error: {:?}", e);
+            Err(e.into())
+        }
+    }
+}
+
+// projection rebuild
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct ProjectionRebuildResponseBody {
+    pub state: String,
+    pub total: u64,
+    pub processed: u64,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+}
+
+impl std::convert::From<RebuildProgress> for ProjectionRebuildResponseBody {
+    fn from(progress: RebuildProgress) -> Self {
+        ProjectionRebuildResponseBody {
+            state: progress.state.as_str().to_string(),
+            total: progress.total,
+            processed: progress.processed,
+            started_at: progress.started_at.map(|at| at.and_utc().to_rfc3339()),
+            finished_at: progress.finished_at.map(|at| at.and_utc().to_rfc3339()),
+            error: progress.error,
+        }
+    }
+}
+
+pub async fn handle_start_projection_rebuild(
+    _: Admin,
+    State(state): State<AppState>,
+) -> Result<(StatusCode, Json<ProjectionRebuildResponseBody>), ApiError> {
+    match state.projection_rebuilder.start().await {
+        Ok(()) => {
+            let progress = state.projection_rebuilder.progress().await;
+            Ok((
+                StatusCode::ACCEPTED,
+                Json(ProjectionRebuildResponseBody::from(progress)),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("error: {:?}", e);
+            if e.is::<RebuildInProgress>() {
+                Err(ApiError::new(StatusCode::CONFLICT, "rebuild_in_progress"))
+            } else {
+                Err(ApiError::internal())
             }
         }
     }
 }
+
+pub async fn handle_fetch_projection_rebuild(
+    _: Admin,
+    State(state): State<AppState>,
+) -> Json<ProjectionRebuildResponseBody> {
+    let progress = state.projection_rebuilder.progress().await;
+    Json(ProjectionRebuildResponseBody::from(progress))
+}
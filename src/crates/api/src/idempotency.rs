@@ -0,0 +1,247 @@
+//! `Idempotency-Key` support for command endpoints.
+//!
+//! The first request with a key runs the command and records its response; retries
+//! with the same key and payload get that response back without running it again.
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::FromRequestParts,
+    http::{
+        header::{CONTENT_TYPE, ETAG},
+        request::Parts,
+        HeaderName, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use domain::interface::command::idempotency_store_interface::{
+    IdempotencyClaim, IdempotencyStoreInterface, StoredResponse, CLAIM_LEASE,
+};
+use sha2::{Digest, Sha256};
+
+use crate::error::ApiError;
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+/// Set on responses replayed from the idempotency store.
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+const MAX_KEY_LENGTH: usize = 255;
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+const RECORDED_HEADERS: [HeaderName; 2] = [CONTENT_TYPE, ETAG];
+
+/// Longest a command may run while it holds an idempotency claim. It is cut off well
+/// within the [`CLAIM_LEASE`], so a retry never takes over the claim of a request that
+/// is still running.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const _: () = assert!(REQUEST_TIMEOUT.as_secs() * 2 <= CLAIM_LEASE.as_secs());
+
+/// `Idempotency-Key` request header.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IdempotencyKey(pub Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for IdempotencyKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(IDEMPOTENCY_KEY) else {
+            return Ok(IdempotencyKey(None));
+        };
+        match value.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => {
+                Ok(IdempotencyKey(Some(key.to_string())))
+            }
+            _ => Err(ApiError::invalid_input(
+                "Idempotency-Key",
+                "must be 1 to 255 visible ASCII characters",
+            )),
+        }
+    }
+}
+
+impl IdempotencyKey {
+    /// Runs `handler` at most once per key within `scope`.
+    ///
+    /// `request` identifies the payload; reusing a key with a different payload is
+    /// rejected with 422. Server errors are not recorded so that the client can retry.
+    /// A command still running after [`REQUEST_TIMEOUT`] is dropped with 503, and its
+    /// claim is left to expire.
+    pub async fn run(
+        self,
+        store: Arc<dyn IdempotencyStoreInterface + Send + Sync>,
+        scope: &str,
+        request: &[u8],
+        handler: impl Future<Output = Response>,
+    ) -> Result<Response, ApiError> {
+        self.run_within(REQUEST_TIMEOUT, store, scope, request, handler)
+            .await
+    }
+
+    async fn run_within(
+        self,
+        timeout: Duration,
+        store: Arc<dyn IdempotencyStoreInterface + Send + Sync>,
+        scope: &str,
+        request: &[u8],
+        handler: impl Future<Output = Response>,
+    ) -> Result<Response, ApiError> {
+        let Some(key) = self.0 else {
+            return Ok(handler.await);
+        };
+
+        let request_hash = Sha256::digest(request)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        match store.claim(scope, &key, &request_hash).await? {
+            IdempotencyClaim::Acquired => {}
+            IdempotencyClaim::Completed(response) => return Ok(replay(response)),
+            IdempotencyClaim::InProgress => {
+                return Err(
+                    ApiError::new(StatusCode::CONFLICT, "idempotency_key_in_use").with_detail(
+                        "a request with this Idempotency-Key is still being processed",
+                    ),
+                )
+            }
+            IdempotencyClaim::Mismatch => {
+                return Err(ApiError::new(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "idempotency_key_reused",
+                )
+                .with_detail("the Idempotency-Key was already used for a different request"))
+            }
+        }
+
+        // a command dropped part way may still have committed, so its claim is not
+        // released but left to expire with the lease
+        let Ok(response) = tokio::time::timeout(timeout, handler).await else {
+            tracing::error!("Request with Idempotency-Key {} timed out", key);
+            return Err(
+                ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "request_timeout").with_detail(
+                    "the request took too long; retry it with the same Idempotency-Key",
+                ),
+            );
+        };
+        if response.status().is_server_error() {
+            if let Err(e) = store.release(scope, &key).await {
+                tracing::error!("error: {:?}", e);
+            }
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, MAX_RESPONSE_BYTES).await.map_err(|e| {
+            tracing::error!("error: {:?}", e);
+            ApiError::internal()
+        })?;
+        let stored = StoredResponse {
+            status: parts.status.as_u16(),
+            headers: RECORDED_HEADERS
+                .iter()
+                .filter_map(|name| {
+                    let value = parts.headers.get(name)?.to_str().ok()?;
+                    Some((name.to_string(), value.to_string()))
+                })
+                .collect(),
+            body: body.to_vec(),
+        };
+        // the command already ran; an unrecorded key expires and lets a retry run it again
+        if let Err(e) = store.complete(scope, &key, &stored).await {
+            tracing::error!("error: {:?}", e);
+        }
+        Ok(Response::from_parts(parts, Body::from(body)))
+    }
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    headers.remove(CONTENT_TYPE);
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use domain::interface::command::idempotency_store_interface::MockIdempotencyStoreInterface;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_completed_key_replays_without_running_handler() -> anyhow::Result<()> {
+        let mut store = MockIdempotencyStoreInterface::new();
+        store.expect_claim().returning(|_, _, _| {
+            Ok(IdempotencyClaim::Completed(StoredResponse {
+                status: 200,
+                headers: vec![("content-type".to_string(), "application/json".to_string())],
+                body: b"{\"circle_id\":\"1\"}".to_vec(),
+            }))
+        });
+        store.expect_complete().never();
+
+        let key = IdempotencyKey(Some("key".to_string()));
+        let response = key
+            .run(Arc::new(store), "POST /circle", b"{}", async {
+                StatusCode::CREATED.into_response()
+            })
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("{:?}", e)))?;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(IDEMPOTENT_REPLAYED));
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(&body[..], b"{\"circle_id\":\"1\"}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_command_keeps_its_claim() -> anyhow::Result<()> {
+        let mut store = MockIdempotencyStoreInterface::new();
+        store
+            .expect_claim()
+            .returning(|_, _, _| Ok(IdempotencyClaim::Acquired));
+        store.expect_complete().never();
+        store.expect_release().never();
+
+        let key = IdempotencyKey(Some("key".to_string()));
+        let result = key
+            .run_within(
+                Duration::from_millis(10),
+                Arc::new(store),
+                "POST /circle",
+                b"{}",
+                std::future::pending(),
+            )
+            .await;
+
+        assert_eq!(result.map_err(|e| e.code()).err(), Some("request_timeout"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reused_key_with_other_payload_is_unprocessable() -> anyhow::Result<()> {
+        let mut store = MockIdempotencyStoreInterface::new();
+        store
+            .expect_claim()
+            .returning(|_, _, _| Ok(IdempotencyClaim::Mismatch));
+
+        let key = IdempotencyKey(Some("key".to_string()));
+        let result = key
+            .run(Arc::new(store), "POST /circle", b"{}", async {
+                StatusCode::OK.into_response()
+            })
+            .await;
+
+        assert_eq!(
+            result.map_err(|e| e.status()).err(),
+            Some(StatusCode::UNPROCESSABLE_ENTITY)
+        );
+        Ok(())
+    }
+}
@@ -1,3 +1,8 @@
+pub mod admin;
 pub mod app_state;
+pub mod error;
 pub mod handler;
+pub mod idempotency;
+pub mod metadata;
+pub mod precondition;
 pub mod router;
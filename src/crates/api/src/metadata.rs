@@ -0,0 +1,200 @@
+//! Request context recorded as [`EventMetadata`] on the events a command produces.
+//!
+//! There is no authentication yet, so the actor is whatever the client sends in
+//! `X-Actor-Id`; treat it as a hint for debugging, not as proof of identity.
+//!
+//! The client IP is the peer address. `X-Forwarded-For` is only believed when the peer
+//! is one of the [`TrustedProxies`], as any client can send it.
+
+use std::{
+    convert::Infallible,
+    net::{AddrParseError, IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{header::USER_AGENT, request::Parts, HeaderName},
+};
+use domain::aggregate::value_object::event_metadata::EventMetadata;
+
+pub const X_ACTOR_ID: HeaderName = HeaderName::from_static("x-actor-id");
+pub const X_CORRELATION_ID: HeaderName = HeaderName::from_static("x-correlation-id");
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+pub const X_CAUSATION_ID: HeaderName = HeaderName::from_static("x-causation-id");
+pub const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+const MAX_VALUE_LENGTH: usize = 255;
+
+/// Metadata of the events produced by the request. Headers that are missing, not
+/// visible ASCII or longer than 255 characters are left out rather than rejected.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RequestMetadata(pub EventMetadata);
+
+/// Addresses of the proxies in front of the server, whose `X-Forwarded-For` is
+/// believed. Parsed from a comma-separated list such as `10.0.0.2, 10.0.0.3`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TrustedProxies(pub Vec<IpAddr>);
+
+impl TrustedProxies {
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.0.contains(ip)
+    }
+
+    /// The client of a request received from `peer`: the hop of `forwarded` closest to
+    /// the server that is not a trusted proxy, or `peer` when it is not trusted itself.
+    fn client_ip(&self, peer: IpAddr, forwarded: Option<&str>) -> String {
+        let Some(forwarded) = forwarded.filter(|_| self.contains(&peer)) else {
+            return peer.to_string();
+        };
+        let hops: Vec<&str> = forwarded.split(',').map(str::trim).collect();
+        hops.iter()
+            .rev()
+            .find(|hop| hop.parse().map_or(true, |ip| !self.contains(&ip)))
+            // every hop was a trusted proxy, so the first one is the client
+            .or(hops.first())
+            .map_or_else(|| peer.to_string(), |hop| hop.to_string())
+    }
+}
+
+impl FromStr for TrustedProxies {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty())
+            .map(IpAddr::from_str)
+            .collect::<Result<_, _>>()
+            .map(TrustedProxies)
+    }
+}
+
+fn header(parts: &Parts, name: &HeaderName) -> Option<String> {
+    let value = parts.headers.get(name)?.to_str().ok()?.trim();
+    (!value.is_empty() && value.len() <= MAX_VALUE_LENGTH).then(|| value.to_string())
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestMetadata
+where
+    TrustedProxies: FromRef<S>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let forwarded = header(parts, &X_FORWARDED_FOR);
+        let client_ip =
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| {
+                    TrustedProxies::from_ref(state).client_ip(addr.ip(), forwarded.as_deref())
+                });
+        Ok(RequestMetadata(EventMetadata {
+            actor_id: header(parts, &X_ACTOR_ID),
+            correlation_id: header(parts, &X_CORRELATION_ID)
+                .or_else(|| header(parts, &X_REQUEST_ID)),
+            causation_id: header(parts, &X_CAUSATION_ID),
+            client_ip,
+            user_agent: header(parts, &USER_AGENT),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    const PROXY: [u8; 4] = [10, 0, 0, 2];
+
+    fn proxies() -> anyhow::Result<TrustedProxies> {
+        Ok("10.0.0.1, 10.0.0.2".parse()?)
+    }
+
+    fn with_peer(mut parts: Parts, peer: [u8; 4]) -> Parts {
+        parts
+            .extensions
+            .insert(ConnectInfo(SocketAddr::from((peer, 40000))));
+        parts
+    }
+
+    #[tokio::test]
+    async fn test_metadata_from_headers() -> anyhow::Result<()> {
+        let (parts, _) = Request::builder()
+            .header("x-actor-id", "member-1")
+            .header("x-request-id", "request-1")
+            .header("x-causation-id", "event-1")
+            .header("x-forwarded-for", "203.0.113.7, 10.0.0.1")
+            .header("user-agent", "curl/8.0")
+            .body(())?
+            .into_parts();
+        let mut parts = with_peer(parts, PROXY);
+        let Ok(RequestMetadata(metadata)) =
+            RequestMetadata::from_request_parts(&mut parts, &proxies()?).await;
+        assert_eq!(
+            metadata,
+            EventMetadata {
+                actor_id: Some("member-1".to_string()),
+                correlation_id: Some("request-1".to_string()),
+                causation_id: Some("event-1".to_string()),
+                client_ip: Some("203.0.113.7".to_string()),
+                user_agent: Some("curl/8.0".to_string()),
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_metadata_falls_back_to_peer_address() -> anyhow::Result<()> {
+        let (parts, _) = Request::builder()
+            .header("x-actor-id", "a".repeat(256))
+            .body(())?
+            .into_parts();
+        let mut parts = with_peer(parts, [192, 0, 2, 1]);
+        let Ok(RequestMetadata(metadata)) =
+            RequestMetadata::from_request_parts(&mut parts, &proxies()?).await;
+        assert_eq!(metadata.actor_id, None);
+        assert_eq!(metadata.client_ip, Some("192.0.2.1".to_string()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_for_is_ignored_from_untrusted_peers() -> anyhow::Result<()> {
+        let (parts, _) = Request::builder()
+            .header("x-forwarded-for", "203.0.113.7")
+            .body(())?
+            .into_parts();
+        let mut parts = with_peer(parts, [192, 0, 2, 1]);
+        let Ok(RequestMetadata(metadata)) =
+            RequestMetadata::from_request_parts(&mut parts, &proxies()?).await;
+        assert_eq!(metadata.client_ip, Some("192.0.2.1".to_string()));
+
+        // nor believed without knowing the peer
+        let (mut parts, _) = Request::builder()
+            .header("x-forwarded-for", "203.0.113.7")
+            .body(())?
+            .into_parts();
+        let Ok(RequestMetadata(metadata)) =
+            RequestMetadata::from_request_parts(&mut parts, &proxies()?).await;
+        assert_eq!(metadata.client_ip, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_is_the_last_untrusted_hop() -> anyhow::Result<()> {
+        let proxies = proxies()?;
+        let peer = IpAddr::from(PROXY);
+        // the client prepended a hop of its own choosing
+        assert_eq!(
+            proxies.client_ip(peer, Some("198.51.100.9, 203.0.113.7, 10.0.0.1")),
+            "203.0.113.7"
+        );
+        assert_eq!(proxies.client_ip(peer, Some("10.0.0.1")), "10.0.0.1");
+        assert_eq!(proxies.client_ip(peer, None), "10.0.0.2");
+        assert!("10.0.0.1, proxy".parse::<TrustedProxies>().is_err());
+        assert_eq!("".parse::<TrustedProxies>()?, TrustedProxies::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Conditional requests (RFC 9110 §13) keyed on the aggregate [`Version`].
+//!
+//! A circle's entity tag is its version as a strong ETag, e.g. `"3"`.
+
+use std::convert::Infallible;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{
+        header::{IF_MATCH, IF_NONE_MATCH},
+        request::Parts,
+        HeaderValue,
+    },
+};
+use domain::aggregate::value_object::version::Version;
+
+use crate::error::ApiError;
+
+pub fn entity_tag(version: Version) -> HeaderValue {
+    // digits and quotes are always valid header characters
+    HeaderValue::from_str(&format!("\"{}\"", u32::from(version)))
+        .unwrap_or_else(|_| HeaderValue::from_static("\"\""))
+}
+
+fn parse_entity_tag(tag: &str) -> Option<(Version, bool)> {
+    let tag = tag.trim();
+    let (tag, weak) = match tag.strip_prefix("W/") {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+    let version = tag
+        .strip_prefix('"')?
+        .strip_suffix('"')?
+        .parse::<u32>()
+        .ok()?;
+    Some((Version::from(version), weak))
+}
+
+/// `If-Match` request header. Holds the version the client expects the circle to be at.
+///
+/// Only a single strong entity tag issued by this API is accepted; anything else is
+/// rejected with 400 because it cannot be turned into a concurrency token.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IfMatch(pub Option<Version>);
+
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(IF_MATCH) else {
+            return Ok(IfMatch(None));
+        };
+        match value.to_str().ok().and_then(parse_entity_tag) {
+            Some((version, false)) => Ok(IfMatch(Some(version))),
+            _ => Err(ApiError::invalid_input(
+                "If-Match",
+                "must be a single strong ETag returned by this API",
+            )),
+        }
+    }
+}
+
+/// `If-None-Match` request header, compared with the weak comparison function.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IfNoneMatch(Option<String>);
+
+impl IfNoneMatch {
+    /// Whether the client already holds the representation at `version`.
+    pub fn matches(&self, version: Version) -> bool {
+        let Some(tags) = &self.0 else {
+            return false;
+        };
+        tags.split(',').any(|tag| {
+            tag.trim() == "*" || parse_entity_tag(tag).is_some_and(|(tagged, _)| tagged == version)
+        })
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for IfNoneMatch {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let tags = parts
+            .headers
+            .get(IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok(IfNoneMatch(tags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    fn parts(header: &str, value: &str) -> anyhow::Result<Parts> {
+        let (parts, _) = Request::builder()
+            .header(header, value)
+            .body(())?
+            .into_parts();
+        Ok(parts)
+    }
+
+    #[test]
+    fn test_entity_tag() {
+        assert_eq!(entity_tag(Version::from(3)), "\"3\"");
+    }
+
+    #[tokio::test]
+    async fn test_if_match_accepts_single_strong_tag() -> anyhow::Result<()> {
+        let if_match = IfMatch::from_request_parts(&mut parts("if-match", "\"3\"")?, &()).await;
+        assert_eq!(if_match.ok(), Some(IfMatch(Some(Version::from(3)))));
+        for value in ["W/\"3\"", "*", "\"2\", \"3\""] {
+            let if_match = IfMatch::from_request_parts(&mut parts("if-match", value)?, &()).await;
+            assert!(if_match.is_err());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_if_none_match_uses_weak_comparison() -> anyhow::Result<()> {
+        let mut parts = parts("if-none-match", "\"2\", W/\"3\"")?;
+        let Ok(if_none_match) = IfNoneMatch::from_request_parts(&mut parts, &()).await;
+        assert!(if_none_match.matches(Version::from(3)));
+        assert!(!if_none_match.matches(Version::from(4)));
+        assert!(IfNoneMatch(Some("*".to_string())).matches(Version::from(4)));
+        assert!(!IfNoneMatch::default().matches(Version::from(1)));
+        Ok(())
+    }
+}
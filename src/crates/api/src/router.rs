@@ -1,20 +1,36 @@
 use crate::{
     app_state::AppState,
     handler::{
-        handle_create_circle, handle_fetch_circle, handle_get_version, handle_update_circle,
+        handle_create_circle, handle_disband_circle, handle_fetch_circle,
+        handle_fetch_circle_events, handle_fetch_projection_rebuild, handle_get_version,
+        handle_join_circle, handle_leave_circle, handle_list_circles,
+        handle_start_projection_rebuild, handle_transfer_ownership, handle_update_circle,
     },
 };
 
 use axum::{
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/version", get(handle_get_version))
-        .route("/circle", get(handle_fetch_circle))
+        .route("/circle", get(handle_list_circles))
         .route("/circle/{id}", get(handle_fetch_circle))
         .route("/circle", post(handle_create_circle))
         .route("/circle/{id}", put(handle_update_circle))
+        .route("/circle/{id}", delete(handle_disband_circle))
+        .route("/circle/{id}/events", get(handle_fetch_circle_events))
+        .route("/circle/{id}/members", post(handle_join_circle))
+        .route("/circle/{id}/members", delete(handle_leave_circle))
+        .route("/circle/{id}/owner", put(handle_transfer_ownership))
+        .route(
+            "/admin/projection/rebuild",
+            get(handle_fetch_projection_rebuild),
+        )
+        .route(
+            "/admin/projection/rebuild",
+            post(handle_start_projection_rebuild),
+        )
 }
@@ -1,2 +1,6 @@
 pub mod create_circle;
+pub mod disband_circle;
+pub mod join_circle;
+pub mod leave_circle;
+pub mod transfer_ownership;
 pub mod update_circle;
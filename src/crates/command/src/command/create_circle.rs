@@ -1,23 +1,36 @@
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 
 use serde::Deserialize;
 
 use domain::{
-    aggregate::circle::Circle,
-    interface::command::circle_repository_interface::CircleRepositoryInterface,
+    aggregate::{
+        circle::{error::CircleError, member::Member, Circle},
+        event_sourced::DomainEvent,
+        value_object::{event_metadata::EventMetadata, member_id::MemberId},
+    },
+    interface::command::{
+        circle_duplicate_checker_interface::CircleDuplicateCheckerInterface,
+        circle_repository_interface::{CircleRepositoryInterface, DuplicateCircleName},
+    },
 };
 
 #[derive(Debug)]
 pub enum Error {
     Circle,
     Duplicate,
-    InvalidInput,
+    InvalidCapacity,
+    InvalidOwnerId,
+    InvalidOwnerName,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Input {
     pub circle_name: String,
     pub capacity: i16,
+    pub owner_id: String,
+    pub owner_name: String,
+    #[serde(default)]
+    pub metadata: EventMetadata,
 }
 
 #[derive(Debug)]
@@ -27,19 +40,43 @@ pub struct Output {
 
 pub async fn handle(
     circle_repository: Arc<dyn CircleRepositoryInterface + Send + Sync>,
+    circle_duplicate_checker: Arc<dyn CircleDuplicateCheckerInterface + Send + Sync>,
     Input {
         circle_name,
         capacity,
+        owner_id,
+        owner_name,
+        metadata,
     }: Input,
 ) -> Result<Output, Error> {
-    let (circle, event) = Circle::create(circle_name, capacity).map_err(|_| Error::InvalidInput)?;
+    let owner_id = MemberId::from_str(owner_id.as_str()).map_err(|_| Error::InvalidOwnerId)?;
+    let owner = Member::new(owner_id, owner_name).map_err(|_| Error::InvalidOwnerName)?;
+    let (circle, event) = Circle::create(circle_name, capacity, owner).map_err(|e| {
+        match e.downcast_ref::<CircleError>() {
+            Some(CircleError::InvalidCapacity) => Error::InvalidCapacity,
+            _ => Error::Circle,
+        }
+    })?;
+
+    circle_duplicate_checker
+        .check_circle_duplicate(&circle)
+        .await
+        .map_err(map_duplicate_error)?;
 
     circle_repository
-        .store(None, vec![event])
+        .store(None, vec![event.with_metadata(metadata)])
         .await
-        .map_err(|_| Error::Circle)?;
+        .map_err(map_duplicate_error)?;
 
     Ok(Output {
         circle_id: circle.id.to_string(),
     })
 }
+
+fn map_duplicate_error(e: anyhow::Error) -> Error {
+    if e.is::<DuplicateCircleName>() {
+        Error::Duplicate
+    } else {
+        Error::Circle
+    }
+}
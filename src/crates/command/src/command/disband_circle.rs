@@ -0,0 +1,90 @@
+use std::{str::FromStr, sync::Arc};
+
+use serde::Deserialize;
+
+use domain::{
+    aggregate::{
+        circle::error::CircleError,
+        event_sourced::DomainEvent,
+        value_object::{circle_id::CircleId, event_metadata::EventMetadata, version::Version},
+    },
+    interface::command::circle_repository_interface::{
+        CircleNotFound, CircleRepositoryInterface, VersionConflict,
+    },
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Circle,
+    Disbanded,
+    InvalidInput,
+    NotFound,
+    VersionMismatch,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Input {
+    pub circle_id: String,
+    pub version: u32,
+    #[serde(default)]
+    pub metadata: EventMetadata,
+}
+
+#[derive(Debug)]
+pub struct Output {
+    pub circle_id: String,
+}
+
+pub async fn handle(
+    circle_repository: Arc<dyn CircleRepositoryInterface + Send + Sync>,
+    Input {
+        circle_id,
+        version,
+        metadata,
+    }: Input,
+) -> Result<Output, Error> {
+    // check input
+    let circle_id = CircleId::from_str(circle_id.as_str()).map_err(|_| Error::InvalidInput)?;
+    let version = Version::from(version);
+
+    // find the circle
+    let circle = circle_repository
+        .find_by_id(&circle_id)
+        .await
+        .map_err(|e| {
+            if e.is::<CircleNotFound>() {
+                Error::NotFound
+            } else {
+                Error::Circle
+            }
+        })?;
+
+    // check version
+    if circle.version != version {
+        return Err(Error::VersionMismatch);
+    }
+
+    // disband
+    let (circle, event) = circle
+        .disband()
+        .map_err(|e| match e.downcast_ref::<CircleError>() {
+            Some(CircleError::Disbanded) => Error::Disbanded,
+            _ => Error::InvalidInput,
+        })?;
+
+    // store
+    circle_repository
+        .store(Some(version), vec![event.with_metadata(metadata)])
+        .await
+        .map_err(|e| {
+            if e.is::<VersionConflict>() {
+                Error::VersionMismatch
+            } else {
+                Error::Circle
+            }
+        })?;
+
+    Ok(Output {
+        circle_id: circle.id.to_string(),
+    })
+}
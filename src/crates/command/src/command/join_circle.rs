@@ -0,0 +1,151 @@
+use std::{str::FromStr, sync::Arc};
+
+use serde::Deserialize;
+
+use domain::{
+    aggregate::{
+        circle::{error::CircleError, member::Member},
+        event_sourced::DomainEvent,
+        value_object::{
+            circle_id::CircleId, event_metadata::EventMetadata, member_id::MemberId,
+            version::Version,
+        },
+    },
+    interface::command::circle_repository_interface::{
+        CircleNotFound, CircleRepositoryInterface, VersionConflict,
+    },
+};
+
+#[derive(Debug)]
+pub enum Error {
+    AlreadyMember,
+    Circle,
+    CircleFull,
+    Disbanded,
+    InvalidInput,
+    NotFound,
+    VersionMismatch,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Input {
+    pub circle_id: String,
+    pub member_id: String,
+    pub member_name: String,
+    pub version: u32,
+    #[serde(default)]
+    pub metadata: EventMetadata,
+}
+
+#[derive(Debug)]
+pub struct Output {
+    pub circle_id: String,
+    pub member_id: String,
+}
+
+pub async fn handle(
+    circle_repository: Arc<dyn CircleRepositoryInterface + Send + Sync>,
+    Input {
+        circle_id,
+        member_id,
+        member_name,
+        version,
+        metadata,
+    }: Input,
+) -> Result<Output, Error> {
+    // check input
+    let circle_id = CircleId::from_str(circle_id.as_str()).map_err(|_| Error::InvalidInput)?;
+    let member_id = MemberId::from_str(member_id.as_str()).map_err(|_| Error::InvalidInput)?;
+    let member = Member::new(member_id, member_name).map_err(|_| Error::InvalidInput)?;
+    let version = Version::from(version);
+
+    // find the circle
+    let circle = circle_repository
+        .find_by_id(&circle_id)
+        .await
+        .map_err(|e| {
+            if e.is::<CircleNotFound>() {
+                Error::NotFound
+            } else {
+                Error::Circle
+            }
+        })?;
+
+    // check version
+    if circle.version != version {
+        return Err(Error::VersionMismatch);
+    }
+
+    // join
+    let member_id = member.id.to_string();
+    let (circle, event) =
+        circle
+            .join(member)
+            .map_err(|e| match e.downcast_ref::<CircleError>() {
+                Some(CircleError::AlreadyMember) => Error::AlreadyMember,
+                Some(CircleError::CircleFull) => Error::CircleFull,
+                Some(CircleError::Disbanded) => Error::Disbanded,
+                _ => Error::InvalidInput,
+            })?;
+
+    // store
+    circle_repository
+        .store(Some(version), vec![event.with_metadata(metadata)])
+        .await
+        .map_err(|e| {
+            if e.is::<VersionConflict>() {
+                Error::VersionMismatch
+            } else {
+                Error::Circle
+            }
+        })?;
+
+    Ok(Output {
+        circle_id: circle.id.to_string(),
+        member_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use domain::{
+        aggregate::circle::Circle,
+        interface::command::circle_repository_interface::MockCircleRepositoryInterface,
+    };
+
+    use super::*;
+
+    fn owner() -> anyhow::Result<Member> {
+        Member::new(MemberId::from_str("owner")?, "owner".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_join_full_circle() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 3, owner()?)?;
+        let mut circle = circle;
+        for id in ["1", "2"] {
+            let member = Member::new(MemberId::from_str(id)?, format!("member {}", id))?;
+            (circle, _) = circle.join(member)?;
+        }
+        let circle_id = circle.id.to_string();
+        let mut repository = MockCircleRepositoryInterface::new();
+        repository
+            .expect_find_by_id()
+            .returning(move |_| Ok(circle.clone()));
+        repository.expect_store().never();
+
+        let result = handle(
+            Arc::new(repository),
+            Input {
+                circle_id,
+                member_id: "3".to_string(),
+                member_name: "member 3".to_string(),
+                version: 3,
+                metadata: EventMetadata::default(),
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::CircleFull)));
+        Ok(())
+    }
+}
@@ -3,15 +3,28 @@ use std::{str::FromStr, sync::Arc};
 use serde::Deserialize;
 
 use domain::{
-    aggregate::value_object::{circle_id::CircleId, version::Version},
-    interface::command::circle_repository_interface::CircleRepositoryInterface,
+    aggregate::{
+        circle::error::CircleError,
+        event_sourced::DomainEvent,
+        value_object::{circle_id::CircleId, event_metadata::EventMetadata, version::Version},
+    },
+    interface::command::{
+        circle_duplicate_checker_interface::CircleDuplicateCheckerInterface,
+        circle_repository_interface::{
+            CircleNotFound, CircleRepositoryInterface, DuplicateCircleName, VersionConflict,
+        },
+    },
 };
 
 #[derive(Debug)]
 pub enum Error {
+    CapacityBelowMembers,
     Circle,
+    Disbanded,
     Duplicate,
+    InvalidCapacity,
     InvalidInput,
+    NotFound,
     VersionMismatch,
 }
 
@@ -21,20 +34,26 @@ pub struct Input {
     pub circle_name: Option<String>,
     pub capacity: Option<i16>,
     pub version: u32,
+    #[serde(default)]
+    pub metadata: EventMetadata,
 }
 
 #[derive(Debug)]
 pub struct Output {
     pub circle_id: String,
+    /// Version of the circle after the update.
+    pub version: u32,
 }
 
 pub async fn handle(
     circle_repository: Arc<dyn CircleRepositoryInterface + Send + Sync>,
+    circle_duplicate_checker: Arc<dyn CircleDuplicateCheckerInterface + Send + Sync>,
     Input {
         circle_id,
         circle_name,
         capacity,
         version,
+        metadata,
     }: Input,
 ) -> Result<Output, Error> {
     // check input
@@ -45,25 +64,180 @@ pub async fn handle(
     let circle = circle_repository
         .find_by_id(&circle_id)
         .await
-        .map_err(|_| Error::Circle)?;
-
-    // update
-    let (circle, event) = circle
-        .update(circle_name, capacity)
-        .map_err(|_| Error::InvalidInput)?;
+        .map_err(|e| {
+            if e.is::<CircleNotFound>() {
+                Error::NotFound
+            } else {
+                Error::Circle
+            }
+        })?;
 
     // check version
     if circle.version != version {
         return Err(Error::VersionMismatch);
     }
 
+    // update
+    let renamed = circle_name.is_some();
+    let (circle, event) = circle.update(circle_name, capacity).map_err(|e| {
+        match e.downcast_ref::<CircleError>() {
+            Some(CircleError::CapacityBelowMembers) => Error::CapacityBelowMembers,
+            Some(CircleError::Disbanded) => Error::Disbanded,
+            Some(CircleError::InvalidCapacity) => Error::InvalidCapacity,
+            _ => Error::InvalidInput,
+        }
+    })?;
+
+    // check duplicate
+    if renamed {
+        circle_duplicate_checker
+            .check_circle_duplicate(&circle)
+            .await
+            .map_err(|e| {
+                if e.is::<DuplicateCircleName>() {
+                    Error::Duplicate
+                } else {
+                    Error::Circle
+                }
+            })?;
+    }
+
     // store
     circle_repository
-        .store(Some(version), vec![event])
+        .store(Some(version), vec![event.with_metadata(metadata)])
         .await
-        .map_err(|_| Error::Circle)?;
+        .map_err(|e| {
+            if e.is::<VersionConflict>() {
+                Error::VersionMismatch
+            } else if e.is::<DuplicateCircleName>() {
+                Error::Duplicate
+            } else {
+                Error::Circle
+            }
+        })?;
 
     Ok(Output {
         circle_id: circle.id.to_string(),
+        version: u32::from(circle.version),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use domain::{
+        aggregate::{
+            circle::{member::Member, Circle},
+            value_object::member_id::MemberId,
+        },
+        interface::command::{
+            circle_duplicate_checker_interface::MockCircleDuplicateCheckerInterface,
+            circle_repository_interface::MockCircleRepositoryInterface,
+        },
+    };
+
+    use super::*;
+
+    fn owner() -> anyhow::Result<Member> {
+        Member::new(MemberId::from_str("owner")?, "owner".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_stale_version_is_rejected_before_store() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 10, owner()?)?;
+        let circle_id = circle.id.to_string();
+        let mut repository = MockCircleRepositoryInterface::new();
+        repository
+            .expect_find_by_id()
+            .returning(move |_| Ok(circle.clone()));
+        repository.expect_store().never();
+        let mut duplicate_checker = MockCircleDuplicateCheckerInterface::new();
+        duplicate_checker.expect_check_circle_duplicate().never();
+
+        let result = handle(
+            Arc::new(repository),
+            Arc::new(duplicate_checker),
+            Input {
+                circle_id,
+                circle_name: Some("Football club".to_string()),
+                capacity: None,
+                version: 2,
+                metadata: EventMetadata::default(),
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::VersionMismatch)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_append_maps_to_version_mismatch() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 10, owner()?)?;
+        let circle_id = circle.id.to_string();
+        let conflict = VersionConflict {
+            circle_id: circle.id.clone(),
+            expected: Some(Version::new()),
+            actual: Some(Version::new().next()),
+        };
+        let mut repository = MockCircleRepositoryInterface::new();
+        repository
+            .expect_find_by_id()
+            .returning(move |_| Ok(circle.clone()));
+        repository
+            .expect_store()
+            .returning(move |_, _| Err(conflict.clone().into()));
+        let mut duplicate_checker = MockCircleDuplicateCheckerInterface::new();
+        duplicate_checker
+            .expect_check_circle_duplicate()
+            .returning(|_| Ok(()));
+
+        let result = handle(
+            Arc::new(repository),
+            Arc::new(duplicate_checker),
+            Input {
+                circle_id,
+                circle_name: Some("Football club".to_string()),
+                capacity: None,
+                version: 1,
+                metadata: EventMetadata::default(),
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::VersionMismatch)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_to_taken_name_is_duplicate() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 10, owner()?)?;
+        let circle_id = circle.id.to_string();
+        let mut repository = MockCircleRepositoryInterface::new();
+        repository
+            .expect_find_by_id()
+            .returning(move |_| Ok(circle.clone()));
+        repository.expect_store().never();
+        let mut duplicate_checker = MockCircleDuplicateCheckerInterface::new();
+        duplicate_checker
+            .expect_check_circle_duplicate()
+            .returning(|circle| {
+                Err(DuplicateCircleName {
+                    name: circle.name.clone(),
+                }
+                .into())
+            });
+
+        let result = handle(
+            Arc::new(repository),
+            Arc::new(duplicate_checker),
+            Input {
+                circle_id,
+                circle_name: Some("Football club".to_string()),
+                capacity: None,
+                version: 1,
+                metadata: EventMetadata::default(),
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Duplicate)));
+        Ok(())
+    }
+}
@@ -3,26 +3,67 @@ use std::sync::Arc;
 use domain::interface::{
     command::circle_duplicate_checker_interface::HasCircleDuplicateCheckerInterface,
     command::circle_repository_interface::HasCircleRepositoryInterface,
+    command::idempotency_store_interface::HasIdempotencyStoreInterface,
 };
 
-use crate::command::{create_circle, update_circle};
+use crate::command::{
+    create_circle, disband_circle, join_circle, leave_circle, transfer_ownership, update_circle,
+};
 
 #[async_trait::async_trait]
 pub trait CommandHandler:
-    HasCircleRepositoryInterface + HasCircleDuplicateCheckerInterface
+    HasCircleRepositoryInterface + HasCircleDuplicateCheckerInterface + HasIdempotencyStoreInterface
 {
     async fn create_circle(
         &self,
         input: create_circle::Input,
     ) -> Result<create_circle::Output, create_circle::Error> {
-        create_circle::handle(self.circle_repository(), input).await
+        create_circle::handle(
+            self.circle_repository(),
+            self.circle_duplicate_checker(),
+            input,
+        )
+        .await
     }
 
     async fn update_circle(
         &self,
         input: update_circle::Input,
     ) -> Result<update_circle::Output, update_circle::Error> {
-        update_circle::handle(self.circle_repository(), input).await
+        update_circle::handle(
+            self.circle_repository(),
+            self.circle_duplicate_checker(),
+            input,
+        )
+        .await
+    }
+
+    async fn join_circle(
+        &self,
+        input: join_circle::Input,
+    ) -> Result<join_circle::Output, join_circle::Error> {
+        join_circle::handle(self.circle_repository(), input).await
+    }
+
+    async fn leave_circle(
+        &self,
+        input: leave_circle::Input,
+    ) -> Result<leave_circle::Output, leave_circle::Error> {
+        leave_circle::handle(self.circle_repository(), input).await
+    }
+
+    async fn transfer_ownership(
+        &self,
+        input: transfer_ownership::Input,
+    ) -> Result<transfer_ownership::Output, transfer_ownership::Error> {
+        transfer_ownership::handle(self.circle_repository(), input).await
+    }
+
+    async fn disband_circle(
+        &self,
+        input: disband_circle::Input,
+    ) -> Result<disband_circle::Output, disband_circle::Error> {
+        disband_circle::handle(self.circle_repository(), input).await
     }
 }
 
@@ -1,2 +1,3 @@
 pub mod circle;
+pub mod event_sourced;
 pub mod value_object;
@@ -1,33 +1,41 @@
-use super::value_object::{circle_id::CircleId, version::Version};
-use anyhow::{Error, Result};
+use super::{
+    event_sourced::Aggregate,
+    value_object::{circle_id::CircleId, member_id::MemberId, version::Version},
+};
+use anyhow::Result;
+use error::CircleError;
 use event::CircleEvent;
+use member::Member;
+use status::CircleStatus;
+pub mod error;
 pub mod event;
+pub mod member;
+pub mod status;
 
 #[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Circle {
     pub id: CircleId,
     pub name: String,
     pub capacity: i16,
+    #[serde(default)]
+    pub members: Vec<Member>,
+    #[serde(default)]
+    pub owner_id: Option<MemberId>,
+    #[serde(default)]
+    pub status: CircleStatus,
     pub version: Version,
 }
 
 impl Circle {
-    pub fn replay(events: Vec<CircleEvent>) -> Self {
-        let mut state = match events.first() {
-            Some(first_event) => Self::from_created_event(first_event.clone()),
-            None => unreachable!("No events to initialize Circle"),
-        };
-        for event in events.iter().skip(1) {
-            state.apply_event(event);
-        }
-        state
-    }
-
-    pub fn create(name: String, capacity: i16) -> Result<(Self, CircleEvent)> {
+    pub fn create(name: String, capacity: i16, owner: Member) -> Result<(Self, CircleEvent)> {
         Self::validate_capacity(capacity)?;
-        let event = CircleEvent::build(CircleId::gen(), Version::new())
-            .circle_created(name.clone(), capacity);
-        let state = Self::from_created_event(event.clone());
+        let event = CircleEvent::build(CircleId::gen(), Version::new()).circle_created(
+            name.clone(),
+            capacity,
+            owner,
+        );
+        let state = Self::initial(&event)
+            .ok_or_else(|| anyhow::Error::msg("circle_created must start a stream"))?;
         Ok((state, event))
     }
 
@@ -36,36 +44,154 @@ impl Circle {
         name: Option<String>,
         capacity: Option<i16>,
     ) -> Result<(Self, CircleEvent)> {
+        self.ensure_active()?;
         if let Some(new_capacity) = capacity {
             Self::validate_capacity(new_capacity)?;
+            if (new_capacity as usize) < self.members.len() {
+                return Err(CircleError::CapacityBelowMembers.into());
+            }
         }
-        let event = CircleEvent::build(self.id.clone(), self.version.clone())
-            .circle_updated(name, capacity);
+        let event =
+            CircleEvent::build(self.id.clone(), self.version).circle_updated(name, capacity);
         let mut state = self.clone();
-        state.apply_event(&event);
+        state.apply(&event);
+        Ok((state, event))
+    }
+
+    pub fn join(self, member: Member) -> Result<(Self, CircleEvent)> {
+        self.ensure_active()?;
+        if self.is_member(&member.id) {
+            return Err(CircleError::AlreadyMember.into());
+        }
+        if self.members.len() >= self.capacity as usize {
+            return Err(CircleError::CircleFull.into());
+        }
+        let event = CircleEvent::build(self.id.clone(), self.version).member_joined(member);
+        let mut state = self;
+        state.apply(&event);
+        Ok((state, event))
+    }
+
+    pub fn leave(self, member_id: &MemberId) -> Result<(Self, CircleEvent)> {
+        self.ensure_active()?;
+        if !self.is_member(member_id) {
+            return Err(CircleError::NotMember.into());
+        }
+        if self.is_owner(member_id) {
+            return Err(CircleError::OwnerCannotLeave.into());
+        }
+        let event =
+            CircleEvent::build(self.id.clone(), self.version).member_left(member_id.clone());
+        let mut state = self;
+        state.apply(&event);
+        Ok((state, event))
+    }
+
+    pub fn transfer_ownership(self, new_owner_id: &MemberId) -> Result<(Self, CircleEvent)> {
+        self.ensure_active()?;
+        if !self.is_member(new_owner_id) {
+            return Err(CircleError::NotMember.into());
+        }
+        if self.is_owner(new_owner_id) {
+            return Err(CircleError::AlreadyOwner.into());
+        }
+        let event = CircleEvent::build(self.id.clone(), self.version)
+            .ownership_transferred(self.owner_id.clone(), new_owner_id.clone());
+        let mut state = self;
+        state.apply(&event);
+        Ok((state, event))
+    }
+
+    pub fn disband(self) -> Result<(Self, CircleEvent)> {
+        self.ensure_active()?;
+        let event = CircleEvent::build(self.id.clone(), self.version).circle_disbanded();
+        let mut state = self;
+        state.apply(&event);
         Ok((state, event))
     }
 
-    // Private helper methods for event sourcing
+    // Getters
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_member(&self, member_id: &MemberId) -> bool {
+        self.members.iter().any(|member| &member.id == member_id)
+    }
+
+    pub fn is_owner(&self, member_id: &MemberId) -> bool {
+        self.owner_id.as_ref() == Some(member_id)
+    }
+
+    pub fn is_disbanded(&self) -> bool {
+        self.status == CircleStatus::Disbanded
+    }
+
+    // utility methods
+
+    fn ensure_active(&self) -> Result<()> {
+        if self.is_disbanded() {
+            Err(CircleError::Disbanded.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn validate_capacity(capacity: i16) -> Result<()> {
+        if capacity < 3 {
+            Err(CircleError::InvalidCapacity.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Aggregate for Circle {
+    const TYPE_NAME: &'static str = "circle";
+
+    type Id = CircleId;
+    type Event = CircleEvent;
+
+    fn id(&self) -> &CircleId {
+        &self.id
+    }
+
+    fn version(&self) -> Version {
+        self.version
+    }
 
-    fn from_created_event(event: CircleEvent) -> Self {
-        match event.data {
-            event::EventData::CircleCreated(event::CircleCreated { name, capacity }) => Self {
-                id: event.circle_id,
+    fn initial(event: &CircleEvent) -> Option<Self> {
+        match &event.data {
+            event::EventData::CircleCreated(event::CircleCreated {
                 name,
                 capacity,
+                owner,
+            }) => Some(Self {
+                id: event.circle_id.clone(),
+                name: name.clone(),
+                capacity: *capacity,
+                owner_id: owner.as_ref().map(|owner| owner.id.clone()),
+                members: owner.iter().cloned().collect(),
+                status: CircleStatus::Active,
                 version: event.version,
-            },
-            _ => panic!("Invalid event for creation"),
+            }),
+            _ => None,
         }
     }
 
-    pub fn apply_event(&mut self, event: &CircleEvent) {
+    fn apply(&mut self, event: &CircleEvent) {
         match &event.data {
-            event::EventData::CircleCreated(event::CircleCreated { name, capacity }) => {
+            event::EventData::CircleCreated(event::CircleCreated {
+                name,
+                capacity,
+                owner,
+            }) => {
                 self.name = name.clone();
                 self.capacity = *capacity;
-                self.version = event.version.clone();
+                self.owner_id = owner.as_ref().map(|owner| owner.id.clone());
+                self.members = owner.iter().cloned().collect();
+                self.version = event.version;
             }
             event::EventData::CircleUpdated(event::CircleUpdated { name, capacity }) => {
                 if let Some(new_name) = name {
@@ -74,24 +200,183 @@ impl Circle {
                 if let Some(new_capacity) = capacity {
                     self.capacity = *new_capacity;
                 }
-                self.version = event.version.clone();
+                self.version = event.version;
+            }
+            event::EventData::MemberJoined(event::MemberJoined { member }) => {
+                self.members.push(member.clone());
+                self.version = event.version;
+            }
+            event::EventData::MemberLeft(event::MemberLeft { member_id }) => {
+                self.members.retain(|member| &member.id != member_id);
+                self.version = event.version;
+            }
+            event::EventData::OwnershipTransferred(event::OwnershipTransferred {
+                new_owner_id,
+                ..
+            }) => {
+                self.owner_id = Some(new_owner_id.clone());
+                self.version = event.version;
+            }
+            event::EventData::CircleDisbanded(event::CircleDisbanded {}) => {
+                self.status = CircleStatus::Disbanded;
+                self.version = event.version;
             }
         }
     }
+}
 
-    // Getters
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
 
-    pub fn name(&self) -> &str {
-        &self.name
+    use super::*;
+
+    fn member(id: &str) -> anyhow::Result<Member> {
+        Member::new(MemberId::from_str(id)?, format!("member {}", id))
     }
 
-    // utility methods
+    fn circle_error(result: Result<(Circle, CircleEvent)>) -> Option<CircleError> {
+        result.err()?.downcast_ref::<CircleError>().cloned()
+    }
 
-    fn validate_capacity(capacity: i16) -> Result<()> {
-        if capacity < 3 {
-            Err(Error::msg("Circle capacity must be 3 or more"))
-        } else {
-            Ok(())
-        }
+    #[test]
+    fn test_create_makes_owner_a_member() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 3, member("owner")?)?;
+        assert!(circle.is_owner(&MemberId::from_str("owner")?));
+        assert!(circle.is_member(&MemberId::from_str("owner")?));
+        assert_eq!(circle.members.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_and_leave() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 3, member("owner")?)?;
+        let (circle, joined) = circle.join(member("1")?)?;
+        assert_eq!(u32::from(joined.version), 2);
+        assert!(circle.is_member(&MemberId::from_str("1")?));
+
+        let (circle, left) = circle.leave(&MemberId::from_str("1")?)?;
+        assert_eq!(u32::from(left.version), 3);
+        assert!(!circle.is_member(&MemberId::from_str("1")?));
+        assert_eq!(circle.version, left.version);
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_rejects_duplicates_and_full_circle() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 3, member("owner")?)?;
+        let (circle, _) = circle.join(member("1")?)?;
+        assert_eq!(
+            circle_error(circle.clone().join(member("1")?)),
+            Some(CircleError::AlreadyMember)
+        );
+
+        let (circle, _) = circle.join(member("2")?)?;
+        assert_eq!(
+            circle_error(circle.clone().join(member("3")?)),
+            Some(CircleError::CircleFull)
+        );
+        assert_eq!(
+            circle_error(circle.leave(&MemberId::from_str("3")?)),
+            Some(CircleError::NotMember)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_capacity_cannot_drop_below_members() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 5, member("owner")?)?;
+        let (circle, _) = circle.join(member("1")?)?;
+        let (circle, _) = circle.join(member("2")?)?;
+        let (circle, _) = circle.join(member("3")?)?;
+        assert_eq!(
+            circle_error(circle.clone().update(None, Some(3))),
+            Some(CircleError::CapacityBelowMembers)
+        );
+        assert!(circle.update(None, Some(4)).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_owner_cannot_leave_without_transfer() -> anyhow::Result<()> {
+        let owner_id = MemberId::from_str("owner")?;
+        let (circle, _) = Circle::create("Music club".to_string(), 3, member("owner")?)?;
+        let (circle, _) = circle.join(member("1")?)?;
+        assert_eq!(
+            circle_error(circle.clone().leave(&owner_id)),
+            Some(CircleError::OwnerCannotLeave)
+        );
+
+        let (circle, transferred) = circle.transfer_ownership(&MemberId::from_str("1")?)?;
+        assert_eq!(u32::from(transferred.version), 3);
+        assert!(circle.is_owner(&MemberId::from_str("1")?));
+        let (circle, _) = circle.leave(&owner_id)?;
+        assert!(!circle.is_member(&owner_id));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transfer_ownership_requires_another_member() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 3, member("owner")?)?;
+        assert_eq!(
+            circle_error(circle.clone().transfer_ownership(&MemberId::from_str("1")?)),
+            Some(CircleError::NotMember)
+        );
+        assert_eq!(
+            circle_error(circle.transfer_ownership(&MemberId::from_str("owner")?)),
+            Some(CircleError::AlreadyOwner)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_disbanded_circle_rejects_commands() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 3, member("owner")?)?;
+        let (circle, _) = circle.join(member("1")?)?;
+        let (circle, disbanded) = circle.disband()?;
+        assert_eq!(u32::from(disbanded.version), 3);
+        assert!(circle.is_disbanded());
+
+        let member_id = MemberId::from_str("1")?;
+        assert_eq!(
+            circle_error(circle.clone().update(Some("Jazz club".to_string()), None)),
+            Some(CircleError::Disbanded)
+        );
+        assert_eq!(
+            circle_error(circle.clone().join(member("2")?)),
+            Some(CircleError::Disbanded)
+        );
+        assert_eq!(
+            circle_error(circle.clone().leave(&member_id)),
+            Some(CircleError::Disbanded)
+        );
+        assert_eq!(
+            circle_error(circle.clone().transfer_ownership(&member_id)),
+            Some(CircleError::Disbanded)
+        );
+        assert_eq!(circle_error(circle.disband()), Some(CircleError::Disbanded));
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_restores_members_and_owner() -> anyhow::Result<()> {
+        let (circle, created) = Circle::create("Music club".to_string(), 5, member("owner")?)?;
+        let (circle, joined_1) = circle.join(member("1")?)?;
+        let (circle, joined_2) = circle.join(member("2")?)?;
+        let (circle, transferred) = circle.transfer_ownership(&MemberId::from_str("2")?)?;
+        let (circle, left) = circle.leave(&MemberId::from_str("1")?)?;
+
+        let replayed = Circle::replay(vec![created, joined_1, joined_2, transferred, left]);
+        assert_eq!(replayed, Some(circle));
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_requires_creation_event() -> anyhow::Result<()> {
+        let (circle, _) = Circle::create("Music club".to_string(), 3, member("owner")?)?;
+        let (_, joined) = circle.join(member("1")?)?;
+        assert_eq!(Circle::replay(vec![]), None);
+        assert_eq!(Circle::replay(vec![joined]), None);
+        Ok(())
     }
 }
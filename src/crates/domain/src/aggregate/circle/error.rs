@@ -0,0 +1,37 @@
+use std::fmt;
+
+/// Invariant violations of the [`Circle`](super::Circle) aggregate.
+///
+/// Aggregate methods return them inside `anyhow::Error`; callers downcast to tell them apart.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CircleError {
+    InvalidCapacity,
+    CapacityBelowMembers,
+    CircleFull,
+    AlreadyMember,
+    NotMember,
+    AlreadyOwner,
+    OwnerCannotLeave,
+    Disbanded,
+}
+
+impl fmt::Display for CircleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircleError::InvalidCapacity => write!(f, "Circle capacity must be 3 or more"),
+            CircleError::CapacityBelowMembers => {
+                write!(f, "Circle capacity must not be below the number of members")
+            }
+            CircleError::CircleFull => write!(f, "Circle is full"),
+            CircleError::AlreadyMember => write!(f, "Member has already joined the circle"),
+            CircleError::NotMember => write!(f, "Member does not belong to the circle"),
+            CircleError::AlreadyOwner => write!(f, "Member already owns the circle"),
+            CircleError::OwnerCannotLeave => {
+                write!(f, "Owner must transfer ownership before leaving")
+            }
+            CircleError::Disbanded => write!(f, "Circle has been disbanded"),
+        }
+    }
+}
+
+impl std::error::Error for CircleError {}
@@ -1,13 +1,21 @@
 use chrono::{NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::aggregate::value_object::{circle_id::CircleId, event_id::EventId, version::Version};
+use super::member::Member;
+use crate::aggregate::{
+    event_sourced::DomainEvent,
+    value_object::{
+        circle_id::CircleId, event_id::EventId, event_metadata::EventMetadata, member_id::MemberId,
+        version::Version,
+    },
+};
 
 #[derive(Clone, Debug)]
 pub struct CircleEvent {
     pub circle_id: CircleId,
     pub data: EventData,
     pub id: EventId,
+    pub metadata: EventMetadata,
     pub occurred_at: NaiveDateTime,
     pub version: Version,
 }
@@ -17,25 +25,91 @@ impl CircleEvent {
         CircleEventBuilder {
             circle_id,
             id: EventId::gen(),
+            metadata: EventMetadata::default(),
             occurred_at: Utc::now().naive_utc(),
             version,
         }
     }
 }
 
+impl DomainEvent for CircleEvent {
+    type AggregateId = CircleId;
+    type Payload = EventData;
+
+    fn id(&self) -> &EventId {
+        &self.id
+    }
+
+    fn aggregate_id(&self) -> &CircleId {
+        &self.circle_id
+    }
+
+    fn version(&self) -> Version {
+        self.version
+    }
+
+    fn occurred_at(&self) -> NaiveDateTime {
+        self.occurred_at
+    }
+
+    fn event_type(&self) -> &'static str {
+        self.data.event_type()
+    }
+
+    fn payload(&self) -> &EventData {
+        &self.data
+    }
+
+    fn metadata(&self) -> &EventMetadata {
+        &self.metadata
+    }
+
+    fn with_metadata(self, metadata: EventMetadata) -> Self {
+        Self { metadata, ..self }
+    }
+
+    fn from_stored(
+        id: EventId,
+        circle_id: CircleId,
+        version: Version,
+        occurred_at: NaiveDateTime,
+        data: EventData,
+    ) -> Self {
+        Self {
+            circle_id,
+            data,
+            id,
+            metadata: EventMetadata::default(),
+            occurred_at,
+            version,
+        }
+    }
+}
+
 pub struct CircleEventBuilder {
     circle_id: CircleId,
     id: EventId,
+    metadata: EventMetadata,
     occurred_at: NaiveDateTime,
     version: Version,
 }
 
 impl CircleEventBuilder {
-    pub fn circle_created(self, name: String, capacity: i16) -> CircleEvent {
+    pub fn metadata(self, metadata: EventMetadata) -> Self {
+        Self { metadata, ..self }
+    }
+
+    pub fn circle_created(self, name: String, capacity: i16, owner: Member) -> CircleEvent {
         CircleEvent {
             circle_id: self.circle_id,
-            data: CircleCreated { name, capacity }.into(),
+            data: CircleCreated {
+                name,
+                capacity,
+                owner: Some(owner),
+            }
+            .into(),
             id: self.id,
+            metadata: self.metadata,
             occurred_at: self.occurred_at,
             version: self.version,
         }
@@ -46,6 +120,59 @@ impl CircleEventBuilder {
             circle_id: self.circle_id,
             data: CircleUpdated { name, capacity }.into(),
             id: self.id,
+            metadata: self.metadata,
+            occurred_at: self.occurred_at,
+            version: self.version.next(),
+        }
+    }
+
+    pub fn member_joined(self, member: Member) -> CircleEvent {
+        CircleEvent {
+            circle_id: self.circle_id,
+            data: MemberJoined { member }.into(),
+            id: self.id,
+            metadata: self.metadata,
+            occurred_at: self.occurred_at,
+            version: self.version.next(),
+        }
+    }
+
+    pub fn member_left(self, member_id: MemberId) -> CircleEvent {
+        CircleEvent {
+            circle_id: self.circle_id,
+            data: MemberLeft { member_id }.into(),
+            id: self.id,
+            metadata: self.metadata,
+            occurred_at: self.occurred_at,
+            version: self.version.next(),
+        }
+    }
+
+    pub fn circle_disbanded(self) -> CircleEvent {
+        CircleEvent {
+            circle_id: self.circle_id,
+            data: CircleDisbanded {}.into(),
+            id: self.id,
+            metadata: self.metadata,
+            occurred_at: self.occurred_at,
+            version: self.version.next(),
+        }
+    }
+
+    pub fn ownership_transferred(
+        self,
+        previous_owner_id: Option<MemberId>,
+        new_owner_id: MemberId,
+    ) -> CircleEvent {
+        CircleEvent {
+            circle_id: self.circle_id,
+            data: OwnershipTransferred {
+                previous_owner_id,
+                new_owner_id,
+            }
+            .into(),
+            id: self.id,
+            metadata: self.metadata,
             occurred_at: self.occurred_at,
             version: self.version.next(),
         }
@@ -57,6 +184,23 @@ impl CircleEventBuilder {
 pub enum EventData {
     CircleCreated(CircleCreated),
     CircleUpdated(CircleUpdated),
+    MemberJoined(MemberJoined),
+    MemberLeft(MemberLeft),
+    OwnershipTransferred(OwnershipTransferred),
+    CircleDisbanded(CircleDisbanded),
+}
+
+impl EventData {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            EventData::CircleCreated(_) => "circle_created",
+            EventData::CircleUpdated(_) => "circle_updated",
+            EventData::MemberJoined(_) => "member_joined",
+            EventData::MemberLeft(_) => "member_left",
+            EventData::OwnershipTransferred(_) => "ownership_transferred",
+            EventData::CircleDisbanded(_) => "circle_disbanded",
+        }
+    }
 }
 
 impl From<CircleCreated> for EventData {
@@ -71,10 +215,36 @@ impl From<CircleUpdated> for EventData {
     }
 }
 
+impl From<MemberJoined> for EventData {
+    fn from(joined: MemberJoined) -> Self {
+        Self::MemberJoined(joined)
+    }
+}
+
+impl From<MemberLeft> for EventData {
+    fn from(left: MemberLeft) -> Self {
+        Self::MemberLeft(left)
+    }
+}
+
+impl From<OwnershipTransferred> for EventData {
+    fn from(transferred: OwnershipTransferred) -> Self {
+        Self::OwnershipTransferred(transferred)
+    }
+}
+
+impl From<CircleDisbanded> for EventData {
+    fn from(disbanded: CircleDisbanded) -> Self {
+        Self::CircleDisbanded(disbanded)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct CircleCreated {
     pub name: String,
     pub capacity: i16,
+    // circles created before ownership existed have no owner
+    pub owner: Option<Member>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -82,3 +252,22 @@ pub struct CircleUpdated {
     pub name: Option<String>,
     pub capacity: Option<i16>,
 }
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MemberJoined {
+    pub member: Member,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MemberLeft {
+    pub member_id: MemberId,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct OwnershipTransferred {
+    pub previous_owner_id: Option<MemberId>,
+    pub new_owner_id: MemberId,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CircleDisbanded {}
@@ -0,0 +1,16 @@
+use crate::aggregate::value_object::member_id::MemberId;
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Member {
+    pub id: MemberId,
+    pub name: String,
+}
+
+impl Member {
+    pub fn new(id: MemberId, name: String) -> anyhow::Result<Self> {
+        if name.trim().is_empty() {
+            return Err(anyhow::Error::msg("Member name must not be empty"));
+        }
+        Ok(Self { id, name })
+    }
+}
@@ -0,0 +1,16 @@
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircleStatus {
+    #[default]
+    Active,
+    Disbanded,
+}
+
+impl CircleStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircleStatus::Active => "active",
+            CircleStatus::Disbanded => "disbanded",
+        }
+    }
+}
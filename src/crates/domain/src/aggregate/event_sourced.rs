@@ -0,0 +1,81 @@
+//! Traits shared by every event-sourced aggregate.
+//!
+//! The event store only depends on these traits, so a new aggregate needs an
+//! implementation of [`Aggregate`] and [`DomainEvent`] and nothing else.
+
+use std::{fmt, str::FromStr};
+
+use chrono::NaiveDateTime;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::value_object::{event_id::EventId, event_metadata::EventMetadata, version::Version};
+
+/// An event in the stream of a single aggregate.
+pub trait DomainEvent: Clone + fmt::Debug + Send + Sync + 'static {
+    type AggregateId;
+    /// Stored as the JSON `payload` of the event.
+    type Payload: Serialize + DeserializeOwned;
+
+    fn id(&self) -> &EventId;
+    fn aggregate_id(&self) -> &Self::AggregateId;
+    fn version(&self) -> Version;
+    fn occurred_at(&self) -> NaiveDateTime;
+    /// Stored as `event_type`; must stay stable across releases.
+    fn event_type(&self) -> &'static str;
+    fn payload(&self) -> &Self::Payload;
+    /// Stored as the JSON `metadata` of the event, apart from the payload.
+    fn metadata(&self) -> &EventMetadata;
+
+    /// Attaches the context of the command that produced the event.
+    fn with_metadata(self, metadata: EventMetadata) -> Self;
+
+    /// Reassembles an event read back from the store.
+    fn from_stored(
+        id: EventId,
+        aggregate_id: Self::AggregateId,
+        version: Version,
+        occurred_at: NaiveDateTime,
+        payload: Self::Payload,
+    ) -> Self;
+}
+
+/// An aggregate whose state is the fold of its event stream.
+///
+/// Snapshots hold the serialised state, so the serde shape of an aggregate is part
+/// of its storage format.
+pub trait Aggregate:
+    Clone + fmt::Debug + Serialize + DeserializeOwned + Send + Sync + 'static
+{
+    /// Stored as `aggregate_type`; must stay stable across releases.
+    const TYPE_NAME: &'static str;
+
+    type Id: Clone
+        + fmt::Debug
+        + fmt::Display
+        + FromStr<Err = anyhow::Error>
+        + Eq
+        + Send
+        + Sync
+        + 'static;
+    type Event: DomainEvent<AggregateId = Self::Id>;
+
+    fn id(&self) -> &Self::Id;
+    fn version(&self) -> Version;
+
+    /// State right after the first event of a stream, or `None` when `event` cannot
+    /// start one.
+    fn initial(event: &Self::Event) -> Option<Self>;
+
+    fn apply(&mut self, event: &Self::Event);
+
+    /// Folds a whole stream. Returns `None` when the stream is empty or does not
+    /// start with an event accepted by [`Aggregate::initial`].
+    fn replay(events: impl IntoIterator<Item = Self::Event>) -> Option<Self> {
+        let mut events = events.into_iter();
+        let mut state = Self::initial(&events.next()?)?;
+        for event in events {
+            state.apply(&event);
+        }
+        Some(state)
+    }
+}
@@ -1,3 +1,5 @@
 pub mod circle_id;
 pub mod event_id;
+pub mod event_metadata;
+pub mod member_id;
 pub mod version;
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// Context of the command that produced an event, kept for audit and debugging.
+///
+/// Every field is supplied by the caller and optional; nothing in the domain
+/// depends on it.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EventMetadata {
+    /// Who issued the command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor_id: Option<String>,
+    /// Shared by every event that belongs to the same request or workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Id of the message that directly caused the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub causation_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+impl EventMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
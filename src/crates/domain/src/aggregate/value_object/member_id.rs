@@ -0,0 +1,60 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use rand::distr::{Alphanumeric, SampleString};
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MemberId(String);
+
+impl MemberId {
+    pub fn gen() -> Self {
+        let mut rng = rand::rng();
+        Self(Alphanumeric.sample_string(&mut rng, 36))
+    }
+}
+
+impl Hash for MemberId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl fmt::Display for MemberId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for MemberId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(anyhow::Error::msg("Member ID must not be empty"));
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl From<MemberId> for String {
+    fn from(member_id: MemberId) -> Self {
+        member_id.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() -> anyhow::Result<()> {
+        let member_id = MemberId::gen();
+        assert_eq!(member_id.to_string().len(), 36);
+
+        let member_id = MemberId::from_str("student-0001")?;
+        assert_eq!(member_id.to_string(), "student-0001");
+        assert!(MemberId::from_str("").is_err());
+        Ok(())
+    }
+}
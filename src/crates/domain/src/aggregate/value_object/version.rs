@@ -13,7 +13,9 @@ impl fmt::Display for Error {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize,
+)]
 pub struct Version(u32);
 
 impl Version {
@@ -29,6 +31,12 @@ impl Version {
     }
 }
 
+impl Default for Version {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Conversions between Version and other types
 impl From<Version> for u32 {
     fn from(version: Version) -> u32 {
@@ -62,10 +70,7 @@ impl TryFrom<i32> for Version {
     type Error = Error;
 
     fn try_from(value: i32) -> Result<Self, Self::Error> {
-        if value < 0 {
-            return Err(Error::OutOfRange);
-        }
-        u32::try_from(value as u32)
+        u32::try_from(value)
             .map(Self)
             .map_err(|_| Error::OutOfRange)
     }
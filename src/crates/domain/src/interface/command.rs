@@ -1,2 +1,3 @@
 pub mod circle_duplicate_checker_interface;
 pub mod circle_repository_interface;
+pub mod idempotency_store_interface;
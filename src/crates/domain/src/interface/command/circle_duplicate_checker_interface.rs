@@ -6,6 +6,10 @@ use anyhow::Error;
 #[mockall::automock]
 #[async_trait::async_trait]
 pub trait CircleDuplicateCheckerInterface: Send + Sync {
+    /// Fails with [`DuplicateCircleName`](super::circle_repository_interface::DuplicateCircleName)
+    /// when another circle already holds `circle.name`.
+    ///
+    /// This is an early check only; the repository enforces uniqueness when storing.
     async fn check_circle_duplicate(&self, circle: &Circle) -> Result<(), Error>;
 }
 
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
 
 use crate::aggregate::{
     circle::Circle,
@@ -9,7 +9,15 @@ use anyhow::Error;
 #[mockall::automock]
 #[async_trait::async_trait]
 pub trait CircleRepositoryInterface: Send + Sync {
+    /// Loads the circle. When the stream has no events, the returned error
+    /// downcasts to [`CircleNotFound`].
     async fn find_by_id(&self, circle_id: &CircleId) -> Result<Circle, Error>;
+    /// Appends `events` to the stream of their circle.
+    ///
+    /// `current_version` is the version the caller expects the stream head to be at
+    /// (`None` for a stream that must not exist yet). When the head differs, the
+    /// returned error downcasts to [`VersionConflict`]. When a created or renamed circle
+    /// would take a name reserved by another circle, it downcasts to [`DuplicateCircleName`].
     async fn store(
         &self,
         current_version: Option<Version>,
@@ -20,3 +28,60 @@ pub trait CircleRepositoryInterface: Send + Sync {
 pub trait HasCircleRepositoryInterface {
     fn circle_repository(&self) -> Arc<dyn CircleRepositoryInterface + Send + Sync>;
 }
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VersionConflict {
+    pub circle_id: CircleId,
+    pub expected: Option<Version>,
+    pub actual: Option<Version>,
+}
+
+impl fmt::Display for VersionConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |version: &Option<Version>| match version {
+            Some(version) => version.to_string(),
+            None => "none".to_string(),
+        };
+        write!(
+            f,
+            "version conflict on circle {}: expected {}, actual {}",
+            self.circle_id,
+            show(&self.expected),
+            show(&self.actual)
+        )
+    }
+}
+
+impl std::error::Error for VersionConflict {}
+
+/// The key a circle name is reserved under, so names differing only in case or in
+/// surrounding spaces are taken as the same on every backend.
+pub fn name_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DuplicateCircleName {
+    pub name: String,
+}
+
+impl fmt::Display for DuplicateCircleName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circle name already exists: {}", self.name)
+    }
+}
+
+impl std::error::Error for DuplicateCircleName {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircleNotFound {
+    pub circle_id: CircleId,
+}
+
+impl fmt::Display for CircleNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circle not found: {}", self.circle_id)
+    }
+}
+
+impl std::error::Error for CircleNotFound {}
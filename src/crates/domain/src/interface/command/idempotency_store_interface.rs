@@ -0,0 +1,54 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Error;
+
+/// How long a claim without a response belongs to the request that made it. Requests
+/// holding a claim are cut off well before it runs out, so an older claim was left by
+/// a request that crashed, and a retry takes it over.
+pub const CLAIM_LEASE: Duration = Duration::from_secs(60);
+
+/// HTTP response recorded for an idempotency key, replayed verbatim on retries.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IdempotencyClaim {
+    /// The key was unused and is now reserved for this request.
+    Acquired,
+    /// The same request already completed with this response.
+    Completed(StoredResponse),
+    /// The same request is still being processed, within its [`CLAIM_LEASE`].
+    InProgress,
+    /// The key was already used for a request with a different payload.
+    Mismatch,
+}
+
+#[mockall::automock]
+#[async_trait::async_trait]
+pub trait IdempotencyStoreInterface: Send + Sync {
+    /// Reserves `key` within `scope` for the request identified by `request_hash`,
+    /// or reports how the key was used before.
+    async fn claim(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> Result<IdempotencyClaim, Error>;
+    /// Records the response of a claimed key.
+    async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<(), Error>;
+    /// Drops a claimed key without a response so that the request can be retried.
+    async fn release(&self, scope: &str, key: &str) -> Result<(), Error>;
+}
+
+pub trait HasIdempotencyStoreInterface {
+    fn idempotency_store(&self) -> Arc<dyn IdempotencyStoreInterface + Send + Sync>;
+}
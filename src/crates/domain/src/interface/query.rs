@@ -1 +1,3 @@
+pub mod circle_event_reader_interface;
 pub mod circle_reader_interface;
+pub mod projection_rebuilder_interface;
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+
+use crate::aggregate::{
+    circle::{event::CircleEvent, Circle},
+    value_object::{circle_id::CircleId, version::Version},
+};
+
+/// A point in the history of a circle stream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AsOf {
+    /// State right after the event with this version, or the latest state when the
+    /// stream ends before it.
+    Version(Version),
+    /// State after every event that occurred at or before this instant (UTC).
+    Time(NaiveDateTime),
+}
+
+#[mockall::automock]
+#[async_trait::async_trait]
+pub trait CircleEventReaderInterface: Send + Sync {
+    /// Returns at most `limit` events of the circle in version order, starting at `from_version`.
+    async fn list_events(
+        &self,
+        circle_id: &CircleId,
+        from_version: Option<Version>,
+        limit: u32,
+    ) -> Result<Vec<CircleEvent>, anyhow::Error>;
+
+    /// Rebuilds the circle from the event store as it was at `as_of`.
+    /// Returns `None` when the circle did not exist yet at that point.
+    async fn get_circle_as_of(
+        &self,
+        circle_id: &CircleId,
+        as_of: AsOf,
+    ) -> Result<Option<Circle>, anyhow::Error>;
+}
+
+pub trait HasCircleEventReader {
+    fn circle_event_reader(&self) -> Arc<dyn CircleEventReaderInterface + Send + Sync>;
+}
@@ -1,13 +1,64 @@
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
+
+use serde::Deserialize;
 
 use crate::aggregate::{circle::Circle, value_object::circle_id::CircleId};
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircleSortKey {
+    Capacity,
+    #[default]
+    CreatedAt,
+    Name,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// A page request over the active circles.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CircleListQuery {
+    pub sort_key: CircleSortKey,
+    pub order: SortOrder,
+    pub name_prefix: Option<String>,
+    pub min_capacity: Option<i16>,
+    pub max_capacity: Option<i16>,
+    pub limit: u32,
+    /// Opaque position returned as `next_cursor` by the previous page.
+    pub cursor: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CirclePage {
+    pub circles: Vec<Circle>,
+    /// Cursor of the following page, or `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
 #[async_trait::async_trait]
 pub trait CircleReaderInterface: Send + Sync {
     async fn get_circle(&self, circle_id: CircleId) -> Result<Option<Circle>, anyhow::Error>;
-    async fn list_circles(&self) -> Result<Vec<Circle>, anyhow::Error>;
+    /// Fails with [`InvalidCursor`] when `query.cursor` was not issued by this reader.
+    async fn list_circles(&self, query: &CircleListQuery) -> Result<CirclePage, anyhow::Error>;
 }
 
 pub trait HasCircleReader {
     fn circle_reader(&self) -> Arc<dyn CircleReaderInterface + Send + Sync>;
 }
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidCursor;
+
+impl fmt::Display for InvalidCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cursor")
+    }
+}
+
+impl std::error::Error for InvalidCursor {}
@@ -0,0 +1,62 @@
+use std::{fmt, sync::Arc};
+
+use chrono::NaiveDateTime;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RebuildState {
+    #[default]
+    Idle,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl RebuildState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RebuildState::Idle => "idle",
+            RebuildState::Running => "running",
+            RebuildState::Completed => "completed",
+            RebuildState::Failed => "failed",
+        }
+    }
+}
+
+/// Progress of the latest read model rebuild.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RebuildProgress {
+    pub state: RebuildState,
+    /// Number of circle streams found in the event store.
+    pub total: u64,
+    /// Number of circle streams projected so far.
+    pub processed: u64,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub error: Option<String>,
+}
+
+#[async_trait::async_trait]
+pub trait ProjectionRebuilderInterface: Send + Sync {
+    /// Starts a rebuild in the background and returns immediately.
+    /// Fails with [`RebuildInProgress`] while another rebuild is running.
+    async fn start(&self) -> Result<(), anyhow::Error>;
+    /// Runs a rebuild to completion.
+    /// Fails with [`RebuildInProgress`] while another rebuild is running.
+    async fn rebuild(&self) -> Result<RebuildProgress, anyhow::Error>;
+    async fn progress(&self) -> RebuildProgress;
+}
+
+pub trait HasProjectionRebuilder {
+    fn projection_rebuilder(&self) -> Arc<dyn ProjectionRebuilderInterface + Send + Sync>;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebuildInProgress;
+
+impl fmt::Display for RebuildInProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a projection rebuild is already running")
+    }
+}
+
+impl std::error::Error for RebuildInProgress {}
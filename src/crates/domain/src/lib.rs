@@ -1,2 +1,4 @@
 pub mod aggregate;
 pub mod interface;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
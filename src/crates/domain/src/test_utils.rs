@@ -0,0 +1,4 @@
+//! Support for testing implementations of the domain interfaces, behind the
+//! `test-utils` feature.
+
+pub mod circle_conformance;
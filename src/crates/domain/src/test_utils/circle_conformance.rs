@@ -0,0 +1,481 @@
+//! Behaviour every backend of [`CircleRepositoryInterface`] and [`CircleReaderInterface`]
+//! must share.
+//!
+//! A backend runs [`run_repository_suite`] and [`run_reader_suite`] from one of its own
+//! tests. Every check names its circles under a random prefix and only looks at those,
+//! so the suites can also run against storage that already holds data. Failures panic
+//! with the usual assertion messages.
+
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use rand::distr::{Alphanumeric, SampleString};
+
+use crate::{
+    aggregate::{
+        circle::{event::CircleEvent, member::Member, Circle},
+        value_object::{circle_id::CircleId, member_id::MemberId, version::Version},
+    },
+    interface::{
+        command::circle_repository_interface::{
+            CircleNotFound, CircleRepositoryInterface, DuplicateCircleName, VersionConflict,
+        },
+        query::circle_reader_interface::{
+            CircleListQuery, CircleReaderInterface, CircleSortKey, InvalidCursor, SortOrder,
+        },
+    },
+};
+
+/// Pages longer than any check creates, so a single page holds all of its circles.
+const LIMIT: u32 = 100;
+
+/// A repository and the reader projected from what it stores.
+#[async_trait::async_trait]
+pub trait CircleBackend: Send + Sync {
+    fn repository(&self) -> Arc<dyn CircleRepositoryInterface + Send + Sync>;
+    fn reader(&self) -> Arc<dyn CircleReaderInterface + Send + Sync>;
+    /// Returns once the reader reflects every event stored so far. Backends that
+    /// project asynchronously catch their subscription up here.
+    async fn settle(&self) -> Result<()>;
+}
+
+/// Names circles under a prefix no other run uses.
+struct Fixture {
+    prefix: String,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        Self {
+            prefix: format!("{} ", Alphanumeric.sample_string(&mut rand::rng(), 8)),
+        }
+    }
+
+    fn name(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    fn create(&self, name: &str, capacity: i16) -> Result<(Circle, CircleEvent)> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        Circle::create(self.name(name), capacity, owner)
+    }
+
+    fn query(&self, sort_key: CircleSortKey, order: SortOrder) -> CircleListQuery {
+        CircleListQuery {
+            sort_key,
+            order,
+            name_prefix: Some(self.prefix.clone()),
+            limit: LIMIT,
+            ..CircleListQuery::default()
+        }
+    }
+}
+
+/// Stores the event that produced `next` from a circle at `version`.
+async fn store(
+    repository: &(dyn CircleRepositoryInterface + Send + Sync),
+    version: Option<Version>,
+    (next, event): (Circle, CircleEvent),
+) -> Result<Circle> {
+    repository.store(version, vec![event]).await?;
+    Ok(next)
+}
+
+async fn names(
+    reader: &(dyn CircleReaderInterface + Send + Sync),
+    query: &CircleListQuery,
+) -> Result<Vec<String>> {
+    let page = reader.list_circles(query).await?;
+    Ok(page.circles.into_iter().map(|circle| circle.name).collect())
+}
+
+pub async fn run_repository_suite(
+    repository: &(dyn CircleRepositoryInterface + Send + Sync),
+) -> Result<()> {
+    unknown_circle_is_not_found(repository).await?;
+    stale_writes_conflict(repository).await?;
+    long_streams_replay_in_order(repository).await?;
+    names_are_unique_among_active_circles(repository).await?;
+    names_ignore_case_and_surrounding_spaces(repository).await?;
+    Ok(())
+}
+
+pub async fn run_reader_suite(backend: &dyn CircleBackend) -> Result<()> {
+    reader_follows_the_stream(backend).await?;
+    list_orders_by_each_sort_key(backend).await?;
+    list_applies_filters(backend).await?;
+    list_pages_cover_every_circle_once(backend).await?;
+    list_rejects_invalid_cursor(backend).await?;
+    Ok(())
+}
+
+pub async fn unknown_circle_is_not_found(
+    repository: &(dyn CircleRepositoryInterface + Send + Sync),
+) -> Result<()> {
+    let circle_id = CircleId::gen();
+    let error = repository
+        .find_by_id(&circle_id)
+        .await
+        .expect_err("an unknown circle should not be found");
+    assert_eq!(
+        error.downcast_ref::<CircleNotFound>(),
+        Some(&CircleNotFound { circle_id })
+    );
+    Ok(())
+}
+
+/// The second of two writers starting from the same version loses, and so does a
+/// second create of the same stream.
+pub async fn stale_writes_conflict(
+    repository: &(dyn CircleRepositoryInterface + Send + Sync),
+) -> Result<()> {
+    let fixture = Fixture::new();
+    let (circle, created) = fixture.create("Chess club", 10)?;
+    repository.store(None, vec![created.clone()]).await?;
+
+    let error = repository
+        .store(None, vec![created])
+        .await
+        .expect_err("creating the same circle twice should conflict");
+    assert_eq!(
+        error.downcast_ref::<VersionConflict>(),
+        Some(&VersionConflict {
+            circle_id: circle.id.clone(),
+            expected: None,
+            actual: Some(circle.version),
+        })
+    );
+
+    let (first, first_event) = circle.clone().update(None, Some(20))?;
+    let (_, second_event) = circle.clone().update(None, Some(30))?;
+    repository
+        .store(Some(circle.version), vec![first_event])
+        .await?;
+    let error = repository
+        .store(Some(circle.version), vec![second_event])
+        .await
+        .expect_err("a write from a stale version should conflict");
+    assert_eq!(
+        error.downcast_ref::<VersionConflict>(),
+        Some(&VersionConflict {
+            circle_id: circle.id.clone(),
+            expected: Some(circle.version),
+            actual: Some(first.version),
+        })
+    );
+    assert_eq!(repository.find_by_id(&circle.id).await?, first);
+    Ok(())
+}
+
+/// Loads a stream long enough to cross the snapshot thresholds of common policies,
+/// so the load goes through a snapshot and the events after it, and includes an
+/// append of several events at once.
+pub async fn long_streams_replay_in_order(
+    repository: &(dyn CircleRepositoryInterface + Send + Sync),
+) -> Result<()> {
+    let fixture = Fixture::new();
+    let (mut circle, created) = fixture.create("Chess club", 3)?;
+    repository.store(None, vec![created]).await?;
+    for capacity in 4..16 {
+        let version = circle.version;
+        circle = store(
+            repository,
+            Some(version),
+            circle.update(None, Some(capacity))?,
+        )
+        .await?;
+        assert_eq!(repository.find_by_id(&circle.id).await?, circle);
+    }
+
+    let version = circle.version;
+    let (renamed, renamed_event) = circle.update(Some(fixture.name("Go club")), None)?;
+    let (resized, resized_event) = renamed.update(None, Some(40))?;
+    repository
+        .store(Some(version), vec![renamed_event, resized_event])
+        .await?;
+
+    let loaded = repository.find_by_id(&resized.id).await?;
+    assert_eq!(loaded, resized);
+    assert_eq!(u32::from(loaded.version), 15);
+    Ok(())
+}
+
+/// A name belongs to one active circle at a time and is given back on disband.
+pub async fn names_are_unique_among_active_circles(
+    repository: &(dyn CircleRepositoryInterface + Send + Sync),
+) -> Result<()> {
+    let fixture = Fixture::new();
+    let (chess, chess_created) = fixture.create("Chess club", 10)?;
+    repository.store(None, vec![chess_created]).await?;
+
+    let (rival, rival_created) = fixture.create("Chess club", 10)?;
+    let error = repository
+        .store(None, vec![rival_created.clone()])
+        .await
+        .expect_err("a taken name should be rejected");
+    assert_eq!(
+        error.downcast_ref::<DuplicateCircleName>(),
+        Some(&DuplicateCircleName {
+            name: fixture.name("Chess club"),
+        })
+    );
+    // the rejected write leaves no stream behind
+    assert!(repository.find_by_id(&rival.id).await.is_err());
+
+    let (go, go_created) = fixture.create("Go club", 10)?;
+    repository.store(None, vec![go_created]).await?;
+    let (_, rename) = go.clone().update(Some(fixture.name("Chess club")), None)?;
+    assert!(repository
+        .store(Some(go.version), vec![rename])
+        .await
+        .is_err_and(|e| e.is::<DuplicateCircleName>()));
+
+    store(repository, Some(chess.version), chess.disband()?).await?;
+    repository.store(None, vec![rival_created]).await?;
+    Ok(())
+}
+
+/// Names differing only in case or surrounding spaces are the same name.
+pub async fn names_ignore_case_and_surrounding_spaces(
+    repository: &(dyn CircleRepositoryInterface + Send + Sync),
+) -> Result<()> {
+    let fixture = Fixture::new();
+    let (jazz, jazz_created) = fixture.create("Jazz club", 10)?;
+    repository.store(None, vec![jazz_created]).await?;
+
+    for taken in ["jazz club", "JAZZ CLUB", "Jazz club  "] {
+        let (_, created) = fixture.create(taken, 10)?;
+        let error = repository
+            .store(None, vec![created])
+            .await
+            .expect_err("a name differing only in case or spaces should be rejected");
+        assert_eq!(
+            error.downcast_ref::<DuplicateCircleName>(),
+            Some(&DuplicateCircleName {
+                name: fixture.name(taken),
+            })
+        );
+    }
+
+    // the holder itself may change the case of its name
+    let jazz = store(
+        repository,
+        Some(jazz.version),
+        jazz.clone().update(Some(fixture.name("JAZZ club")), None)?,
+    )
+    .await?;
+    store(repository, Some(jazz.version), jazz.disband()?).await?;
+    let (_, created) = fixture.create("jazz club", 10)?;
+    repository.store(None, vec![created]).await?;
+    Ok(())
+}
+
+pub async fn reader_follows_the_stream(backend: &dyn CircleBackend) -> Result<()> {
+    let (repository, reader) = (backend.repository(), backend.reader());
+    let fixture = Fixture::new();
+    assert_eq!(reader.get_circle(CircleId::gen()).await?, None);
+
+    let (circle, created) = fixture.create("Chess club", 10)?;
+    repository.store(None, vec![created]).await?;
+    backend.settle().await?;
+    assert_eq!(
+        reader.get_circle(circle.id.clone()).await?,
+        Some(circle.clone())
+    );
+
+    let version = circle.version;
+    let updated = store(&*repository, Some(version), circle.update(None, Some(20))?).await?;
+    backend.settle().await?;
+    assert_eq!(
+        reader.get_circle(updated.id.clone()).await?,
+        Some(updated.clone())
+    );
+
+    // a disbanded circle can still be fetched but is no longer listed
+    let version = updated.version;
+    let disbanded = store(&*repository, Some(version), updated.disband()?).await?;
+    backend.settle().await?;
+    assert_eq!(
+        reader.get_circle(disbanded.id.clone()).await?,
+        Some(disbanded)
+    );
+    let query = fixture.query(CircleSortKey::Name, SortOrder::Asc);
+    assert!(names(&*reader, &query).await?.is_empty());
+    Ok(())
+}
+
+/// Stores the circles of `specs` one minute apart, in order.
+async fn create_all(
+    backend: &dyn CircleBackend,
+    fixture: &Fixture,
+    specs: &[(&str, i16)],
+) -> Result<Vec<Circle>> {
+    let repository = backend.repository();
+    let start = Utc::now().naive_utc() - Duration::hours(1);
+    let mut circles = vec![];
+    for ((name, capacity), minutes) in specs.iter().zip(0..) {
+        let (circle, mut created) = fixture.create(name, *capacity)?;
+        created.occurred_at = start + Duration::minutes(minutes);
+        repository.store(None, vec![created]).await?;
+        circles.push(circle);
+    }
+    backend.settle().await?;
+    Ok(circles)
+}
+
+/// Lists are ordered by the sort key, then by circle id.
+pub async fn list_orders_by_each_sort_key(backend: &dyn CircleBackend) -> Result<()> {
+    let reader = backend.reader();
+    let fixture = Fixture::new();
+    let circles = create_all(
+        backend,
+        &fixture,
+        &[
+            ("Chess club", 10),
+            ("Art club", 30),
+            ("Book club", 20),
+            ("Drama club", 20),
+        ],
+    )
+    .await?;
+
+    let by_name =
+        ["Art club", "Book club", "Chess club", "Drama club"].map(|name| fixture.name(name));
+    let query = fixture.query(CircleSortKey::Name, SortOrder::Asc);
+    assert_eq!(names(&*reader, &query).await?, by_name);
+    let query = fixture.query(CircleSortKey::Name, SortOrder::Desc);
+    assert_eq!(
+        names(&*reader, &query).await?,
+        by_name.iter().rev().cloned().collect::<Vec<_>>()
+    );
+
+    let by_created_at = circles
+        .iter()
+        .map(|circle| circle.name.clone())
+        .collect::<Vec<_>>();
+    let query = fixture.query(CircleSortKey::CreatedAt, SortOrder::Asc);
+    assert_eq!(names(&*reader, &query).await?, by_created_at);
+
+    let mut by_capacity = circles.clone();
+    by_capacity.sort_by_key(|circle| (circle.capacity, circle.id.to_string()));
+    let by_capacity = by_capacity
+        .into_iter()
+        .map(|circle| circle.name)
+        .collect::<Vec<_>>();
+    let query = fixture.query(CircleSortKey::Capacity, SortOrder::Asc);
+    assert_eq!(names(&*reader, &query).await?, by_capacity);
+    let query = fixture.query(CircleSortKey::Capacity, SortOrder::Desc);
+    assert_eq!(
+        names(&*reader, &query).await?,
+        by_capacity.iter().rev().cloned().collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+pub async fn list_applies_filters(backend: &dyn CircleBackend) -> Result<()> {
+    let reader = backend.reader();
+    let fixture = Fixture::new();
+    create_all(
+        backend,
+        &fixture,
+        &[
+            ("Chess club", 10),
+            ("Art club", 30),
+            ("Book club", 20),
+            ("Bridge club", 25),
+        ],
+    )
+    .await?;
+
+    for sort_key in [CircleSortKey::Name, CircleSortKey::Capacity] {
+        let query = CircleListQuery {
+            name_prefix: Some(fixture.name("B")),
+            ..fixture.query(sort_key, SortOrder::Asc)
+        };
+        assert_eq!(
+            names(&*reader, &query).await?,
+            [fixture.name("Book club"), fixture.name("Bridge club")]
+        );
+
+        let query = CircleListQuery {
+            min_capacity: Some(20),
+            max_capacity: Some(25),
+            ..fixture.query(sort_key, SortOrder::Asc)
+        };
+        assert_eq!(
+            names(&*reader, &query).await?,
+            [fixture.name("Book club"), fixture.name("Bridge club")]
+        );
+    }
+    Ok(())
+}
+
+/// Following `next_cursor` visits every circle exactly once, in list order.
+pub async fn list_pages_cover_every_circle_once(backend: &dyn CircleBackend) -> Result<()> {
+    let reader = backend.reader();
+    let fixture = Fixture::new();
+    create_all(
+        backend,
+        &fixture,
+        &[
+            ("Chess club", 10),
+            ("Art club", 30),
+            ("Book club", 20),
+            ("Drama club", 20),
+            ("Go club", 5),
+        ],
+    )
+    .await?;
+
+    for sort_key in [
+        CircleSortKey::Name,
+        CircleSortKey::Capacity,
+        CircleSortKey::CreatedAt,
+    ] {
+        for order in [SortOrder::Asc, SortOrder::Desc] {
+            let query = fixture.query(sort_key, order);
+            let expected = names(&*reader, &query).await?;
+            assert_eq!(expected.len(), 5);
+
+            let mut paged = vec![];
+            let mut cursor = None;
+            for _ in 0..=expected.len() {
+                let page = reader
+                    .list_circles(&CircleListQuery {
+                        limit: 2,
+                        cursor,
+                        ..query.clone()
+                    })
+                    .await?;
+                assert!(page.circles.len() <= 2);
+                paged.extend(page.circles.into_iter().map(|circle| circle.name));
+                cursor = page.next_cursor;
+                if cursor.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(
+                cursor, None,
+                "paging {:?} {:?} did not end",
+                sort_key, order
+            );
+            assert_eq!(paged, expected);
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_rejects_invalid_cursor(backend: &dyn CircleBackend) -> Result<()> {
+    let query = CircleListQuery {
+        cursor: Some("not a cursor".to_string()),
+        ..Fixture::new().query(CircleSortKey::Name, SortOrder::Asc)
+    };
+    let error = backend
+        .reader()
+        .list_circles(&query)
+        .await
+        .expect_err("an invalid cursor should be rejected");
+    assert!(error.is::<InvalidCursor>());
+    Ok(())
+}
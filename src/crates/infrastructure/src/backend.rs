@@ -0,0 +1,74 @@
+//! The SQL databases the event store and the command-side tables run on.
+//!
+//! The stores of this crate are generic over the sqlx [`Database`] and write their SQL
+//! once, with `?` placeholders and the column types every backend shares. A
+//! [`Backend`] supplies what differs between dialects.
+
+use std::borrow::Cow;
+
+use sqlx::{mysql::MySqlQueryResult, Database, MySql};
+
+use crate::migration::{Migration, MigrationLock, MYSQL_MIGRATIONS};
+
+pub trait Backend: Database {
+    /// SQL for the current time, in the time zone timestamp columns are written in.
+    const NOW: &'static str;
+
+    /// Statement run in the append transaction after the events are written, with
+    /// their aggregate type as its only parameter.
+    const APPENDED: Option<&'static str> = None;
+
+    /// The migrations of this database, in order.
+    const MIGRATIONS: &'static [Migration];
+
+    /// Version of the last of the [`MIGRATIONS`](Self::MIGRATIONS) that upgrade a schema
+    /// from before versioned migrations; a new database starts after it.
+    const LEGACY_UPGRADES: i64 = 0;
+
+    /// Creates `schema_migrations` when it is missing.
+    const MIGRATIONS_TABLE: &'static str;
+
+    /// How [`migrate`](crate::migration::migrate) keeps a second process out while it
+    /// applies the migrations.
+    const MIGRATION_LOCK: MigrationLock;
+
+    /// Lists the tables of the current schema as `(table_name, column_name)` rows.
+    const SCHEMA_COLUMNS: &'static str;
+
+    /// `query`, written with `?` placeholders, in the form this database expects.
+    fn sql(query: &str) -> Cow<'_, str> {
+        Cow::Borrowed(query)
+    }
+
+    /// Rows changed by a statement; sqlx has no trait for it.
+    fn rows_affected(result: &Self::QueryResult) -> u64;
+}
+
+impl Backend for MySql {
+    const NOW: &'static str = "NOW()";
+
+    const MIGRATIONS: &'static [Migration] = MYSQL_MIGRATIONS;
+
+    const LEGACY_UPGRADES: i64 = 9;
+
+    const MIGRATIONS_TABLE: &'static str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version BIGINT NOT NULL PRIMARY KEY,
+    description VARCHAR(255) NOT NULL,
+    applied_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+)";
+
+    // a named lock of the session, as the transaction ends with the first schema change
+    const MIGRATION_LOCK: MigrationLock = MigrationLock {
+        begin: "BEGIN",
+        acquire: Some("SELECT GET_LOCK('schema_migrations', 60)"),
+        release: Some("SELECT RELEASE_LOCK('schema_migrations')"),
+    };
+
+    const SCHEMA_COLUMNS: &'static str = "SELECT CAST(table_name AS CHAR) AS table_name, \
+         CAST(column_name AS CHAR) AS column_name \
+         FROM information_schema.columns WHERE table_schema = DATABASE()";
+
+    fn rows_affected(result: &MySqlQueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
@@ -1,34 +1,48 @@
 use anyhow::{Error, Result};
 use domain::{
     aggregate::circle::Circle,
-    interface::command::circle_duplicate_checker_interface::CircleDuplicateCheckerInterface,
+    interface::command::{
+        circle_duplicate_checker_interface::CircleDuplicateCheckerInterface,
+        circle_repository_interface::{name_key, DuplicateCircleName},
+    },
 };
-use sqlx::MySqlPool;
+use sqlx::{ColumnIndex, Database, Decode, Encode, Executor, IntoArguments, MySql, Pool, Type};
+
+use crate::backend::Backend;
 
 #[derive(Clone, Debug)]
-pub struct CircleDuplicateChecker {
-    db: MySqlPool,
+pub struct CircleDuplicateChecker<DB: Database = MySql> {
+    db: Pool<DB>,
 }
 
-impl CircleDuplicateChecker {
-    pub fn new(db: MySqlPool) -> Self {
+impl<DB: Database> CircleDuplicateChecker<DB> {
+    pub fn new(db: Pool<DB>) -> Self {
         Self { db }
     }
 }
 
 #[async_trait::async_trait]
-impl CircleDuplicateCheckerInterface for CircleDuplicateChecker {
+impl<DB> CircleDuplicateCheckerInterface for CircleDuplicateChecker<DB>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<DB::Row>,
+{
     async fn check_circle_duplicate(&self, circle: &Circle) -> Result<(), Error> {
-        let query = "SELECT * FROM circles WHERE name = ?";
-        let record = sqlx::query(query)
-            .bind(circle.name())
+        let query = DB::sql("SELECT circle_id FROM circle_names WHERE name = ?");
+        let owner: Option<String> = sqlx::query_scalar(&query)
+            .bind(name_key(circle.name()))
             .fetch_optional(&self.db)
             .await?;
 
-        if record.is_some() {
-            return Err(anyhow::anyhow!("Circle name already exists"));
+        match owner {
+            Some(circle_id) if circle_id != circle.id.to_string() => Err(DuplicateCircleName {
+                name: circle.name().to_string(),
+            }
+            .into()),
+            _ => Ok(()),
         }
-
-        Ok(())
     }
 }
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use anyhow::Error;
+use domain::{
+    aggregate::{
+        circle::{event::CircleEvent, Circle},
+        event_sourced::Aggregate,
+        value_object::{circle_id::CircleId, version::Version},
+    },
+    interface::query::circle_event_reader_interface::{AsOf, CircleEventReaderInterface},
+};
+
+use crate::event_store::EventLog;
+
+#[derive(Clone, Debug)]
+pub struct CircleEventReader {
+    events: Arc<dyn EventLog<Circle>>,
+}
+
+impl CircleEventReader {
+    pub fn new(events: Arc<dyn EventLog<Circle>>) -> Self {
+        Self { events }
+    }
+}
+
+#[async_trait::async_trait]
+impl CircleEventReaderInterface for CircleEventReader {
+    async fn list_events(
+        &self,
+        circle_id: &CircleId,
+        from_version: Option<Version>,
+        limit: u32,
+    ) -> Result<Vec<CircleEvent>, Error> {
+        tracing::info!("list_events: {:?}", circle_id);
+
+        self.events.read_from(circle_id, from_version, limit).await
+    }
+
+    async fn get_circle_as_of(
+        &self,
+        circle_id: &CircleId,
+        as_of: AsOf,
+    ) -> Result<Option<Circle>, Error> {
+        tracing::info!("get_circle_as_of: {:?} {:?}", circle_id, as_of);
+
+        let target_version = match as_of {
+            AsOf::Version(version) => version,
+            AsOf::Time(occurred_at) => {
+                match self.events.version_at(circle_id, occurred_at).await? {
+                    Some(version) => version,
+                    None => return Ok(None),
+                }
+            }
+        };
+        let snapshot = self
+            .events
+            .latest_snapshot(circle_id, Some(target_version))
+            .await?;
+        let snapshot_version = snapshot.as_ref().map(|circle| circle.version);
+        let events = self
+            .events
+            .load(circle_id, snapshot_version, Some(target_version))
+            .await?;
+
+        match snapshot {
+            Some(mut circle) => {
+                for event in &events {
+                    circle.apply(event);
+                }
+                Ok(Some(circle))
+            }
+            None => Ok(Circle::replay(events)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "in-memory"))]
+mod tests {
+    use std::str::FromStr;
+
+    use domain::aggregate::{circle::member::Member, value_object::member_id::MemberId};
+
+    use super::*;
+    use crate::in_memory::event_store::InMemoryEventStore;
+
+    /// A circle renamed `renames` times, with every state it went through.
+    fn history(renames: usize) -> anyhow::Result<(Vec<Circle>, Vec<CircleEvent>)> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (mut circle, created) = Circle::create("Club 1".to_string(), 10, owner)?;
+        let mut states = vec![circle.clone()];
+        let mut events = vec![created];
+        for rename in 2..=renames + 1 {
+            let (renamed, event) = circle.update(Some(format!("Club {}", rename)), None)?;
+            circle = renamed;
+            states.push(circle.clone());
+            events.push(event);
+        }
+        Ok((states, events))
+    }
+
+    fn reader(events: &InMemoryEventStore<Circle>) -> CircleEventReader {
+        CircleEventReader::new(Arc::new(events.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_as_of_version_below_the_latest_snapshot() -> anyhow::Result<()> {
+        let (states, events) = history(4)?;
+        let store = InMemoryEventStore::new();
+        store.append(None, &events)?;
+        // told apart from a replay, so the test sees which one was used
+        let mut snapshot = states[2].clone();
+        snapshot.name = "From the snapshot".to_string();
+        store.save_snapshot(&snapshot);
+        store.save_snapshot(&states[4]);
+
+        let circle = reader(&store)
+            .get_circle_as_of(&states[0].id, AsOf::Version(Version::from(4)))
+            .await?
+            .ok_or_else(|| Error::msg("the circle existed at version 4"))?;
+        assert_eq!(circle.version, Version::from(4));
+        // the snapshot at version 3, then the rename at version 4
+        assert_eq!(circle.name, "Club 4");
+        assert_eq!(circle, states[3]);
+
+        let circle = reader(&store)
+            .get_circle_as_of(&states[0].id, AsOf::Version(Version::from(3)))
+            .await?;
+        assert_eq!(circle.map(|circle| circle.name), Some(snapshot.name));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_as_of_time_before_the_first_event() -> anyhow::Result<()> {
+        let (states, events) = history(1)?;
+        let store = InMemoryEventStore::new();
+        store.append(None, &events)?;
+
+        let before = events[0].occurred_at - chrono::Duration::seconds(1);
+        let circle = reader(&store)
+            .get_circle_as_of(&states[0].id, AsOf::Time(before))
+            .await?;
+        assert_eq!(circle, None);
+        let circle = reader(&store)
+            .get_circle_as_of(&states[0].id, AsOf::Time(events[1].occurred_at))
+            .await?;
+        assert_eq!(circle.as_ref(), states.last());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_as_of_version_past_the_head() -> anyhow::Result<()> {
+        let (states, events) = history(2)?;
+        let store = InMemoryEventStore::new();
+        store.append(None, &events)?;
+        store.save_snapshot(&states[1]);
+
+        let circle = reader(&store)
+            .get_circle_as_of(&states[0].id, AsOf::Version(Version::from(10)))
+            .await?;
+        assert_eq!(circle.as_ref(), states.last());
+        Ok(())
+    }
+}
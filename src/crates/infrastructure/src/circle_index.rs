@@ -0,0 +1,151 @@
+//! Sorted-set indexes over the active circles of the Redis read model.
+//!
+//! Every index is a lexicographically ordered ZSET (all scores 0) whose members are
+//! `{sort value}\0{circle id}`, so equal sort values are ordered by circle id and a
+//! member doubles as a stable pagination cursor. Numbers are zero-padded to keep
+//! lexicographic order equal to numeric order.
+
+use chrono::NaiveDateTime;
+use domain::{
+    aggregate::circle::Circle,
+    interface::query::circle_reader_interface::{CircleListQuery, CircleSortKey, InvalidCursor},
+};
+
+pub(crate) const SORT_KEYS: [CircleSortKey; 3] = [
+    CircleSortKey::Capacity,
+    CircleSortKey::CreatedAt,
+    CircleSortKey::Name,
+];
+
+const SEPARATOR: char = '\0';
+
+pub(crate) fn entry(sort_key: CircleSortKey, circle: &Circle, created_at: NaiveDateTime) -> String {
+    let sort_value = match sort_key {
+        CircleSortKey::Capacity => capacity_value(circle.capacity),
+        CircleSortKey::CreatedAt => {
+            format!("{:015}", created_at.and_utc().timestamp_millis().max(0))
+        }
+        CircleSortKey::Name => circle.name.clone(),
+    };
+    format!("{}{}{}", sort_value, SEPARATOR, circle.id)
+}
+
+pub(crate) fn circle_id_of(entry: &str) -> Option<&str> {
+    entry.rsplit_once(SEPARATOR).map(|(_, circle_id)| circle_id)
+}
+
+fn capacity_value(capacity: i16) -> String {
+    format!("{:05}", capacity.max(0))
+}
+
+/// Inclusive `ZRANGEBYLEX` bounds of the entries matching the filters the index can answer.
+pub(crate) fn lex_range(query: &CircleListQuery) -> (Vec<u8>, Vec<u8>) {
+    // 0xff never occurs in UTF-8, so `value\xff` sorts after every entry starting with `value`
+    let bounded = |value: String| {
+        let min = [b"[", value.as_bytes()].concat();
+        let max = [b"[", value.as_bytes(), b"\xff"].concat();
+        (min, max)
+    };
+    let unbounded = (b"-".to_vec(), b"+".to_vec());
+    match query.sort_key {
+        CircleSortKey::Name => match &query.name_prefix {
+            Some(prefix) => bounded(prefix.clone()),
+            None => unbounded,
+        },
+        CircleSortKey::Capacity => {
+            let min = match query.min_capacity {
+                Some(min) => [b"[", capacity_value(min).as_bytes()].concat(),
+                None => unbounded.0,
+            };
+            let max = match query.max_capacity {
+                Some(max) => [b"[", capacity_value(max).as_bytes(), b"\xff"].concat(),
+                None => unbounded.1,
+            };
+            (min, max)
+        }
+        CircleSortKey::CreatedAt => unbounded,
+    }
+}
+
+/// Whether the circle satisfies the filters, including those the index cannot answer.
+pub(crate) fn matches(query: &CircleListQuery, circle: &Circle) -> bool {
+    query
+        .name_prefix
+        .as_ref()
+        .is_none_or(|prefix| circle.name.starts_with(prefix.as_str()))
+        && query.min_capacity.is_none_or(|min| circle.capacity >= min)
+        && query.max_capacity.is_none_or(|max| circle.capacity <= max)
+}
+
+pub(crate) fn encode_cursor(entry: &str) -> String {
+    entry.bytes().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn decode_cursor(cursor: &str) -> Result<String, InvalidCursor> {
+    if !cursor.len().is_multiple_of(2) {
+        return Err(InvalidCursor);
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| {
+            cursor
+                .get(i..i + 2)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or(InvalidCursor)
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+    let entry = String::from_utf8(bytes).map_err(|_| InvalidCursor)?;
+    circle_id_of(&entry).ok_or(InvalidCursor)?;
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use domain::aggregate::{circle::member::Member, value_object::member_id::MemberId};
+
+    use super::*;
+
+    fn circle(name: &str, capacity: i16) -> anyhow::Result<Circle> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        Ok(Circle::create(name.to_string(), capacity, owner)?.0)
+    }
+
+    #[test]
+    fn test_capacity_entries_sort_numerically() -> anyhow::Result<()> {
+        let created_at = NaiveDateTime::default();
+        let small = entry(CircleSortKey::Capacity, &circle("a", 9)?, created_at);
+        let large = entry(CircleSortKey::Capacity, &circle("b", 10)?, created_at);
+        assert!(small < large);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor_round_trip() -> anyhow::Result<()> {
+        let circle = circle("Music club", 10)?;
+        let entry = entry(CircleSortKey::Name, &circle, NaiveDateTime::default());
+        let decoded = decode_cursor(&encode_cursor(&entry))?;
+        assert_eq!(decoded, entry);
+        assert_eq!(circle_id_of(&decoded), Some(circle.id.to_string().as_str()));
+        assert!(decode_cursor("not a cursor").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_name_prefix_range_covers_matching_entries() -> anyhow::Result<()> {
+        let query = CircleListQuery {
+            sort_key: CircleSortKey::Name,
+            name_prefix: Some("Music".to_string()),
+            ..CircleListQuery::default()
+        };
+        let (min, max) = lex_range(&query);
+        let entry = entry(
+            CircleSortKey::Name,
+            &circle("Music club", 10)?,
+            NaiveDateTime::default(),
+        );
+        assert!(min[1..] <= *entry.as_bytes() && *entry.as_bytes() <= max[1..]);
+        Ok(())
+    }
+}
@@ -1,12 +1,32 @@
-use std::str::FromStr;
-
 use anyhow::Error;
 use domain::{
     aggregate::{circle::Circle, value_object::circle_id::CircleId},
-    interface::query::circle_reader_interface::CircleReaderInterface,
+    interface::query::circle_reader_interface::{
+        CircleListQuery, CirclePage, CircleReaderInterface, SortOrder,
+    },
 };
 use redis::{AsyncCommands, Client};
 
+use crate::{circle_index, redis_keys::RedisKeys};
+
+/// Number of index entries read from Redis per round trip while filling a page.
+const SCAN_BATCH_SIZE: usize = 200;
+
+/// Pairs every index entry with the key of its circle, skipping malformed entries so
+/// the rest stay paired with their own circle.
+fn indexed_circles<'a>(keys: &RedisKeys, entries: &'a [String]) -> Vec<(&'a String, String)> {
+    entries
+        .iter()
+        .filter_map(|entry| match circle_index::circle_id_of(entry) {
+            Some(circle_id) => Some((entry, keys.circle(circle_id))),
+            None => {
+                tracing::warn!("Skipping malformed circle index entry {:?}", entry);
+                None
+            }
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct CircleReader {
     client: Client,
@@ -18,11 +38,7 @@ impl CircleReader {
     }
 
     fn circle_key(&self, circle_id: &CircleId) -> String {
-        format!("circle:{}", circle_id.to_string())
-    }
-
-    fn circles_list_key(&self) -> String {
-        "circles:list".to_string()
+        RedisKeys::live().circle(&circle_id.to_string())
     }
 }
 
@@ -30,8 +46,9 @@ impl CircleReader {
 impl CircleReaderInterface for CircleReader {
     async fn get_circle(&self, circle_id: CircleId) -> Result<Option<Circle>, Error> {
         tracing::info!("find_circle_by_id from Redis: {:?}", circle_id);
-        
-        let mut conn = self.client
+
+        let mut conn = self
+            .client
             .get_multiplexed_async_connection()
             .await
             .map_err(|e| anyhow::Error::msg(format!("Failed to connect to Redis: {}", e)))?;
@@ -44,38 +61,123 @@ impl CircleReaderInterface for CircleReader {
 
         match json_data {
             Some(data) => {
-                let circle: Circle = serde_json::from_str(&data)
-                    .map_err(|e| anyhow::Error::msg(format!("Failed to deserialize circle: {}", e)))?;
+                let circle: Circle = serde_json::from_str(&data).map_err(|e| {
+                    anyhow::Error::msg(format!("Failed to deserialize circle: {}", e))
+                })?;
                 Ok(Some(circle))
             }
             None => Ok(None),
         }
     }
 
-    async fn list_circles(&self) -> Result<Vec<Circle>, Error> {
-        tracing::info!("list_circles from Redis");
-        
-        let mut conn = self.client
+    async fn list_circles(&self, query: &CircleListQuery) -> Result<CirclePage, Error> {
+        tracing::info!("list_circles from Redis: {:?}", query);
+
+        let mut conn = self
+            .client
             .get_multiplexed_async_connection()
             .await
             .map_err(|e| anyhow::Error::msg(format!("Failed to connect to Redis: {}", e)))?;
 
-        let circle_ids: Vec<String> = conn
-            .smembers(self.circles_list_key())
-            .await
-            .map_err(|e| anyhow::Error::msg(format!("Failed to get circle list from Redis: {}", e)))?;
+        let keys = RedisKeys::live();
+        let index = keys.index(query.sort_key);
+        let (min, max) = circle_index::lex_range(query);
+        // the cursor is the last entry of the previous page, so the next page starts right after it
+        let mut start = match &query.cursor {
+            Some(cursor) => [b"(", circle_index::decode_cursor(cursor)?.as_bytes()].concat(),
+            None => match query.order {
+                SortOrder::Asc => min.clone(),
+                SortOrder::Desc => max.clone(),
+            },
+        };
 
+        let limit = query.limit as usize;
         let mut circles = Vec::new();
-        
-        for circle_id_str in circle_ids {
-            let circle_id = CircleId::from_str(&circle_id_str)
-                .map_err(|e| anyhow::Error::msg(format!("Invalid circle ID: {}", e)))?;
-            
-            if let Some(circle) = self.get_circle(circle_id).await? {
+        loop {
+            let (command, end) = match query.order {
+                SortOrder::Asc => ("ZRANGEBYLEX", &max),
+                SortOrder::Desc => ("ZREVRANGEBYLEX", &min),
+            };
+            let entries: Vec<String> = redis::cmd(command)
+                .arg(&index)
+                .arg(&start)
+                .arg(end)
+                .arg("LIMIT")
+                .arg(0)
+                .arg(SCAN_BATCH_SIZE)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| {
+                    anyhow::Error::msg(format!("Failed to read circle index from Redis: {}", e))
+                })?;
+            let Some(last_entry) = entries.last() else {
+                break;
+            };
+
+            let indexed = indexed_circles(&keys, &entries);
+            // MGET without keys is an error
+            let json_data: Vec<Option<String>> = if indexed.is_empty() {
+                Vec::new()
+            } else {
+                redis::cmd("MGET")
+                    .arg(indexed.iter().map(|(_, key)| key).collect::<Vec<_>>())
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| {
+                        anyhow::Error::msg(format!("Failed to get circles from Redis: {}", e))
+                    })?
+            };
+
+            for ((entry, _), data) in indexed.into_iter().zip(json_data) {
+                let Some(data) = data else {
+                    continue;
+                };
+                let circle: Circle = serde_json::from_str(&data).map_err(|e| {
+                    anyhow::Error::msg(format!("Failed to deserialize circle: {}", e))
+                })?;
+                if !circle_index::matches(query, &circle) {
+                    continue;
+                }
                 circles.push(circle);
+                if circles.len() == limit {
+                    return Ok(CirclePage {
+                        circles,
+                        next_cursor: Some(circle_index::encode_cursor(entry)),
+                    });
+                }
+            }
+
+            if entries.len() < SCAN_BATCH_SIZE {
+                break;
             }
+            start = [b"(", last_entry.as_bytes()].concat();
         }
 
-        Ok(circles)
+        Ok(CirclePage {
+            circles,
+            next_cursor: None,
+        })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_malformed_entries_do_not_shift_the_others() {
+        let keys = RedisKeys::live();
+        let entries = vec![
+            "Book club\0book".to_string(),
+            "malformed".to_string(),
+            "Music club\0music".to_string(),
+        ];
+        assert_eq!(
+            indexed_circles(&keys, &entries),
+            vec![
+                (&entries[0], keys.circle("book")),
+                (&entries[2], keys.circle("music")),
+            ]
+        );
+    }
+}
@@ -1,280 +1,190 @@
-use std::str::FromStr;
+use std::sync::Arc;
 
+use chrono::NaiveDateTime;
 use domain::{
     aggregate::{
         circle::{
             event::{self, CircleEvent},
             Circle,
         },
-        value_object::{
-            circle_id::CircleId,
-            event_id::EventId,
-            version::{self, Version},
-        },
+        value_object::{circle_id::CircleId, event_metadata::EventMetadata, version::Version},
+    },
+    interface::command::circle_repository_interface::{
+        name_key, CircleNotFound, CircleRepositoryInterface, DuplicateCircleName, VersionConflict,
     },
-    interface::command::circle_repository_interface::CircleRepositoryInterface,
 };
 
-use crate::maria_db_schema::{
-    circle_snapshot_data::State, CircleEventData, CircleSnapshotData,
+use sqlx::{
+    types::Json, ColumnIndex, Database, Decode, Encode, Executor, FromRow, IntoArguments, MySql,
+    Pool, Type,
 };
 
-const SNAPSHOT_INTERVAL: i32 = 5;
-
-use crate::event_publisher::EventPublisher;
-use std::sync::Arc;
+use crate::{
+    backend::Backend,
+    event_store::WrongExpectedVersion,
+    maria_db_schema::{SnapshotData, StoredEventData},
+    repository::{AppendHook, Repository},
+    snapshot_policy::SnapshotPolicy,
+};
 
 #[derive(Clone, Debug)]
-pub struct CircleRepository {
-    db: sqlx::MySqlPool,
-    event_publisher: Arc<dyn EventPublisher>,
+pub struct CircleRepository<DB: Database = MySql> {
+    repository: Repository<Circle, DB>,
 }
 
-impl CircleRepository {
-    pub fn new(db: sqlx::MySqlPool, event_publisher: Arc<dyn EventPublisher>) -> Self {
-        Self { 
-            db, 
-            event_publisher,
+impl<DB> CircleRepository<DB>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+{
+    pub fn new(db: Pool<DB>) -> Self {
+        Self {
+            repository: Repository::new(db).with_hook(Arc::new(CircleNameReservation)),
         }
     }
 
-    async fn get_latest_snapshot(
-        &self,
-        circle_id: &CircleId,
-    ) -> Result<Option<(Circle, Version)>, anyhow::Error> {
-        let query = sqlx::query(
-            "SELECT * FROM circle_snapshots WHERE circle_id = ? ORDER BY version DESC LIMIT 1",
-        )
-        .bind(circle_id.to_string());
-
-        let row = match query.fetch_optional(&self.db).await {
-            Ok(Some(row)) => row,
-            Ok(None) => return Ok(None),
-            Err(e) => {
-                tracing::error!("Failed to fetch snapshot: {:?}", e);
-                return Err(anyhow::Error::msg("Failed to fetch circle snapshot"));
-            }
-        };
-
-        let snapshot = CircleSnapshotData::from_row(&row);
-        let circle = snapshot.state.to_circle()?;
-        let version = Version::try_from(snapshot.version)
-            .map_err(|_| anyhow::Error::msg("Failed to convert version from i32"))?;
-
-        Ok(Some((circle, version)))
+    pub fn with_snapshot_policy(self, policy: Arc<dyn SnapshotPolicy>) -> Self {
+        Self {
+            repository: self.repository.with_snapshot_policy(policy),
+        }
     }
 
-    async fn save_snapshot(&self, circle: &Circle) -> Result<(), anyhow::Error> {
-        let circle_id = circle.id.to_string();
-        let version: i32 = circle.version.try_into().map_err(|_| {
-            tracing::error!("Failed to convert version to i32");
-            anyhow::Error::msg("Failed to convert version to i32")
-        })?;
-        let state = State::from_circle(circle).map_err(|e| {
-            tracing::error!("Failed to convert circle to state: {:?}", e);
-            anyhow::Error::msg("Failed to convert circle to state")
-        })?;
-
-        sqlx::query(
-            "INSERT INTO circle_snapshots (circle_id, version, state) 
-             VALUES (?, ?, ?)",
-        )
-        .bind(&circle_id)
-        .bind(version)
-        .bind(&sqlx::types::Json(state)) // Json型に明示的に変換
-        .execute(&self.db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to save snapshot: {:?}", e);
-            anyhow::Error::msg("Failed to save circle snapshot")
-        })?;
-
-        tracing::info!(
-            "Saved snapshot for circle {} at version {}",
-            circle_id,
-            version
-        );
-        Ok(())
+    /// Keeps only the `keep` newest snapshots of each circle.
+    pub fn with_snapshot_retention(self, keep: u32) -> Self {
+        Self {
+            repository: self.repository.with_snapshot_retention(keep),
+        }
     }
 }
 
-#[async_trait::async_trait]
-impl CircleRepositoryInterface for CircleRepository {
-    async fn find_by_id(&self, circle_id: &CircleId) -> Result<Circle, anyhow::Error> {
-        tracing::info!("find_circle_by_id : {:?}", circle_id);
-
-        // check snapshot
-        if let Ok(Some((mut circle, snapshot_version))) = self.get_latest_snapshot(circle_id).await
-        {
-            tracing::info!(
-                "Found snapshot for circle {:?} at version {:?}",
-                circle_id,
-                snapshot_version
-            );
+/// Keeps `circle_names` in step with the stream so names stay unique without a race window.
+#[derive(Debug)]
+struct CircleNameReservation;
 
-            let version_i32: i32 = snapshot_version.try_into().map_err(|_| {
-                tracing::error!("Failed to convert version to i32");
-                anyhow::Error::msg("Failed to convert version to i32")
-            })?;
-            let event_query = sqlx::query(
-                "SELECT * FROM circle_events WHERE circle_id = ? AND version > ? ORDER BY version ASC"
-            )
-            .bind(circle_id.to_string())
-            .bind(version_i32);
+impl CircleNameReservation {
+    async fn maintain_circle_name<DB>(
+        connection: &mut DB::Connection,
+        event: &CircleEvent,
+    ) -> Result<(), anyhow::Error>
+    where
+        DB: Backend,
+        for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+        for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'q> String: Encode<'q, DB> + Type<DB>,
+    {
+        let name = match &event.data {
+            event::EventData::CircleCreated(event::CircleCreated { name, .. }) => Some(name),
+            event::EventData::CircleUpdated(event::CircleUpdated {
+                name: Some(name), ..
+            }) => Some(name),
+            // a disbanded circle gives its name back
+            event::EventData::CircleDisbanded(_) => None,
+            _ => return Ok(()),
+        };
 
-            let event_rows = event_query.fetch_all(&self.db).await.map_err(|e| {
-                tracing::error!("Failed to fetch circle events after snapshot: {:?}", e);
-                anyhow::Error::msg("Failed to fetch circle events after snapshot")
+        sqlx::query(&DB::sql("DELETE FROM circle_names WHERE circle_id = ?"))
+            .bind(event.circle_id.to_string())
+            .execute(&mut *connection)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to release circle name: {:?}", e);
+                anyhow::Error::msg("Failed to release circle name")
             })?;
 
-            println!("event_rows: {:?}", event_rows);
-
-            if !event_rows.is_empty() {
-                let events = event_rows
-                    .iter()
-                    .map(|row| CircleEvent::from_circle_event_data(CircleEventData::from_row(row)))
-                    .collect::<Result<Vec<CircleEvent>, _>>()?;
-
-                for event in events {
-                    circle.apply_event(&event);
+        if let Some(name) = name {
+            sqlx::query(&DB::sql(
+                "INSERT INTO circle_names (name, circle_id) VALUES (?, ?)",
+            ))
+            .bind(name_key(name))
+            .bind(event.circle_id.to_string())
+            .execute(&mut *connection)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(db_error) if db_error.is_unique_violation() => {
+                    DuplicateCircleName { name: name.clone() }.into()
                 }
-            }
-
-            return Ok(circle);
-        }
-
-        let event_query =
-            sqlx::query("SELECT * FROM circle_events WHERE circle_id = ? ORDER BY version ASC")
-                .bind(circle_id.to_string());
-        let event_rows = event_query.fetch_all(&self.db).await.map_err(|e| {
-            eprintln!("Failed to fetch circle events by circle_id: {:?}", e);
-            anyhow::Error::msg("Failed to fetch circle events by circle_id")
-        })?;
-
-        if event_rows.is_empty() {
-            return Err(anyhow::Error::msg("Circle not found"));
+                e => {
+                    tracing::error!("Failed to reserve circle name: {:?}", e);
+                    anyhow::Error::msg("Failed to reserve circle name")
+                }
+            })?;
         }
-
-        let event_data = event_rows
-            .iter()
-            .map(|row| CircleEvent::from_circle_event_data(CircleEventData::from_row(row)))
-            .collect::<Result<Vec<CircleEvent>, _>>()?;
-
-        let mut event_data = event_data;
-        event_data.sort_by(|a, b| a.version.cmp(&b.version));
-        let circle = Circle::replay(event_data);
-
-        Ok(circle)
+        Ok(())
     }
+}
 
-    async fn store(
+#[async_trait::async_trait]
+impl<DB> AppendHook<Circle, DB> for CircleNameReservation
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+{
+    async fn on_append(
         &self,
-        _version: Option<version::Version>,
-        events: Vec<event::CircleEvent>,
+        connection: &mut DB::Connection,
+        events: &[CircleEvent],
     ) -> Result<(), anyhow::Error> {
-        if events.is_empty() {
-            tracing::info!("No events to store");
-            return Ok(());
-        }
-
-        let events_for_logging = events.clone();
-
-        // Step 1: Store events in MySQL (this is the source of truth)
-        {
-            let mut transaction = self.db.begin().await?;
-
-            for event in events {
-                let event_data = CircleEventData::try_from(event.clone())?;
-
-                sqlx::query("INSERT INTO circle_events (circle_id, id, occurred_at, event_type, version, payload) VALUES (?, ?, ?, ?, ?, ?)")
-                .bind(event_data.circle_id.clone())
-                .bind(event_data.id)
-                .bind(event_data.occurred_at)
-                .bind(event_data.event_type.clone())
-                .bind(event_data.version)
-                .bind(event_data.payload.clone())
-                .execute(&mut *transaction)
-                .await.map_err(|e| {
-                    eprintln!("Failed to insert circle event: {:?}", e);
-                    anyhow::Error::msg("Failed to insert circle event")
-                })?;
-            }
-
-            transaction.commit().await?;
-        }
-
-        let first_event = events_for_logging
-            .first()
-            .ok_or_else(|| anyhow::Error::msg("No events found"))?;
-        let mut current_circle = self.find_by_id(&first_event.circle_id).await?;
-
-        for event in &events_for_logging {
-            current_circle.apply_event(event);
-        }
-
-        // Save snapshot if needed
-        let version_i32: i32 = current_circle.version.try_into().map_err(|e| {
-            anyhow::Error::msg(format!("Failed to convert version to i32: {:?}", e))
-        })?;
-        if version_i32 % SNAPSHOT_INTERVAL == 0 {
-            if let Err(e) = self.save_snapshot(&current_circle).await {
-                tracing::error!("Failed to save snapshot: {:?}", e);
-            } else {
-                tracing::info!("Snapshot saved for circle at version {}", version_i32);
-            }
-        }
-
-        // Step 2: Update Redis asynchronously via event-driven approach
-        if let Err(e) = self.event_publisher.publish(events_for_logging.clone()).await {
-            tracing::error!("Failed to publish events for Redis update: {:?}", e);
+        for event in events {
+            Self::maintain_circle_name::<DB>(connection, event).await?;
         }
-
-        tracing::info!("Stored circle events: {:?}", events_for_logging);
         Ok(())
     }
 }
 
-pub(crate) trait EventExt {
-    fn from_circle_event_data(event_data: CircleEventData) -> Result<Self, anyhow::Error>
-    where
-        Self: Sized;
-}
+#[async_trait::async_trait]
+impl<DB> CircleRepositoryInterface for CircleRepository<DB>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB> + ColumnIndex<DB::Row>,
+    for<'q> String: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> NaiveDateTime: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> Json<serde_json::Value>: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<Json<EventMetadata>>: Encode<'q, DB> + Type<DB>,
+    for<'r> StoredEventData: FromRow<'r, DB::Row>,
+    for<'r> SnapshotData<Circle>: FromRow<'r, DB::Row>,
+    usize: ColumnIndex<DB::Row>,
+{
+    async fn find_by_id(&self, circle_id: &CircleId) -> Result<Circle, anyhow::Error> {
+        tracing::info!("find_circle_by_id : {:?}", circle_id);
 
-impl EventExt for CircleEvent {
-    fn from_circle_event_data(v: CircleEventData) -> Result<Self, anyhow::Error> {
-        let event: event::EventData = serde_json::from_str(&v.payload.to_string())?;
-        Ok(Self {
-            id: EventId::from_str(&v.id)?,
-            circle_id: CircleId::from_str(&v.circle_id)?,
-            version: Version::try_from(v.version)
-                .map_err(|_| anyhow::Error::msg("Failed to convert version from string"))?,
-            data: event,
-            occurred_at: v.occurred_at,
+        self.repository.load(circle_id).await?.ok_or_else(|| {
+            CircleNotFound {
+                circle_id: circle_id.clone(),
+            }
+            .into()
         })
     }
-}
 
-// event -> circle_event_data
-impl TryFrom<event::CircleEvent> for CircleEventData {
-    type Error = anyhow::Error;
-    fn try_from(value: event::CircleEvent) -> Result<Self, Self::Error> {
-        let event_type = match value.data.clone() {
-            event::EventData::CircleCreated(_) => "circle_created",
-            event::EventData::CircleUpdated(_) => "circle_updated",
+    async fn store(
+        &self,
+        current_version: Option<Version>,
+        events: Vec<CircleEvent>,
+    ) -> Result<(), anyhow::Error> {
+        let Some(circle_id) = events.first().map(|event| event.circle_id.clone()) else {
+            tracing::info!("No events to store");
+            return Ok(());
         };
 
-        let event_data = CircleEventData {
-            circle_id: value.circle_id.to_string(),
-            event_type: event_type.to_string(),
-            id: value.id.to_string(),
-            occurred_at: value.occurred_at,
-            payload: sqlx::types::Json(serde_json::to_value(value.data)?),
-            version: value
-                .version
-                .try_into()
-                .map_err(|_| anyhow::Error::msg("Failed to convert version to i32"))?,
-        };
-        Ok(event_data)
+        self.repository
+            .save(current_version, &events)
+            .await
+            .map_err(|e| match e.downcast::<WrongExpectedVersion>() {
+                Ok(WrongExpectedVersion { expected, actual }) => VersionConflict {
+                    circle_id,
+                    expected,
+                    actual,
+                }
+                .into(),
+                Err(e) => e,
+            })
     }
 }
 
@@ -1,111 +1,324 @@
+use std::sync::Arc;
+
 use anyhow::Result;
-use domain::aggregate::circle::event::CircleEvent;
-use tokio::sync::mpsc;
+use domain::aggregate::{
+    circle::{event::CircleEvent, Circle},
+    event_sourced::Aggregate,
+    value_object::{circle_id::CircleId, version::Version},
+};
+
+use chrono::NaiveDateTime;
 use redis::AsyncCommands;
 
-#[async_trait::async_trait]
-pub trait EventPublisher: Send + Sync + std::fmt::Debug {
-    async fn publish(&self, events: Vec<CircleEvent>) -> Result<()>;
-}
+use crate::{
+    circle_index, event_store::EventLog, redis_keys::RedisKeys, subscription::EventHandler,
+};
 
-#[derive(Debug)]
-pub struct InMemoryEventPublisher {
-    sender: mpsc::UnboundedSender<CircleEvent>,
+/// A circle as stored in the read model, with when its stream started for the
+/// `created_at` index. Readers deserialise the same JSON as a plain [`Circle`].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct ProjectedCircle {
+    #[serde(flatten)]
+    pub(crate) circle: Circle,
+    pub(crate) created_at: NaiveDateTime,
 }
 
-impl InMemoryEventPublisher {
-    pub fn new() -> (Self, mpsc::UnboundedReceiver<CircleEvent>) {
-        let (sender, receiver) = mpsc::unbounded_channel();
-        (Self { sender }, receiver)
-    }
+/// What an event does to the projection of its circle.
+#[derive(Debug, PartialEq)]
+enum Step {
+    Skip,
+    Save(ProjectedCircle),
+    Rebuild,
 }
 
-#[async_trait::async_trait]
-impl EventPublisher for InMemoryEventPublisher {
-    async fn publish(&self, events: Vec<CircleEvent>) -> Result<()> {
-        for event in events {
-            self.sender.send(event)
-                .map_err(|_| anyhow::Error::msg("Failed to send event"))?;
+fn next_step(projected: Option<&ProjectedCircle>, event: &CircleEvent) -> Result<Step> {
+    Ok(match projected {
+        // delivered again after its checkpoint was not saved, or already covered by a repair
+        Some(projected) if projected.circle.version >= event.version => Step::Skip,
+        Some(projected) if projected.circle.version.next() == event.version => {
+            let mut projected = projected.clone();
+            projected.circle.apply(event);
+            Step::Save(projected)
         }
-        Ok(())
-    }
+        None if event.version == Version::new() => {
+            let circle = Circle::initial(event).ok_or_else(|| {
+                anyhow::Error::msg("Circle stream does not start with circle_created")
+            })?;
+            Step::Save(ProjectedCircle {
+                circle,
+                created_at: event.occurred_at,
+            })
+        }
+        _ => Step::Rebuild,
+    })
 }
 
+/// Keeps the Redis read model in step with the circle streams.
+///
+/// An event one version ahead of the projected circle is applied to it directly.
+/// Anything else means the projection missed events, so the circle is rebuilt from
+/// its latest snapshot and the events after it, which also repairs the gap.
 #[derive(Debug)]
 pub struct RedisProjectionHandler {
     redis_client: redis::Client,
-    db: sqlx::MySqlPool,
+    events: Arc<dyn EventLog<Circle>>,
 }
 
 impl RedisProjectionHandler {
-    pub fn new(redis_client: redis::Client, db: sqlx::MySqlPool) -> Self {
-        Self { redis_client, db }
+    pub fn new(redis_client: redis::Client, events: Arc<dyn EventLog<Circle>>) -> Self {
+        Self {
+            redis_client,
+            events,
+        }
     }
 
-    pub async fn handle_event(&self, event: CircleEvent) -> Result<()> {
+    pub async fn handle_event(&self, event: &CircleEvent) -> Result<()> {
         tracing::info!("Handling event for Redis projection: {:?}", event.circle_id);
-        
-        let circle = self.rebuild_circle_from_events(&event.circle_id).await?;
-        
-        self.save_circle_to_redis(&circle).await?;
-        
+
+        let keys = RedisKeys::live();
+        let previous = self.projected_circle(&keys, &event.circle_id).await?;
+        let projected = match next_step(previous.as_ref(), event)? {
+            Step::Skip => return Ok(()),
+            Step::Save(projected) => projected,
+            Step::Rebuild => {
+                tracing::warn!(
+                    "Projection of circle {} is at version {:?} but received version {:?}; rebuilding it",
+                    event.circle_id,
+                    previous.as_ref().map(|projected| projected.circle.version),
+                    event.version
+                );
+                self.rebuild_circle_from_events(&event.circle_id).await?
+            }
+        };
+
+        self.save_circle_to_redis(&keys, &projected, previous.as_ref())
+            .await?;
+
         Ok(())
     }
 
-    async fn rebuild_circle_from_events(&self, circle_id: &domain::aggregate::value_object::circle_id::CircleId) -> Result<domain::aggregate::circle::Circle> {
-        use crate::maria_db_schema::CircleEventData;
-        use crate::circle_repository::EventExt;
-        
-        // fetch all events for the Circle ID from MySQL
-        let query = sqlx::query(
-            "SELECT * FROM circle_events WHERE circle_id = ? ORDER BY version ASC"
-        ).bind(circle_id.to_string());
-        
-        let rows = query.fetch_all(&self.db).await
-            .map_err(|e| anyhow::Error::msg(format!("Failed to fetch events: {}", e)))?;
-        
-        if rows.is_empty() {
-            return Err(anyhow::Error::msg("No events found for circle"));
+    /// Loads the circle from its latest snapshot and the events after it, with when
+    /// its first event occurred.
+    pub(crate) async fn rebuild_circle_from_events(
+        &self,
+        circle_id: &CircleId,
+    ) -> Result<ProjectedCircle> {
+        let circle = self
+            .events
+            .load_aggregate(circle_id)
+            .await?
+            .ok_or_else(|| {
+                anyhow::Error::msg("Circle stream does not start with circle_created")
+            })?;
+        let created_at = self.created_at(circle_id).await?;
+        Ok(ProjectedCircle { circle, created_at })
+    }
+
+    async fn created_at(&self, circle_id: &CircleId) -> Result<NaiveDateTime> {
+        self.events
+            .read_from(circle_id, None, 1)
+            .await?
+            .first()
+            .map(|event| event.occurred_at)
+            .ok_or_else(|| anyhow::Error::msg("No events found for circle"))
+    }
+
+    /// The circle as currently projected, or `None` when it is missing or unreadable.
+    pub(crate) async fn projected_circle(
+        &self,
+        keys: &RedisKeys,
+        circle_id: &CircleId,
+    ) -> Result<Option<ProjectedCircle>> {
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("Failed to connect to Redis: {}", e)))?;
+        let json: Option<String> = conn
+            .get(keys.circle(&circle_id.to_string()))
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("Failed to get circle from Redis: {}", e)))?;
+        let Some(json) = json else {
+            return Ok(None);
+        };
+        if let Ok(projected) = serde_json::from_str::<ProjectedCircle>(&json) {
+            return Ok(Some(projected));
+        }
+        // projected before `created_at` was stored with the circle
+        match serde_json::from_str::<Circle>(&json) {
+            Ok(circle) => {
+                let created_at = self.created_at(circle_id).await?;
+                Ok(Some(ProjectedCircle { circle, created_at }))
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Ignoring unreadable projection of circle {}: {:?}",
+                    circle_id,
+                    e
+                );
+                Ok(None)
+            }
         }
-        
-        // Build event data
-        let events = rows.iter()
-            .map(|row| {
-                let event_data = CircleEventData::from_row(row);
-                CircleEvent::from_circle_event_data(event_data)
-            })
-            .collect::<Result<Vec<CircleEvent>, _>>()?;
-        
-        // Rebuild Circle
-        Ok(domain::aggregate::circle::Circle::replay(events))
     }
 
-    async fn save_circle_to_redis(&self, circle: &domain::aggregate::circle::Circle) -> Result<()> {
-        let mut conn = self.redis_client.get_multiplexed_async_connection().await
+    /// Saves `projected` and moves its index entries away from those of `previous`, the
+    /// state it replaces in `keys`.
+    pub(crate) async fn save_circle_to_redis(
+        &self,
+        keys: &RedisKeys,
+        projected: &ProjectedCircle,
+        previous: Option<&ProjectedCircle>,
+    ) -> Result<()> {
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
             .map_err(|e| anyhow::Error::msg(format!("Failed to connect to Redis: {}", e)))?;
-        
+
+        let circle = &projected.circle;
         let circle_id_str = circle.id.to_string();
-        let circle_json = serde_json::to_string(circle)
+        let circle_json = serde_json::to_string(projected)
             .map_err(|e| anyhow::Error::msg(format!("Failed to serialize circle: {}", e)))?;
-        
-        // Save Circle data
-        let _: () = conn.set(format!("circle:{}", circle_id_str), circle_json).await
+
+        // Save Circle data and keep it listed and indexed only while it is active
+        let mut pipe = redis::pipe();
+        pipe.atomic().set(keys.circle(&circle_id_str), circle_json);
+        for sort_key in circle_index::SORT_KEYS {
+            // index entries of the previous state go stale when the circle is renamed or resized
+            if let Some(previous) = previous {
+                pipe.zrem(
+                    keys.index(sort_key),
+                    circle_index::entry(sort_key, &previous.circle, previous.created_at),
+                );
+            }
+            if !circle.is_disbanded() {
+                pipe.zadd(
+                    keys.index(sort_key),
+                    circle_index::entry(sort_key, circle, projected.created_at),
+                    0,
+                );
+            }
+        }
+        if circle.is_disbanded() {
+            pipe.srem(keys.list(), &circle_id_str)
+                .sadd(keys.archived(), &circle_id_str);
+        } else {
+            pipe.sadd(keys.list(), &circle_id_str);
+        }
+        let _: () = pipe
+            .query_async(&mut conn)
+            .await
             .map_err(|e| anyhow::Error::msg(format!("Failed to save circle to Redis: {}", e)))?;
-        
-        // Add Circle ID to list
-        let _: () = conn.sadd("circles:list", &circle_id_str).await
-            .map_err(|e| anyhow::Error::msg(format!("Failed to add to circles list: {}", e)))?;
-        
+
         tracing::info!("Successfully saved circle {} to Redis", circle_id_str);
         Ok(())
     }
+}
 
-    pub async fn start_processing(&self, mut receiver: mpsc::UnboundedReceiver<CircleEvent>) {
-        while let Some(event) = receiver.recv().await {
-            if let Err(e) = self.handle_event(event).await {
-                tracing::error!("Failed to process event for Redis: {:?}", e);
-                // Error handling: retry logic or dead-letter queue, etc.
-            }
+#[async_trait::async_trait]
+impl EventHandler<Circle> for RedisProjectionHandler {
+    async fn handle(&self, event: &CircleEvent) -> Result<()> {
+        self.handle_event(event).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use domain::aggregate::{circle::member::Member, value_object::member_id::MemberId};
+
+    use super::*;
+
+    /// The events of a circle renamed `renames` times.
+    fn history(renames: usize) -> Result<(Circle, Vec<CircleEvent>)> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (mut circle, created) = Circle::create("Club 1".to_string(), 10, owner)?;
+        let mut events = vec![created];
+        for rename in 2..=renames + 1 {
+            let (renamed, event) = circle.update(Some(format!("Club {}", rename)), None)?;
+            circle = renamed;
+            events.push(event);
         }
+        Ok((circle, events))
+    }
+
+    fn projected_at(events: &[CircleEvent]) -> Result<ProjectedCircle> {
+        let circle =
+            Circle::replay(events.to_vec()).ok_or_else(|| anyhow::Error::msg("no circle"))?;
+        Ok(ProjectedCircle {
+            circle,
+            created_at: events[0].occurred_at,
+        })
+    }
+
+    #[test]
+    fn test_next_event_is_applied() -> Result<()> {
+        let (_, events) = history(2)?;
+        assert_eq!(
+            next_step(None, &events[0])?,
+            Step::Save(projected_at(&events[..1])?)
+        );
+
+        let projected = projected_at(&events[..2])?;
+        // the creation time is carried over rather than read from the log
+        assert_eq!(
+            next_step(Some(&projected), &events[2])?,
+            Step::Save(projected_at(&events)?)
+        );
+        Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_event_already_projected_is_skipped() -> Result<()> {
+        let (_, events) = history(2)?;
+        let projected = projected_at(&events[..2])?;
+        assert_eq!(next_step(Some(&projected), &events[1])?, Step::Skip);
+        assert_eq!(next_step(Some(&projected), &events[0])?, Step::Skip);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gap_is_rebuilt() -> Result<()> {
+        let (_, events) = history(3)?;
+        let projected = projected_at(&events[..2])?;
+        assert_eq!(next_step(Some(&projected), &events[3])?, Step::Rebuild);
+        assert_eq!(next_step(None, &events[2])?, Step::Rebuild);
+        Ok(())
+    }
+
+    #[cfg(feature = "in-memory")]
+    #[tokio::test]
+    async fn test_rebuild_reads_the_circle_and_its_creation_time() -> Result<()> {
+        use crate::in_memory::event_store::InMemoryEventStore;
+
+        let (circle, events) = history(3)?;
+        let store = InMemoryEventStore::new();
+        store.append(None, &events)?;
+        // never connected to, as a rebuild only reads the log
+        let handler = RedisProjectionHandler::new(
+            redis::Client::open("redis://127.0.0.1/")?,
+            Arc::new(store),
+        );
+
+        let projected = handler.rebuild_circle_from_events(&circle.id).await?;
+        assert_eq!(
+            projected,
+            ProjectedCircle {
+                circle,
+                created_at: events[0].occurred_at
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_projected_circle_reads_as_a_circle() -> Result<()> {
+        let (_, events) = history(1)?;
+        let projected = projected_at(&events)?;
+        let json = serde_json::to_string(&projected)?;
+        assert_eq!(serde_json::from_str::<Circle>(&json)?, projected.circle);
+        assert_eq!(serde_json::from_str::<ProjectedCircle>(&json)?, projected);
+        Ok(())
+    }
+}
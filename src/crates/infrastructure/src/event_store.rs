@@ -0,0 +1,687 @@
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use anyhow::{Error, Result};
+use chrono::NaiveDateTime;
+use domain::aggregate::value_object::event_metadata::EventMetadata;
+use domain::aggregate::{event_sourced::DomainEvent, value_object::version::Version};
+use sqlx::{
+    types::Json, ColumnIndex, Database, Decode, Encode, Executor, FromRow, IntoArguments, MySql,
+    Pool, Row, Type,
+};
+
+use crate::{
+    backend::Backend,
+    maria_db_schema::{SnapshotData, StoredEventData},
+    snapshot_policy::LastSnapshot,
+    upcaster::EventSchema,
+};
+
+/// The stream head was not at the version the writer expected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WrongExpectedVersion {
+    pub expected: Option<Version>,
+    pub actual: Option<Version>,
+}
+
+impl fmt::Display for WrongExpectedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "wrong expected version: expected {:?}, actual {:?}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for WrongExpectedVersion {}
+
+pub(crate) fn to_i32(version: Version) -> Result<i32> {
+    version
+        .try_into()
+        .map_err(|_| Error::msg("Failed to convert version to i32"))
+}
+
+/// Read access to the streams of aggregates of type `A`, whichever database holds them.
+#[async_trait::async_trait]
+pub trait EventLog<A: EventSchema>: Send + Sync + fmt::Debug {
+    /// Events of the stream with `after < version <= up_to`, in version order.
+    async fn load(
+        &self,
+        id: &A::Id,
+        after: Option<Version>,
+        up_to: Option<Version>,
+    ) -> Result<Vec<A::Event>>;
+
+    /// At most `limit` events of the stream in version order, starting at `from`.
+    async fn read_from(
+        &self,
+        id: &A::Id,
+        from: Option<Version>,
+        limit: u32,
+    ) -> Result<Vec<A::Event>>;
+
+    /// At most `limit` events of every stream of type `A` whose position in the global
+    /// log is after `after`, in log order, each with its position.
+    async fn read_all(&self, after: u64, limit: u32) -> Result<Vec<(u64, A::Event)>>;
+
+    /// Version of the last event that occurred at or before `occurred_at`.
+    async fn version_at(&self, id: &A::Id, occurred_at: NaiveDateTime) -> Result<Option<Version>>;
+
+    /// Ids of the streams with an event that occurred at or after `since`, or of all streams.
+    async fn stream_ids(&self, since: Option<NaiveDateTime>) -> Result<Vec<A::Id>>;
+
+    /// Latest snapshot of the stream, limited to versions up to `at_or_below` when given.
+    ///
+    /// Snapshots of another [`EventSchema::SNAPSHOT_VERSION`] are ignored, and a snapshot
+    /// whose state no longer deserialises is deleted, so callers fall back to replay.
+    async fn latest_snapshot(&self, id: &A::Id, at_or_below: Option<Version>) -> Result<Option<A>>;
+
+    /// Rebuilds the aggregate from its latest snapshot and the events after it.
+    /// Returns `None` when the stream has no events.
+    async fn load_aggregate(&self, id: &A::Id) -> Result<Option<A>> {
+        // an unreadable snapshot only costs a full replay
+        let snapshot = match self.latest_snapshot(id, None).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::error!("Failed to load snapshot: {:?}", e);
+                None
+            }
+        };
+
+        match snapshot {
+            Some(mut state) => {
+                let tail = self.load(id, Some(state.version()), None).await?;
+                for event in &tail {
+                    state.apply(event);
+                }
+                Ok(Some(state))
+            }
+            None => Ok(A::replay(self.load(id, None, None).await?)),
+        }
+    }
+}
+
+/// Streams of every aggregate of type `A` in the shared `events` and `snapshots` tables
+/// of a database `DB`.
+///
+/// Rows are told apart by `aggregate_type`, so aggregates only need to pick a unique
+/// [`Aggregate::TYPE_NAME`](domain::aggregate::event_sourced::Aggregate::TYPE_NAME).
+#[derive(Debug)]
+pub struct EventStore<A, DB: Database = MySql> {
+    db: Pool<DB>,
+    aggregate: PhantomData<fn() -> A>,
+}
+
+impl<A, DB: Database> Clone for EventStore<A, DB> {
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+            aggregate: PhantomData,
+        }
+    }
+}
+
+impl<A: EventSchema, DB: Database> EventStore<A, DB> {
+    pub fn new(db: Pool<DB>) -> Self {
+        Self {
+            db,
+            aggregate: PhantomData,
+        }
+    }
+}
+
+impl<A, DB> EventStore<A, DB>
+where
+    A: EventSchema,
+    DB: Backend,
+    for<'r> StoredEventData: FromRow<'r, DB::Row>,
+{
+    fn to_events(rows: &[DB::Row]) -> Result<Vec<A::Event>> {
+        rows.iter()
+            .map(|row| StoredEventData::from_row(row)?.into_event::<A>())
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl<A, DB> EventLog<A> for EventStore<A, DB>
+where
+    A: EventSchema,
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB> + ColumnIndex<DB::Row>,
+    for<'q> String: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> NaiveDateTime: Encode<'q, DB> + Type<DB>,
+    for<'r> StoredEventData: FromRow<'r, DB::Row>,
+    for<'r> SnapshotData<A>: FromRow<'r, DB::Row>,
+    usize: ColumnIndex<DB::Row>,
+{
+    async fn load(
+        &self,
+        id: &A::Id,
+        after: Option<Version>,
+        up_to: Option<Version>,
+    ) -> Result<Vec<A::Event>> {
+        let after = after.map(to_i32).transpose()?.unwrap_or(0);
+        let up_to = up_to.map(to_i32).transpose()?.unwrap_or(i32::MAX);
+        let rows = sqlx::query(&DB::sql(
+            "SELECT * FROM events WHERE aggregate_type = ? AND aggregate_id = ? AND version > ? AND version <= ? ORDER BY version ASC",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(id.to_string())
+        .bind(after)
+        .bind(up_to)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to fetch {} events: {}", A::TYPE_NAME, e)))?;
+        Self::to_events(&rows)
+    }
+
+    async fn read_from(
+        &self,
+        id: &A::Id,
+        from: Option<Version>,
+        limit: u32,
+    ) -> Result<Vec<A::Event>> {
+        let from = to_i32(from.unwrap_or_default())?;
+        let rows = sqlx::query(&DB::sql(
+            "SELECT * FROM events WHERE aggregate_type = ? AND aggregate_id = ? AND version >= ? ORDER BY version ASC LIMIT ?",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(id.to_string())
+        .bind(from)
+        .bind(i64::from(limit))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to fetch {} events: {}", A::TYPE_NAME, e)))?;
+        Self::to_events(&rows)
+    }
+
+    async fn read_all(&self, after: u64, limit: u32) -> Result<Vec<(u64, A::Event)>> {
+        let rows = sqlx::query(&DB::sql(
+            "SELECT * FROM events WHERE aggregate_type = ? AND sequence > ? ORDER BY sequence ASC LIMIT ?",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(after as i64)
+        .bind(i64::from(limit))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to read the {} event log: {}", A::TYPE_NAME, e)))?;
+        rows.iter()
+            .map(|row| {
+                let sequence: i64 = row.try_get("sequence")?;
+                let event = StoredEventData::from_row(row)?.into_event::<A>()?;
+                Ok((sequence as u64, event))
+            })
+            .collect()
+    }
+
+    async fn version_at(&self, id: &A::Id, occurred_at: NaiveDateTime) -> Result<Option<Version>> {
+        let version: Option<i32> = sqlx::query_scalar(&DB::sql(
+            "SELECT MAX(version) FROM events WHERE aggregate_type = ? AND aggregate_id = ? AND occurred_at <= ?",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(id.to_string())
+        .bind(occurred_at)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to resolve version at time: {}", e)))?;
+        version
+            .map(Version::try_from)
+            .transpose()
+            .map_err(|_| Error::msg("Failed to convert version from i32"))
+    }
+
+    async fn stream_ids(&self, since: Option<NaiveDateTime>) -> Result<Vec<A::Id>> {
+        let rows: Vec<String> = match since {
+            Some(since) => {
+                sqlx::query_scalar(&DB::sql(
+                    "SELECT DISTINCT aggregate_id FROM events WHERE aggregate_type = ? AND occurred_at >= ?",
+                ))
+                .bind(A::TYPE_NAME)
+                .bind(since)
+                .fetch_all(&self.db)
+                .await
+            }
+            None => {
+                sqlx::query_scalar(&DB::sql(
+                    "SELECT DISTINCT aggregate_id FROM events WHERE aggregate_type = ?",
+                ))
+                .bind(A::TYPE_NAME)
+                .fetch_all(&self.db)
+                .await
+            }
+        }
+        .map_err(|e| Error::msg(format!("Failed to list {} streams: {}", A::TYPE_NAME, e)))?;
+
+        rows.iter().map(|id| A::Id::from_str(id)).collect()
+    }
+
+    async fn latest_snapshot(&self, id: &A::Id, at_or_below: Option<Version>) -> Result<Option<A>> {
+        let at_or_below = at_or_below.map(to_i32).transpose()?.unwrap_or(i32::MAX);
+        let row = sqlx::query(&DB::sql(
+            "SELECT * FROM snapshots WHERE aggregate_type = ? AND aggregate_id = ? AND schema_version = ? AND version <= ? ORDER BY version DESC LIMIT 1",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(id.to_string())
+        .bind(A::SNAPSHOT_VERSION)
+        .bind(at_or_below)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to fetch {} snapshot: {}", A::TYPE_NAME, e)))?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        match SnapshotData::<A>::from_row(&row) {
+            Ok(snapshot) => Ok(Some(snapshot.state.0)),
+            Err(e) => {
+                tracing::warn!(
+                    "Discarding unreadable {} snapshot of {}: {:?}",
+                    A::TYPE_NAME,
+                    id,
+                    e
+                );
+                let snapshot_id: i64 = row.try_get("id")?;
+                sqlx::query(&DB::sql("DELETE FROM snapshots WHERE id = ?"))
+                    .bind(snapshot_id)
+                    .execute(&self.db)
+                    .await
+                    .map_err(|e| Error::msg(format!("Failed to discard snapshot: {}", e)))?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<A, DB> EventStore<A, DB>
+where
+    A: EventSchema,
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB> + ColumnIndex<DB::Row>,
+    for<'q> String: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> NaiveDateTime: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> Json<serde_json::Value>: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<Json<EventMetadata>>: Encode<'q, DB> + Type<DB>,
+    for<'r> StoredEventData: FromRow<'r, DB::Row>,
+    for<'r> SnapshotData<A>: FromRow<'r, DB::Row>,
+    usize: ColumnIndex<DB::Row>,
+{
+    /// Appends `events` to their stream inside the caller's transaction and gives them
+    /// the next positions of the global log.
+    ///
+    /// Fails with [`WrongExpectedVersion`] when the stream head is not `expected`
+    /// (`None` for a stream that must not exist yet).
+    pub async fn append(
+        &self,
+        connection: &mut DB::Connection,
+        expected: Option<Version>,
+        events: &[A::Event],
+    ) -> Result<()> {
+        let Some(first_event) = events.first() else {
+            return Ok(());
+        };
+        let id = first_event.aggregate_id();
+
+        let mut expected_version = expected.map_or_else(Version::new, |v| v.next());
+        for event in events {
+            if event.aggregate_id() != id || event.version() != expected_version {
+                return Err(Error::msg(
+                    "Events must be contiguous and belong to a single stream",
+                ));
+            }
+            expected_version = expected_version.next();
+        }
+
+        // the counter row stays locked until commit, so appends serialize here, the head
+        // read below cannot go stale, and positions become visible in order: a reader
+        // never sees a position after one that is still uncommitted
+        sqlx::query(&DB::sql(
+            "UPDATE event_sequence SET last_sequence = last_sequence + ? WHERE id = 1",
+        ))
+        .bind(events.len() as i64)
+        .execute(&mut *connection)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to allocate event positions: {:?}", e);
+            Error::msg("Failed to allocate event positions")
+        })?;
+        let last_sequence: i64 =
+            sqlx::query_scalar("SELECT last_sequence FROM event_sequence WHERE id = 1")
+                .fetch_one(&mut *connection)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to allocate event positions: {:?}", e);
+                    Error::msg("Failed to allocate event positions")
+                })?;
+
+        let head = Self::stream_head(&mut *connection, id).await?;
+
+        if head != expected {
+            return Err(WrongExpectedVersion {
+                expected,
+                actual: head,
+            }
+            .into());
+        }
+
+        for (event, sequence) in events.iter().zip(last_sequence - events.len() as i64 + 1..) {
+            let event_data = StoredEventData::from_event::<A>(event)?;
+
+            let inserted = sqlx::query(&DB::sql("INSERT INTO events (id, sequence, aggregate_type, aggregate_id, version, event_type, schema_version, payload, metadata, occurred_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"))
+                .bind(event_data.id)
+                .bind(sequence)
+                .bind(event_data.aggregate_type)
+                .bind(event_data.aggregate_id)
+                .bind(event_data.version)
+                .bind(event_data.event_type)
+                .bind(event_data.schema_version)
+                .bind(event_data.payload)
+                .bind(event_data.metadata)
+                .bind(event_data.occurred_at)
+                .execute(&mut *connection)
+                .await;
+            match inserted {
+                Ok(_) => {}
+                // the unique key on the stream and version is the backstop for racing
+                // appends. The other writer has committed by the time it fires, so its head
+                // is read outside this transaction, which PostgreSQL aborts on the error.
+                Err(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {
+                    return Err(WrongExpectedVersion {
+                        expected,
+                        actual: Self::stream_head(&self.db, id).await?,
+                    }
+                    .into());
+                }
+                Err(e) => {
+                    tracing::error!("Failed to insert event: {:?}", e);
+                    return Err(Error::msg("Failed to insert event"));
+                }
+            }
+        }
+
+        if let Some(appended) = DB::APPENDED {
+            sqlx::query(&DB::sql(appended))
+                .bind(A::TYPE_NAME)
+                .execute(&mut *connection)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to notify listeners: {:?}", e);
+                    Error::msg("Failed to notify listeners")
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Version of the last event of the stream, or `None` when it has none.
+    async fn stream_head<'e, E>(executor: E, id: &A::Id) -> Result<Option<Version>>
+    where
+        E: Executor<'e, Database = DB>,
+    {
+        let head: Option<i32> = sqlx::query_scalar(&DB::sql(
+            "SELECT MAX(version) FROM events WHERE aggregate_type = ? AND aggregate_id = ?",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(id.to_string())
+        .fetch_one(executor)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch stream head: {:?}", e);
+            Error::msg("Failed to fetch stream head")
+        })?;
+        head.map(Version::try_from)
+            .transpose()
+            .map_err(|_| Error::msg("Failed to convert version from i32"))
+    }
+
+    /// Version and age of the latest snapshot of the current shape, without its state.
+    pub async fn last_snapshot(&self, id: &A::Id) -> Result<Option<LastSnapshot>> {
+        let row: Option<(i32, NaiveDateTime)> = sqlx::query_as(&DB::sql(
+            "SELECT version, created_at FROM snapshots WHERE aggregate_type = ? AND aggregate_id = ? AND schema_version = ? ORDER BY version DESC LIMIT 1",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(id.to_string())
+        .bind(A::SNAPSHOT_VERSION)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to fetch {} snapshot: {}", A::TYPE_NAME, e)))?;
+
+        row.map(|(version, created_at)| {
+            Ok(LastSnapshot {
+                version: Version::try_from(version)
+                    .map_err(|_| Error::msg("Failed to convert version from i32"))?,
+                created_at,
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn save_snapshot(&self, state: &A) -> Result<()> {
+        sqlx::query(&DB::sql(
+            "INSERT INTO snapshots (aggregate_type, aggregate_id, version, schema_version, state) VALUES (?, ?, ?, ?, ?)",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(state.id().to_string())
+        .bind(to_i32(state.version())?)
+        .bind(A::SNAPSHOT_VERSION)
+        .bind(Json(serde_json::to_value(state)?))
+        .execute(&self.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save snapshot: {:?}", e);
+            Error::msg(format!("Failed to save {} snapshot", A::TYPE_NAME))
+        })?;
+
+        tracing::info!(
+            "Saved snapshot for {} {} at version {}",
+            A::TYPE_NAME,
+            state.id(),
+            state.version()
+        );
+        Ok(())
+    }
+
+    /// Deletes all but the `keep` newest snapshots of the stream. Returns how many
+    /// were deleted.
+    pub async fn prune_snapshots(&self, id: &A::Id, keep: u32) -> Result<u64> {
+        if keep == 0 {
+            return Err(Error::msg("At least one snapshot must be kept"));
+        }
+        let oldest_kept: Option<i32> = sqlx::query_scalar(&DB::sql(
+            "SELECT version FROM snapshots WHERE aggregate_type = ? AND aggregate_id = ? AND schema_version = ? ORDER BY version DESC LIMIT 1 OFFSET ?",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(id.to_string())
+        .bind(A::SNAPSHOT_VERSION)
+        .bind(i64::from(keep - 1))
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to fetch {} snapshot: {}", A::TYPE_NAME, e)))?;
+        let Some(oldest_kept) = oldest_kept else {
+            return Ok(0);
+        };
+
+        let result = sqlx::query(&DB::sql(
+            "DELETE FROM snapshots WHERE aggregate_type = ? AND aggregate_id = ? AND version < ?",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(id.to_string())
+        .bind(oldest_kept)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to prune {} snapshots: {}", A::TYPE_NAME, e)))?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    /// Replaces the snapshots of another [`EventSchema::SNAPSHOT_VERSION`] with one of the
+    /// current shape, replayed from the events alone. Returns how many streams were
+    /// regenerated.
+    ///
+    /// Run after changing the shape of the aggregate; until then affected streams are
+    /// loaded by full replay.
+    pub async fn regenerate_snapshots(&self) -> Result<u64> {
+        let ids: Vec<String> = sqlx::query_scalar(&DB::sql(
+            "SELECT DISTINCT aggregate_id FROM snapshots WHERE aggregate_type = ? AND schema_version <> ?",
+        ))
+        .bind(A::TYPE_NAME)
+        .bind(A::SNAPSHOT_VERSION)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to list stale snapshots: {}", e)))?;
+
+        let mut regenerated = 0;
+        for id in ids {
+            let id = A::Id::from_str(&id)?;
+            if let Some(state) = A::replay(self.load(&id, None, None).await?) {
+                self.save_snapshot(&state).await?;
+            }
+            sqlx::query(&DB::sql(
+                "DELETE FROM snapshots WHERE aggregate_type = ? AND aggregate_id = ? AND schema_version <> ?",
+            ))
+            .bind(A::TYPE_NAME)
+            .bind(id.to_string())
+            .bind(A::SNAPSHOT_VERSION)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::msg(format!("Failed to delete stale snapshots: {}", e)))?;
+            regenerated += 1;
+        }
+        Ok(regenerated)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::sync::Arc;
+
+    use domain::{
+        aggregate::{
+            circle::{member::Member, Circle},
+            event_sourced::Aggregate,
+            value_object::member_id::MemberId,
+        },
+        interface::command::circle_repository_interface::CircleRepositoryInterface,
+    };
+    use sqlx::{Sqlite, SqlitePool};
+
+    use super::*;
+    use crate::{
+        circle_repository::CircleRepository, snapshot_policy::Never, sqlite::tests::memory_pool,
+    };
+
+    /// A circle renamed once, stored without snapshots.
+    async fn renamed_circle(db: &SqlitePool, name: &str) -> Result<Circle> {
+        let repository = CircleRepository::new(db.clone()).with_snapshot_policy(Arc::new(Never));
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (circle, created) = Circle::create(name.to_string(), 10, owner)?;
+        let version = circle.version;
+        let (renamed, updated) = circle.update(Some(format!("{} 2", name)), None)?;
+        repository.store(None, vec![created]).await?;
+        repository.store(Some(version), vec![updated]).await?;
+        Ok(renamed)
+    }
+
+    /// Writes a snapshot of `circle` with `state` and `schema_version` as given.
+    async fn insert_snapshot(
+        db: &SqlitePool,
+        circle: &Circle,
+        schema_version: i32,
+        state: String,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO snapshots (aggregate_type, aggregate_id, version, schema_version, state) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(Circle::TYPE_NAME)
+        .bind(circle.id.to_string())
+        .bind(to_i32(circle.version)?)
+        .bind(schema_version)
+        .bind(state)
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// The `(version, schema_version)` of every snapshot of `circle`.
+    async fn snapshots(db: &SqlitePool, circle: &Circle) -> Result<Vec<(i32, i32)>> {
+        Ok(sqlx::query_as(
+            "SELECT version, schema_version FROM snapshots WHERE aggregate_id = ? ORDER BY version",
+        )
+        .bind(circle.id.to_string())
+        .fetch_all(db)
+        .await?)
+    }
+
+    /// The state of `circle` with another name, so a load that used it shows.
+    fn tampered(circle: &Circle) -> Result<String> {
+        let (tampered, _) = circle.clone().update(Some("Tampered".to_string()), None)?;
+        Ok(serde_json::to_string(&tampered)?)
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_of_another_shape_is_ignored() -> Result<()> {
+        let db = memory_pool().await?;
+        let circle = renamed_circle(&db, "Music club").await?;
+        insert_snapshot(
+            &db,
+            &circle,
+            Circle::SNAPSHOT_VERSION - 1,
+            tampered(&circle)?,
+        )
+        .await?;
+        let events = EventStore::<Circle, Sqlite>::new(db.clone());
+
+        assert_eq!(events.latest_snapshot(&circle.id, None).await?, None);
+        assert_eq!(
+            events.load_aggregate(&circle.id).await?,
+            Some(circle.clone())
+        );
+        // kept for regenerate_snapshots to replace
+        assert_eq!(snapshots(&db, &circle).await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_snapshot_is_deleted() -> Result<()> {
+        let db = memory_pool().await?;
+        let circle = renamed_circle(&db, "Music club").await?;
+        insert_snapshot(
+            &db,
+            &circle,
+            Circle::SNAPSHOT_VERSION,
+            "{\"name\":1}".to_string(),
+        )
+        .await?;
+        let events = EventStore::<Circle, Sqlite>::new(db.clone());
+
+        assert_eq!(events.latest_snapshot(&circle.id, None).await?, None);
+        assert!(snapshots(&db, &circle).await?.is_empty());
+        assert_eq!(events.load_aggregate(&circle.id).await?, Some(circle));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_replaces_stale_snapshots_by_replay() -> Result<()> {
+        let db = memory_pool().await?;
+        let music = renamed_circle(&db, "Music club").await?;
+        let book = renamed_circle(&db, "Book club").await?;
+        insert_snapshot(&db, &music, Circle::SNAPSHOT_VERSION - 1, tampered(&music)?).await?;
+        let events = EventStore::<Circle, Sqlite>::new(db.clone());
+        events.save_snapshot(&book).await?;
+
+        assert_eq!(events.regenerate_snapshots().await?, 1);
+        let current = vec![(to_i32(music.version)?, Circle::SNAPSHOT_VERSION)];
+        assert_eq!(snapshots(&db, &music).await?, current);
+        assert_eq!(events.latest_snapshot(&music.id, None).await?, Some(music));
+        // streams without stale snapshots are left alone
+        assert_eq!(snapshots(&db, &book).await?.len(), 1);
+        assert_eq!(events.regenerate_snapshots().await?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{Error, Result};
+use chrono::{Duration, NaiveDateTime};
+use domain::interface::command::idempotency_store_interface::{
+    IdempotencyClaim, IdempotencyStoreInterface, StoredResponse, CLAIM_LEASE,
+};
+use sqlx::{
+    types::Json, Database, Decode, Encode, Executor, FromRow, IntoArguments, MySql, Pool, Type,
+};
+
+use crate::backend::Backend;
+
+#[derive(Clone, Debug)]
+pub struct IdempotencyStore<DB: Database = MySql> {
+    db: Pool<DB>,
+}
+
+impl<DB: Database> IdempotencyStore<DB> {
+    pub fn new(db: Pool<DB>) -> Self {
+        Self { db }
+    }
+}
+
+/// A row of `idempotency_keys`, with the database clock at the time it was read.
+#[derive(Debug, FromRow)]
+pub struct StoredClaim {
+    request_hash: String,
+    response_status: Option<i32>,
+    response_headers: Option<Json<Vec<(String, String)>>>,
+    response_body: Option<Vec<u8>>,
+    created_at: NaiveDateTime,
+    now: NaiveDateTime,
+}
+
+#[async_trait::async_trait]
+impl<DB> IdempotencyStoreInterface for IdempotencyStore<DB>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Type<DB>,
+    for<'q> NaiveDateTime: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> Json<&'q Vec<(String, String)>>: Encode<'q, DB> + Type<DB>,
+    for<'q> Vec<u8>: Encode<'q, DB> + Type<DB>,
+    for<'r> StoredClaim: FromRow<'r, DB::Row>,
+{
+    async fn claim(&self, scope: &str, key: &str, request_hash: &str) -> Result<IdempotencyClaim> {
+        let inserted = sqlx::query(&DB::sql(
+            "INSERT INTO idempotency_keys (scope, idempotency_key, request_hash) VALUES (?, ?, ?)",
+        ))
+        .bind(scope)
+        .bind(key)
+        .bind(request_hash)
+        .execute(&self.db)
+        .await;
+        match inserted {
+            Ok(_) => return Ok(IdempotencyClaim::Acquired),
+            Err(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {}
+            Err(e) => {
+                return Err(Error::msg(format!(
+                    "Failed to claim idempotency key: {}",
+                    e
+                )));
+            }
+        }
+
+        let claim: StoredClaim = sqlx::query_as(&DB::sql(&format!(
+            "SELECT request_hash, response_status, response_headers, response_body, created_at, \
+             {} AS now FROM idempotency_keys WHERE scope = ? AND idempotency_key = ?",
+            DB::NOW
+        )))
+        .bind(scope)
+        .bind(key)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to fetch idempotency key: {}", e)))?;
+
+        if claim.request_hash != request_hash {
+            return Ok(IdempotencyClaim::Mismatch);
+        }
+        match claim.response_status {
+            Some(status) => Ok(IdempotencyClaim::Completed(StoredResponse {
+                status: u16::try_from(status)
+                    .map_err(|_| Error::msg("Stored response status is out of range"))?,
+                headers: claim
+                    .response_headers
+                    .map(|headers| headers.0)
+                    .unwrap_or_default(),
+                body: claim.response_body.unwrap_or_default(),
+            })),
+            None => {
+                let abandoned_before = claim.now
+                    - Duration::from_std(CLAIM_LEASE)
+                        .map_err(|_| Error::msg("Claim lease is out of range"))?;
+                if claim.created_at >= abandoned_before {
+                    return Ok(IdempotencyClaim::InProgress);
+                }
+                // take the claim over, unless another retry got there first
+                let retaken = sqlx::query(&DB::sql(&format!(
+                    "UPDATE idempotency_keys SET created_at = {} \
+                     WHERE scope = ? AND idempotency_key = ? AND response_status IS NULL \
+                     AND created_at < ?",
+                    DB::NOW
+                )))
+                .bind(scope)
+                .bind(key)
+                .bind(abandoned_before)
+                .execute(&self.db)
+                .await
+                .map_err(|e| Error::msg(format!("Failed to reclaim idempotency key: {}", e)))?;
+                if DB::rows_affected(&retaken) == 1 {
+                    Ok(IdempotencyClaim::Acquired)
+                } else {
+                    Ok(IdempotencyClaim::InProgress)
+                }
+            }
+        }
+    }
+
+    async fn complete(&self, scope: &str, key: &str, response: &StoredResponse) -> Result<()> {
+        sqlx::query(&DB::sql(&format!(
+            "UPDATE idempotency_keys \
+             SET response_status = ?, response_headers = ?, response_body = ?, completed_at = {} \
+             WHERE scope = ? AND idempotency_key = ?",
+            DB::NOW
+        )))
+        .bind(i32::from(response.status))
+        .bind(Json(&response.headers))
+        .bind(&response.body)
+        .bind(scope)
+        .bind(key)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to store idempotent response: {}", e)))?;
+        Ok(())
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> Result<()> {
+        sqlx::query(&DB::sql(
+            "DELETE FROM idempotency_keys \
+             WHERE scope = ? AND idempotency_key = ? AND response_status IS NULL",
+        ))
+        .bind(scope)
+        .bind(key)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to release idempotency key: {}", e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+//! Process-local stand-ins for the MySQL event store and the Redis read model.
+//!
+//! They keep the semantics the handlers rely on: optimistic concurrency on append,
+//! unique circle names, snapshots taken by a [`SnapshotPolicy`](crate::snapshot_policy::SnapshotPolicy)
+//! and an incremental projection that repairs gaps. This lets the whole application
+//! run in `cargo test` without any service. Nothing is persisted.
+//!
+//! The one deliberate difference is delivery: subscribers are called right after an
+//! append instead of polling the log, so a read sees a write as soon as the command
+//! returns.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+pub mod circle_event_reader;
+pub mod circle_projection;
+pub mod circle_repository;
+pub mod event_store;
+pub mod idempotency_store;
+
+/// A panicking test must not fail every later test sharing the state.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use domain::{
+        aggregate::circle::Circle,
+        interface::{
+            command::circle_repository_interface::CircleRepositoryInterface,
+            query::circle_reader_interface::CircleReaderInterface,
+        },
+        test_utils::circle_conformance::{self, CircleBackend},
+    };
+
+    use super::{
+        circle_projection::InMemoryCircleProjection, circle_repository::InMemoryCircleRepository,
+        event_store::InMemoryEventStore,
+    };
+    use crate::snapshot_policy::{EventsSinceLastSnapshot, EveryNEvents, Never, SnapshotPolicy};
+
+    struct InMemoryBackend {
+        repository: Arc<InMemoryCircleRepository>,
+        projection: Arc<InMemoryCircleProjection>,
+    }
+
+    impl InMemoryBackend {
+        fn new() -> Self {
+            let events = InMemoryEventStore::<Circle>::new();
+            Self {
+                projection: InMemoryCircleProjection::subscribed_to(events.clone()),
+                repository: Arc::new(InMemoryCircleRepository::new(events)),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl CircleBackend for InMemoryBackend {
+        fn repository(&self) -> Arc<dyn CircleRepositoryInterface + Send + Sync> {
+            self.repository.clone()
+        }
+
+        fn reader(&self) -> Arc<dyn CircleReaderInterface + Send + Sync> {
+            self.projection.clone()
+        }
+
+        async fn settle(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repository_conformance() -> anyhow::Result<()> {
+        // snapshots on every append, never, and every few events
+        let policies: [Arc<dyn SnapshotPolicy>; 3] = [
+            Arc::new(EveryNEvents(1)),
+            Arc::new(Never),
+            Arc::new(EventsSinceLastSnapshot(5)),
+        ];
+        for policy in policies {
+            let repository = InMemoryCircleRepository::new(InMemoryEventStore::new())
+                .with_snapshot_policy(policy)
+                .with_snapshot_retention(2);
+            circle_conformance::run_repository_suite(&repository).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_conformance() -> anyhow::Result<()> {
+        circle_conformance::run_reader_suite(&InMemoryBackend::new()).await
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use anyhow::Error;
+use domain::{
+    aggregate::{
+        circle::{event::CircleEvent, Circle},
+        value_object::{circle_id::CircleId, version::Version},
+    },
+    interface::query::circle_event_reader_interface::{AsOf, CircleEventReaderInterface},
+};
+
+use super::event_store::InMemoryEventStore;
+use crate::circle_event_reader::CircleEventReader;
+
+#[async_trait::async_trait]
+impl CircleEventReaderInterface for InMemoryEventStore<Circle> {
+    async fn list_events(
+        &self,
+        circle_id: &CircleId,
+        from_version: Option<Version>,
+        limit: u32,
+    ) -> Result<Vec<CircleEvent>, Error> {
+        Ok(self.read_from(circle_id, from_version, limit))
+    }
+
+    async fn get_circle_as_of(
+        &self,
+        circle_id: &CircleId,
+        as_of: AsOf,
+    ) -> Result<Option<Circle>, Error> {
+        CircleEventReader::new(Arc::new(self.clone()))
+            .get_circle_as_of(circle_id, as_of)
+            .await
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Bound,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Error, Result};
+use chrono::{NaiveDateTime, Utc};
+use domain::{
+    aggregate::{
+        circle::{event::CircleEvent, Circle},
+        event_sourced::Aggregate,
+        value_object::{circle_id::CircleId, version::Version},
+    },
+    interface::query::{
+        circle_reader_interface::{
+            CircleListQuery, CirclePage, CircleReaderInterface, CircleSortKey, SortOrder,
+        },
+        projection_rebuilder_interface::{
+            ProjectionRebuilderInterface, RebuildInProgress, RebuildProgress, RebuildState,
+        },
+    },
+};
+
+use super::{event_store::InMemoryEventStore, lock};
+use crate::{circle_index, subscription::EventHandler};
+
+/// The read model as Redis holds it: every projected circle with when it was created,
+/// and one ordered index of `circle_index` entries per sort key over the active ones.
+#[derive(Debug, Default)]
+struct ProjectedCircles {
+    circles: HashMap<String, (Circle, NaiveDateTime)>,
+    indexes: [BTreeSet<String>; 3],
+}
+
+impl ProjectedCircles {
+    fn index(&self, sort_key: CircleSortKey) -> &BTreeSet<String> {
+        &self.indexes[Self::position(sort_key)]
+    }
+
+    fn position(sort_key: CircleSortKey) -> usize {
+        match sort_key {
+            CircleSortKey::Capacity => 0,
+            CircleSortKey::CreatedAt => 1,
+            CircleSortKey::Name => 2,
+        }
+    }
+
+    fn save(&mut self, circle: Circle, created_at: NaiveDateTime) {
+        let previous = self.circles.get(&circle.id.to_string());
+        for sort_key in circle_index::SORT_KEYS {
+            let index = &mut self.indexes[Self::position(sort_key)];
+            // index entries of the previous state go stale when the circle is renamed or resized
+            if let Some((previous, previous_created_at)) = previous {
+                index.remove(&circle_index::entry(
+                    sort_key,
+                    previous,
+                    *previous_created_at,
+                ));
+            }
+            if !circle.is_disbanded() {
+                index.insert(circle_index::entry(sort_key, &circle, created_at));
+            }
+        }
+        self.circles
+            .insert(circle.id.to_string(), (circle, created_at));
+    }
+}
+
+/// Whether `entry` lies within `ZRANGEBYLEX` style bounds (`[` inclusive, `(`
+/// exclusive, `-` and `+` open).
+fn within(entry: &str, min: &[u8], max: &[u8]) -> bool {
+    let bound = |lex: &[u8]| match lex.split_first() {
+        Some((b'[', value)) => Bound::Included(value.to_vec()),
+        Some((b'(', value)) => Bound::Excluded(value.to_vec()),
+        _ => Bound::Unbounded,
+    };
+    let entry = entry.as_bytes();
+    let above_min = match bound(min) {
+        Bound::Included(min) => entry >= min.as_slice(),
+        Bound::Excluded(min) => entry > min.as_slice(),
+        Bound::Unbounded => true,
+    };
+    let below_max = match bound(max) {
+        Bound::Included(max) => entry <= max.as_slice(),
+        Bound::Excluded(max) => entry < max.as_slice(),
+        Bound::Unbounded => true,
+    };
+    above_min && below_max
+}
+
+/// Projection of the circle streams of an [`InMemoryEventStore`] that serves
+/// [`CircleReaderInterface`] and can be rebuilt from the events, like
+/// [`RedisProjectionHandler`](crate::event_publisher::RedisProjectionHandler) with
+/// [`CircleReader`](crate::circle_reader::CircleReader) and
+/// [`ProjectionRebuilder`](crate::projection_rebuilder::ProjectionRebuilder).
+#[derive(Clone, Debug)]
+pub struct InMemoryCircleProjection {
+    events: InMemoryEventStore<Circle>,
+    projected: Arc<Mutex<ProjectedCircles>>,
+    progress: Arc<Mutex<RebuildProgress>>,
+}
+
+impl InMemoryCircleProjection {
+    /// A projection kept up to date with every event appended to `events` from now on.
+    pub fn subscribed_to(events: InMemoryEventStore<Circle>) -> Arc<Self> {
+        let projection = Arc::new(Self {
+            events: events.clone(),
+            projected: Arc::new(Mutex::new(ProjectedCircles::default())),
+            progress: Arc::new(Mutex::new(RebuildProgress::default())),
+        });
+        events.subscribe(projection.clone());
+        projection
+    }
+
+    pub fn handle_event(&self, event: &CircleEvent) -> Result<()> {
+        let projected = lock(&self.projected)
+            .circles
+            .get(&event.circle_id.to_string())
+            .cloned();
+        let (circle, created_at) = match projected {
+            Some((projected, _)) if projected.version >= event.version => return Ok(()),
+            Some((mut circle, created_at)) if circle.version.next() == event.version => {
+                circle.apply(event);
+                (circle, created_at)
+            }
+            None if event.version == Version::new() => {
+                let circle = Circle::initial(event).ok_or_else(|| {
+                    Error::msg("Circle stream does not start with circle_created")
+                })?;
+                (circle, event.occurred_at)
+            }
+            projected => {
+                tracing::warn!(
+                    "Projection of circle {} is at version {:?} but received version {:?}; rebuilding it",
+                    event.circle_id,
+                    projected.map(|(circle, _)| circle.version),
+                    event.version
+                );
+                self.rebuild_circle_from_events(&event.circle_id)?
+            }
+        };
+
+        lock(&self.projected).save(circle, created_at);
+        Ok(())
+    }
+
+    fn rebuild_circle_from_events(&self, circle_id: &CircleId) -> Result<(Circle, NaiveDateTime)> {
+        let circle = self
+            .events
+            .load_aggregate(circle_id)
+            .ok_or_else(|| Error::msg("Circle stream does not start with circle_created"))?;
+        let created_at = self.created_at(circle_id)?;
+        Ok((circle, created_at))
+    }
+
+    fn created_at(&self, circle_id: &CircleId) -> Result<NaiveDateTime> {
+        self.events
+            .read_from(circle_id, None, 1)
+            .first()
+            .map(|event| event.occurred_at)
+            .ok_or_else(|| Error::msg("No events found for circle"))
+    }
+
+    fn begin(&self) -> Result<()> {
+        let mut progress = lock(&self.progress);
+        if progress.state == RebuildState::Running {
+            return Err(RebuildInProgress.into());
+        }
+        *progress = RebuildProgress {
+            state: RebuildState::Running,
+            started_at: Some(Utc::now().naive_utc()),
+            ..RebuildProgress::default()
+        };
+        Ok(())
+    }
+
+    fn run(&self) -> Result<()> {
+        let result = self.rebuild_all();
+
+        let mut progress = lock(&self.progress);
+        progress.finished_at = Some(Utc::now().naive_utc());
+        match &result {
+            Ok(()) => progress.state = RebuildState::Completed,
+            Err(e) => {
+                progress.state = RebuildState::Failed;
+                progress.error = Some(e.to_string());
+            }
+        }
+        result
+    }
+
+    /// Projects every stream into a fresh read model and swaps it in at once.
+    fn rebuild_all(&self) -> Result<()> {
+        let circle_ids = self.events.stream_ids(None);
+        lock(&self.progress).total = circle_ids.len() as u64;
+
+        let mut rebuilt = ProjectedCircles::default();
+        for circle_id in &circle_ids {
+            let (circle, created_at) = self.rebuild_circle_from_events(circle_id)?;
+            rebuilt.save(circle, created_at);
+            lock(&self.progress).processed += 1;
+        }
+        *lock(&self.projected) = rebuilt;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler<Circle> for InMemoryCircleProjection {
+    async fn handle(&self, event: &CircleEvent) -> Result<()> {
+        self.handle_event(event)
+    }
+}
+
+#[async_trait::async_trait]
+impl CircleReaderInterface for InMemoryCircleProjection {
+    async fn get_circle(&self, circle_id: CircleId) -> Result<Option<Circle>, Error> {
+        Ok(lock(&self.projected)
+            .circles
+            .get(&circle_id.to_string())
+            .map(|(circle, _)| circle.clone()))
+    }
+
+    async fn list_circles(&self, query: &CircleListQuery) -> Result<CirclePage, Error> {
+        let (mut min, mut max) = circle_index::lex_range(query);
+        // the cursor is the last entry of the previous page, so the next page starts right after it
+        if let Some(cursor) = &query.cursor {
+            let start = [b"(", circle_index::decode_cursor(cursor)?.as_bytes()].concat();
+            match query.order {
+                SortOrder::Asc => min = start,
+                SortOrder::Desc => max = start,
+            }
+        }
+
+        let projected = lock(&self.projected);
+        let index = projected.index(query.sort_key);
+        let entries: Box<dyn Iterator<Item = &String>> = match query.order {
+            SortOrder::Asc => Box::new(index.iter()),
+            SortOrder::Desc => Box::new(index.iter().rev()),
+        };
+
+        let limit = query.limit as usize;
+        let mut circles = Vec::new();
+        for entry in entries.filter(|entry| within(entry, &min, &max)) {
+            let Some((circle, _)) = circle_index::circle_id_of(entry)
+                .and_then(|circle_id| projected.circles.get(circle_id))
+            else {
+                continue;
+            };
+            if !circle_index::matches(query, circle) {
+                continue;
+            }
+            circles.push(circle.clone());
+            if circles.len() == limit {
+                return Ok(CirclePage {
+                    circles,
+                    next_cursor: Some(circle_index::encode_cursor(entry)),
+                });
+            }
+        }
+
+        Ok(CirclePage {
+            circles,
+            next_cursor: None,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectionRebuilderInterface for InMemoryCircleProjection {
+    /// Runs the rebuild before returning; there is no I/O to wait for.
+    async fn start(&self) -> Result<()> {
+        self.begin()?;
+        if let Err(e) = self.run() {
+            tracing::error!("Projection rebuild failed: {:?}", e);
+        }
+        Ok(())
+    }
+
+    async fn rebuild(&self) -> Result<RebuildProgress> {
+        self.begin()?;
+        self.run()?;
+        Ok(self.progress().await)
+    }
+
+    async fn progress(&self) -> RebuildProgress {
+        lock(&self.progress).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use domain::aggregate::{circle::member::Member, value_object::member_id::MemberId};
+
+    use super::*;
+
+    fn create(name: &str) -> anyhow::Result<(Circle, CircleEvent)> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        Circle::create(name.to_string(), 10, owner)
+    }
+
+    #[tokio::test]
+    async fn test_list_circles_pages_through_the_index() -> anyhow::Result<()> {
+        let events = InMemoryEventStore::new();
+        let projection = InMemoryCircleProjection::subscribed_to(events.clone());
+        for name in ["Chess club", "Art club", "Book club"] {
+            let (_, created) = create(name)?;
+            events.append(None, std::slice::from_ref(&created))?;
+            events.publish(&[created]).await;
+        }
+
+        let query = CircleListQuery {
+            sort_key: CircleSortKey::Name,
+            limit: 2,
+            ..CircleListQuery::default()
+        };
+        let first = projection.list_circles(&query).await?;
+        let names: Vec<&str> = first.circles.iter().map(Circle::name).collect();
+        assert_eq!(names, ["Art club", "Book club"]);
+
+        let second = projection
+            .list_circles(&CircleListQuery {
+                cursor: first.next_cursor,
+                ..query
+            })
+            .await?;
+        let names: Vec<&str> = second.circles.iter().map(Circle::name).collect();
+        assert_eq!(names, ["Chess club"]);
+        assert_eq!(second.next_cursor, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missed_events_are_repaired_from_the_stream() -> anyhow::Result<()> {
+        let events = InMemoryEventStore::new();
+        let projection = InMemoryCircleProjection::subscribed_to(events.clone());
+        let (circle, created) = create("Music club")?;
+        let (updated_circle, updated) = circle.update(Some("Football club".to_string()), None)?;
+        // appended without publishing, as if the projection missed them
+        events.append(None, &[created, updated.clone()])?;
+
+        projection.handle_event(&updated)?;
+        assert_eq!(
+            projection.get_circle(updated_circle.id.clone()).await?,
+            Some(updated_circle)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Error, Result};
+use domain::{
+    aggregate::{
+        circle::{
+            event::{self, CircleEvent},
+            Circle,
+        },
+        value_object::{circle_id::CircleId, version::Version},
+    },
+    interface::command::{
+        circle_duplicate_checker_interface::CircleDuplicateCheckerInterface,
+        circle_repository_interface::{
+            name_key, CircleNotFound, CircleRepositoryInterface, DuplicateCircleName,
+            VersionConflict,
+        },
+    },
+};
+
+use super::{event_store::InMemoryEventStore, lock};
+use crate::{
+    event_store::WrongExpectedVersion,
+    snapshot_policy::{AppendedEvents, EveryNEvents, SnapshotPolicy},
+};
+
+/// [`CircleRepository`](crate::circle_repository::CircleRepository) and
+/// [`CircleDuplicateChecker`](crate::circle_duplicate_checker::CircleDuplicateChecker)
+/// over an [`InMemoryEventStore`], sharing the reserved circle names.
+#[derive(Clone, Debug)]
+pub struct InMemoryCircleRepository {
+    events: InMemoryEventStore<Circle>,
+    /// Reserved name keys and the circle holding each, like `circle_names`.
+    names: Arc<Mutex<HashMap<String, CircleId>>>,
+    snapshot_policy: Arc<dyn SnapshotPolicy>,
+    snapshot_retention: Option<u32>,
+}
+
+impl InMemoryCircleRepository {
+    pub fn new(events: InMemoryEventStore<Circle>) -> Self {
+        Self {
+            events,
+            names: Arc::new(Mutex::new(HashMap::new())),
+            snapshot_policy: Arc::new(EveryNEvents(5)),
+            snapshot_retention: None,
+        }
+    }
+
+    pub fn with_snapshot_policy(mut self, policy: Arc<dyn SnapshotPolicy>) -> Self {
+        self.snapshot_policy = policy;
+        self
+    }
+
+    /// Keeps only the `keep` newest snapshots of each circle.
+    pub fn with_snapshot_retention(mut self, keep: u32) -> Self {
+        self.snapshot_retention = Some(keep.max(1));
+        self
+    }
+
+    /// Moves the names `events` reserve or give back, failing without changes when a
+    /// name is held by another circle.
+    fn reserve_names(
+        names: &mut HashMap<String, CircleId>,
+        events: &[CircleEvent],
+    ) -> Result<(), DuplicateCircleName> {
+        let mut reserved = names.clone();
+        for event in events {
+            let name = match &event.data {
+                event::EventData::CircleCreated(event::CircleCreated { name, .. }) => Some(name),
+                event::EventData::CircleUpdated(event::CircleUpdated {
+                    name: Some(name), ..
+                }) => Some(name),
+                // a disbanded circle gives its name back
+                event::EventData::CircleDisbanded(_) => None,
+                _ => continue,
+            };
+
+            reserved.retain(|_, circle_id| *circle_id != event.circle_id);
+            if let Some(name) = name {
+                let key = name_key(name);
+                if reserved.contains_key(&key) {
+                    return Err(DuplicateCircleName { name: name.clone() });
+                }
+                reserved.insert(key, event.circle_id.clone());
+            }
+        }
+        *names = reserved;
+        Ok(())
+    }
+
+    fn snapshot(&self, circle_id: &CircleId, appended: &AppendedEvents) -> Result<()> {
+        let last = self.events.last_snapshot(circle_id);
+        if !self
+            .snapshot_policy
+            .should_snapshot(appended, last.as_ref())
+        {
+            return Ok(());
+        }
+        let Some(circle) = self.events.load_aggregate(circle_id) else {
+            return Ok(());
+        };
+        self.events.save_snapshot(&circle);
+
+        if let Some(keep) = self.snapshot_retention {
+            self.events.prune_snapshots(circle_id, keep)?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl CircleRepositoryInterface for InMemoryCircleRepository {
+    async fn find_by_id(&self, circle_id: &CircleId) -> Result<Circle, Error> {
+        self.events.load_aggregate(circle_id).ok_or_else(|| {
+            CircleNotFound {
+                circle_id: circle_id.clone(),
+            }
+            .into()
+        })
+    }
+
+    async fn store(
+        &self,
+        current_version: Option<Version>,
+        events: Vec<CircleEvent>,
+    ) -> Result<(), Error> {
+        let Some(last_event) = events.last() else {
+            tracing::info!("No events to store");
+            return Ok(());
+        };
+        let circle_id = last_event.circle_id.clone();
+
+        {
+            let mut names = lock(&self.names);
+            self.events
+                .append_with(current_version, &events, || {
+                    Ok(Self::reserve_names(&mut names, &events)?)
+                })
+                .map_err(|e| match e.downcast::<WrongExpectedVersion>() {
+                    Ok(WrongExpectedVersion { expected, actual }) => VersionConflict {
+                        circle_id: circle_id.clone(),
+                        expected,
+                        actual,
+                    }
+                    .into(),
+                    Err(e) => e,
+                })?;
+        }
+
+        let appended = AppendedEvents {
+            head: last_event.version,
+            count: events.len() as u32,
+            occurred_at: last_event.occurred_at,
+        };
+        if let Err(e) = self.snapshot(&circle_id, &appended) {
+            tracing::error!("Failed to save snapshot: {:?}", e);
+        }
+        self.events.publish(&events).await;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl CircleDuplicateCheckerInterface for InMemoryCircleRepository {
+    async fn check_circle_duplicate(&self, circle: &Circle) -> Result<(), Error> {
+        match lock(&self.names).get(&name_key(circle.name())) {
+            Some(circle_id) if *circle_id != circle.id => Err(DuplicateCircleName {
+                name: circle.name().to_string(),
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use domain::aggregate::{circle::member::Member, value_object::member_id::MemberId};
+
+    use super::*;
+
+    fn create(name: &str) -> anyhow::Result<(Circle, CircleEvent)> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        Circle::create(name.to_string(), 10, owner)
+    }
+
+    #[tokio::test]
+    async fn test_store_rejects_stale_version() -> anyhow::Result<()> {
+        let repository = InMemoryCircleRepository::new(InMemoryEventStore::new());
+        let (circle, created) = create("Music club")?;
+        repository.store(None, vec![created.clone()]).await?;
+
+        let error = repository
+            .store(None, vec![created])
+            .await
+            .err()
+            .ok_or_else(|| Error::msg("the second create should conflict"))?;
+        assert_eq!(
+            error.downcast_ref::<VersionConflict>(),
+            Some(&VersionConflict {
+                circle_id: circle.id.clone(),
+                expected: None,
+                actual: Some(circle.version),
+            })
+        );
+        assert_eq!(repository.find_by_id(&circle.id).await?, circle);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_name_is_reserved_until_disbanded() -> anyhow::Result<()> {
+        let repository = InMemoryCircleRepository::new(InMemoryEventStore::new());
+        let (music, music_created) = create("Music club")?;
+        repository.store(None, vec![music_created]).await?;
+
+        let (rival, rival_created) = create("Music club")?;
+        assert!(repository.check_circle_duplicate(&rival).await.is_err());
+        let error = repository
+            .store(None, vec![rival_created.clone()])
+            .await
+            .err()
+            .ok_or_else(|| Error::msg("the name should be taken"))?;
+        assert!(error.is::<DuplicateCircleName>());
+        // the rejected append left no events behind
+        assert!(repository.find_by_id(&rival.id).await.is_err());
+
+        let version = music.version;
+        let (_, disbanded) = music.disband()?;
+        repository.store(Some(version), vec![disbanded]).await?;
+        repository.store(None, vec![rival_created]).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Error, Result};
+use chrono::{NaiveDateTime, Utc};
+use domain::aggregate::{event_sourced::DomainEvent, value_object::version::Version};
+
+use super::lock;
+use crate::{
+    event_store::{EventLog, WrongExpectedVersion},
+    snapshot_policy::LastSnapshot,
+    subscription::EventHandler,
+    upcaster::EventSchema,
+};
+
+struct Streams<A: EventSchema> {
+    /// Every event in append order; the position of an event is its index + 1.
+    log: Vec<A::Event>,
+    /// Snapshots per stream id, oldest first.
+    snapshots: HashMap<String, Vec<(A, NaiveDateTime)>>,
+}
+
+/// Streams of every aggregate of type `A`, kept in memory with the same contract as
+/// [`EventStore`](crate::event_store::EventStore).
+///
+/// Clones share the streams, so a store handed to a repository and to readers sees
+/// the same events.
+pub struct InMemoryEventStore<A: EventSchema> {
+    streams: Arc<Mutex<Streams<A>>>,
+    subscribers: Arc<Mutex<Vec<Arc<dyn EventHandler<A>>>>>,
+}
+
+impl<A: EventSchema> Clone for InMemoryEventStore<A> {
+    fn clone(&self) -> Self {
+        Self {
+            streams: self.streams.clone(),
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl<A: EventSchema> Default for InMemoryEventStore<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: EventSchema> fmt::Debug for InMemoryEventStore<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InMemoryEventStore")
+            .field("aggregate_type", &A::TYPE_NAME)
+            .field("events", &lock(&self.streams).log.len())
+            .finish()
+    }
+}
+
+impl<A: EventSchema> InMemoryEventStore<A> {
+    pub fn new() -> Self {
+        Self {
+            streams: Arc::new(Mutex::new(Streams {
+                log: vec![],
+                snapshots: HashMap::new(),
+            })),
+            subscribers: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// Calls `handler` with every event appended from now on, in append order.
+    pub fn subscribe(&self, handler: Arc<dyn EventHandler<A>>) {
+        lock(&self.subscribers).push(handler);
+    }
+
+    /// Hands `events` to every subscriber. A failing subscriber is logged and skipped,
+    /// as a projection failure does not fail the command that appended the events.
+    pub async fn publish(&self, events: &[A::Event]) {
+        let subscribers = lock(&self.subscribers).clone();
+        for subscriber in &subscribers {
+            for event in events {
+                if let Err(e) = subscriber.handle(event).await {
+                    tracing::error!("Subscriber {:?} failed: {:?}", subscriber, e);
+                }
+            }
+        }
+    }
+
+    /// Events of the stream with `after < version <= up_to`, in version order.
+    pub fn load(
+        &self,
+        id: &A::Id,
+        after: Option<Version>,
+        up_to: Option<Version>,
+    ) -> Vec<A::Event> {
+        lock(&self.streams)
+            .log
+            .iter()
+            .filter(|event| event.aggregate_id() == id)
+            .filter(|event| after.is_none_or(|after| event.version() > after))
+            .filter(|event| up_to.is_none_or(|up_to| event.version() <= up_to))
+            .cloned()
+            .collect()
+    }
+
+    /// At most `limit` events of the stream in version order, starting at `from`.
+    pub fn read_from(&self, id: &A::Id, from: Option<Version>, limit: u32) -> Vec<A::Event> {
+        let from = from.unwrap_or_default();
+        lock(&self.streams)
+            .log
+            .iter()
+            .filter(|event| event.aggregate_id() == id && event.version() >= from)
+            .take(limit as usize)
+            .cloned()
+            .collect()
+    }
+
+    /// At most `limit` events whose position in the log is after `after`, in log
+    /// order, each with its position.
+    pub fn read_all(&self, after: u64, limit: u32) -> Vec<(u64, A::Event)> {
+        lock(&self.streams)
+            .log
+            .iter()
+            .zip(1..)
+            .skip(after as usize)
+            .take(limit as usize)
+            .map(|(event, position)| (position, event.clone()))
+            .collect()
+    }
+
+    /// Version of the last event that occurred at or before `occurred_at`.
+    pub fn version_at(&self, id: &A::Id, occurred_at: NaiveDateTime) -> Option<Version> {
+        lock(&self.streams)
+            .log
+            .iter()
+            .filter(|event| event.aggregate_id() == id && event.occurred_at() <= occurred_at)
+            .map(|event| event.version())
+            .max()
+    }
+
+    /// Ids of the streams with an event that occurred at or after `since`, or of all
+    /// streams, in the order the streams were started.
+    pub fn stream_ids(&self, since: Option<NaiveDateTime>) -> Vec<A::Id> {
+        let mut ids: Vec<A::Id> = vec![];
+        for event in &lock(&self.streams).log {
+            if since.is_some_and(|since| event.occurred_at() < since) {
+                continue;
+            }
+            if !ids.contains(event.aggregate_id()) {
+                ids.push(event.aggregate_id().clone());
+            }
+        }
+        ids
+    }
+
+    /// Appends `events` to their stream.
+    ///
+    /// Fails with [`WrongExpectedVersion`] when the stream head is not `expected`
+    /// (`None` for a stream that must not exist yet).
+    pub fn append(&self, expected: Option<Version>, events: &[A::Event]) -> Result<()> {
+        self.append_with(expected, events, || Ok(()))
+    }
+
+    /// Like [`append`](Self::append), but runs `hook` once the version check passed and
+    /// keeps the events only when it succeeds, like an
+    /// [`AppendHook`](crate::repository::AppendHook) sharing the transaction.
+    pub fn append_with(
+        &self,
+        expected: Option<Version>,
+        events: &[A::Event],
+        hook: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let Some(first_event) = events.first() else {
+            return Ok(());
+        };
+        let id = first_event.aggregate_id();
+
+        // holding the lock for the whole append serializes concurrent writers
+        let mut streams = lock(&self.streams);
+        let head = streams
+            .log
+            .iter()
+            .filter(|event| event.aggregate_id() == id)
+            .map(|event| event.version())
+            .max();
+        if head != expected {
+            return Err(WrongExpectedVersion {
+                expected,
+                actual: head,
+            }
+            .into());
+        }
+
+        let mut expected_version = expected.map_or_else(Version::new, |v| v.next());
+        for event in events {
+            if event.aggregate_id() != id || event.version() != expected_version {
+                return Err(Error::msg(
+                    "Events must be contiguous and belong to a single stream",
+                ));
+            }
+            expected_version = expected_version.next();
+        }
+
+        hook()?;
+        streams.log.extend_from_slice(events);
+        Ok(())
+    }
+
+    /// Rebuilds the aggregate from its latest snapshot and the events after it.
+    /// Returns `None` when the stream has no events.
+    pub fn load_aggregate(&self, id: &A::Id) -> Option<A> {
+        match self.latest_snapshot(id, None) {
+            Some(mut state) => {
+                for event in &self.load(id, Some(state.version()), None) {
+                    state.apply(event);
+                }
+                Some(state)
+            }
+            None => A::replay(self.load(id, None, None)),
+        }
+    }
+
+    /// Latest snapshot of the stream, limited to versions up to `at_or_below` when given.
+    pub fn latest_snapshot(&self, id: &A::Id, at_or_below: Option<Version>) -> Option<A> {
+        lock(&self.streams)
+            .snapshots
+            .get(&id.to_string())?
+            .iter()
+            .filter(|(state, _)| {
+                at_or_below.is_none_or(|at_or_below| state.version() <= at_or_below)
+            })
+            .max_by_key(|(state, _)| state.version())
+            .map(|(state, _)| state.clone())
+    }
+
+    /// Version and age of the latest snapshot, without its state.
+    pub fn last_snapshot(&self, id: &A::Id) -> Option<LastSnapshot> {
+        lock(&self.streams)
+            .snapshots
+            .get(&id.to_string())?
+            .iter()
+            .max_by_key(|(state, _)| state.version())
+            .map(|(state, created_at)| LastSnapshot {
+                version: state.version(),
+                created_at: *created_at,
+            })
+    }
+
+    pub fn save_snapshot(&self, state: &A) {
+        lock(&self.streams)
+            .snapshots
+            .entry(state.id().to_string())
+            .or_default()
+            .push((state.clone(), Utc::now().naive_utc()));
+        tracing::info!(
+            "Saved snapshot for {} {} at version {}",
+            A::TYPE_NAME,
+            state.id(),
+            state.version()
+        );
+    }
+
+    /// Deletes all but the `keep` newest snapshots of the stream. Returns how many
+    /// were deleted.
+    pub fn prune_snapshots(&self, id: &A::Id, keep: u32) -> Result<u64> {
+        if keep == 0 {
+            return Err(Error::msg("At least one snapshot must be kept"));
+        }
+        let mut streams = lock(&self.streams);
+        let Some(snapshots) = streams.snapshots.get_mut(&id.to_string()) else {
+            return Ok(0);
+        };
+        snapshots.sort_by_key(|(state, _)| state.version());
+        let pruned = snapshots.len().saturating_sub(keep as usize);
+        snapshots.drain(..pruned);
+        Ok(pruned as u64)
+    }
+}
+
+#[async_trait::async_trait]
+impl<A: EventSchema> EventLog<A> for InMemoryEventStore<A> {
+    async fn load(
+        &self,
+        id: &A::Id,
+        after: Option<Version>,
+        up_to: Option<Version>,
+    ) -> Result<Vec<A::Event>> {
+        Ok(InMemoryEventStore::load(self, id, after, up_to))
+    }
+
+    async fn read_from(
+        &self,
+        id: &A::Id,
+        from: Option<Version>,
+        limit: u32,
+    ) -> Result<Vec<A::Event>> {
+        Ok(InMemoryEventStore::read_from(self, id, from, limit))
+    }
+
+    async fn read_all(&self, after: u64, limit: u32) -> Result<Vec<(u64, A::Event)>> {
+        Ok(InMemoryEventStore::read_all(self, after, limit))
+    }
+
+    async fn version_at(&self, id: &A::Id, occurred_at: NaiveDateTime) -> Result<Option<Version>> {
+        Ok(InMemoryEventStore::version_at(self, id, occurred_at))
+    }
+
+    async fn stream_ids(&self, since: Option<NaiveDateTime>) -> Result<Vec<A::Id>> {
+        Ok(InMemoryEventStore::stream_ids(self, since))
+    }
+
+    async fn latest_snapshot(&self, id: &A::Id, at_or_below: Option<Version>) -> Result<Option<A>> {
+        Ok(InMemoryEventStore::latest_snapshot(self, id, at_or_below))
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use anyhow::{Error, Result};
+use domain::interface::command::idempotency_store_interface::{
+    IdempotencyClaim, IdempotencyStoreInterface, StoredResponse, CLAIM_LEASE,
+};
+
+use super::lock;
+
+#[derive(Debug)]
+struct IdempotencyKey {
+    request_hash: String,
+    response: Option<StoredResponse>,
+    claimed_at: Instant,
+}
+
+/// [`IdempotencyStore`](crate::idempotency_store::IdempotencyStore) kept in memory.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryIdempotencyStore {
+    /// Keys by scope and idempotency key, like `idempotency_keys`.
+    keys: Arc<Mutex<HashMap<(String, String), IdempotencyKey>>>,
+}
+
+impl InMemoryIdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl IdempotencyStoreInterface for InMemoryIdempotencyStore {
+    async fn claim(&self, scope: &str, key: &str, request_hash: &str) -> Result<IdempotencyClaim> {
+        let mut keys = lock(&self.keys);
+        let Some(claimed) = keys.get_mut(&(scope.to_string(), key.to_string())) else {
+            keys.insert(
+                (scope.to_string(), key.to_string()),
+                IdempotencyKey {
+                    request_hash: request_hash.to_string(),
+                    response: None,
+                    claimed_at: Instant::now(),
+                },
+            );
+            return Ok(IdempotencyClaim::Acquired);
+        };
+
+        if claimed.request_hash != request_hash {
+            return Ok(IdempotencyClaim::Mismatch);
+        }
+        match &claimed.response {
+            Some(response) => Ok(IdempotencyClaim::Completed(response.clone())),
+            // take the claim over from a request that never finished
+            None if claimed.claimed_at.elapsed() > CLAIM_LEASE => {
+                claimed.claimed_at = Instant::now();
+                Ok(IdempotencyClaim::Acquired)
+            }
+            None => Ok(IdempotencyClaim::InProgress),
+        }
+    }
+
+    async fn complete(&self, scope: &str, key: &str, response: &StoredResponse) -> Result<()> {
+        let mut keys = lock(&self.keys);
+        let claimed = keys
+            .get_mut(&(scope.to_string(), key.to_string()))
+            .ok_or_else(|| Error::msg("Idempotency key was not claimed"))?;
+        claimed.response = Some(response.clone());
+        Ok(())
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> Result<()> {
+        let mut keys = lock(&self.keys);
+        let id = (scope.to_string(), key.to_string());
+        if keys
+            .get(&id)
+            .is_some_and(|claimed| claimed.response.is_none())
+        {
+            keys.remove(&id);
+        }
+        Ok(())
+    }
+}
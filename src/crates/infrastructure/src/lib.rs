@@ -1,5 +1,23 @@
+pub mod backend;
 pub mod circle_duplicate_checker;
+pub mod circle_event_reader;
+pub(crate) mod circle_index;
 pub mod circle_reader;
 pub mod circle_repository;
 pub mod event_publisher;
+pub mod event_store;
+pub mod idempotency_store;
+#[cfg(feature = "in-memory")]
+pub mod in_memory;
 pub(crate) mod maria_db_schema;
+pub mod migration;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod projection_rebuilder;
+pub(crate) mod redis_keys;
+pub mod repository;
+pub mod snapshot_policy;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod subscription;
+pub mod upcaster;
@@ -1,6 +1,6 @@
-pub(super) mod circle_event_data;
-pub(super) mod circle_snapshot_data;
+pub(super) mod event_data;
+pub(super) mod snapshot_data;
 
 // re-export
-pub(super) use circle_event_data::CircleEventData;
-pub(super) use circle_snapshot_data::CircleSnapshotData;
+pub(super) use event_data::StoredEventData;
+pub(super) use snapshot_data::SnapshotData;
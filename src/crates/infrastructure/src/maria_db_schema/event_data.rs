@@ -0,0 +1,118 @@
+// CREATE TABLE IF NOT EXISTS events (
+//     id CHAR(36) NOT NULL PRIMARY KEY,
+//     aggregate_type VARCHAR(100) NOT NULL,
+//     aggregate_id VARCHAR(64) NOT NULL,
+//     version INT NOT NULL,
+//     event_type VARCHAR(100) NOT NULL,
+//     schema_version INT NOT NULL DEFAULT 1,
+//     payload JSON NOT NULL,
+//     metadata JSON NULL,
+//     occurred_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     CONSTRAINT uq_events_stream_version UNIQUE (aggregate_type, aggregate_id, version)
+// );
+
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use domain::aggregate::{
+    event_sourced::DomainEvent,
+    value_object::{event_id::EventId, event_metadata::EventMetadata, version::Version},
+};
+use sqlx::types::Json;
+
+use crate::upcaster::{self, EventSchema};
+
+/// A row of `events`, read the same way from every database backend.
+#[derive(serde::Deserialize, serde::Serialize, Debug, sqlx::FromRow)]
+pub struct StoredEventData {
+    pub id: String,
+    pub aggregate_type: String,
+    pub aggregate_id: String,
+    pub version: i32,
+    pub event_type: String,
+    /// Shape of `payload`; older shapes are upcast when read.
+    pub schema_version: i32,
+    pub payload: Json<serde_json::Value>,
+    /// `NULL` for events written without any metadata.
+    pub metadata: Option<Json<EventMetadata>>,
+    pub occurred_at: NaiveDateTime,
+}
+
+impl StoredEventData {
+    pub fn from_event<A: EventSchema>(event: &A::Event) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            id: event.id().to_string(),
+            aggregate_type: A::TYPE_NAME.to_string(),
+            aggregate_id: event.aggregate_id().to_string(),
+            version: event
+                .version()
+                .try_into()
+                .map_err(|_| anyhow::Error::msg("Failed to convert version to i32"))?,
+            event_type: event.event_type().to_string(),
+            schema_version: A::current_schema_version(),
+            payload: Json(serde_json::to_value(event.payload())?),
+            metadata: (!event.metadata().is_empty()).then(|| Json(event.metadata().clone())),
+            occurred_at: event.occurred_at(),
+        })
+    }
+
+    pub fn into_event<A: EventSchema>(self) -> Result<A::Event, anyhow::Error> {
+        if self.aggregate_type != A::TYPE_NAME {
+            return Err(anyhow::Error::msg(format!(
+                "Event {} belongs to a {} stream, not {}",
+                self.id,
+                self.aggregate_type,
+                A::TYPE_NAME
+            )));
+        }
+        let payload = upcaster::upcast::<A>(&self.event_type, self.schema_version, self.payload.0)?;
+        let payload = serde_json::from_value(payload)?;
+        Ok(A::Event::from_stored(
+            EventId::from_str(&self.id)?,
+            A::Id::from_str(&self.aggregate_id)?,
+            Version::try_from(self.version)
+                .map_err(|_| anyhow::Error::msg("Failed to convert version from i32"))?,
+            self.occurred_at,
+            payload,
+        )
+        .with_metadata(
+            self.metadata
+                .map(|Json(metadata)| metadata)
+                .unwrap_or_default(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use domain::aggregate::{
+        circle::{member::Member, Circle},
+        value_object::member_id::MemberId,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_event_round_trip() -> anyhow::Result<()> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (_, created) = Circle::create("Music club".to_string(), 3, owner)?;
+        let created = created.with_metadata(EventMetadata {
+            actor_id: Some("owner".to_string()),
+            correlation_id: Some("request-1".to_string()),
+            ..EventMetadata::default()
+        });
+
+        let stored = StoredEventData::from_event::<Circle>(&created)?;
+        assert_eq!(stored.aggregate_type, "circle");
+        assert_eq!(stored.event_type, "circle_created");
+        assert_eq!(stored.schema_version, Circle::current_schema_version());
+
+        let restored = stored.into_event::<Circle>()?;
+        assert_eq!(restored.id, created.id);
+        assert_eq!(restored.circle_id, created.circle_id);
+        assert_eq!(restored.version, created.version);
+        assert_eq!(restored.data, created.data);
+        assert_eq!(restored.metadata, created.metadata);
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+// -- スナップショットテーブル
+// CREATE TABLE IF NOT EXISTS snapshots (
+//     id BIGINT AUTO_INCREMENT PRIMARY KEY,
+//     aggregate_type VARCHAR(100) NOT NULL,
+//     aggregate_id VARCHAR(64) NOT NULL,
+//     version INT NOT NULL,
+//     schema_version INT NOT NULL DEFAULT 1,
+//     state JSON NOT NULL,
+//     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     INDEX idx_snapshots_stream_version (aggregate_type, aggregate_id, version DESC)
+// );
+
+use chrono::NaiveDateTime;
+use sqlx::types::Json;
+
+/// Decoding fails when `state` no longer deserialises into `A`.
+#[derive(serde::Deserialize, serde::Serialize, Debug, sqlx::FromRow)]
+pub struct SnapshotData<A> {
+    /// スナップショットの一意識別子 (自動採番)
+    pub id: i64,
+    /// 集約の種類 (例: circle)
+    pub aggregate_type: String,
+    /// スナップショットの対象となる集約の一意識別子
+    pub aggregate_id: String,
+    /// スナップショット作成時点での集約のバージョン番号
+    pub version: i32,
+    /// state の形式のバージョン
+    pub schema_version: i32,
+    /// 集約の完全な状態をJSONとして格納
+    pub state: Json<A>,
+    /// スナップショットが作成された日時
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,418 @@
+//! Versioned schema migrations, embedded into the binary.
+//!
+//! Every [`Backend`] has its own list, applied in order and recorded in
+//! `schema_migrations`. A database recording a version that the list does not hold was
+//! migrated by a newer build, and is refused rather than used with an older schema.
+
+use anyhow::{Error, Result};
+use sqlx::{ColumnIndex, Decode, Encode, Executor, IntoArguments, Pool, Type};
+
+use crate::backend::Backend;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// The MySQL migrations in `sql/migrations/mysql`.
+///
+/// Versions 1 to 9 upgrade databases from before the event store was shared between
+/// aggregates, and version 10 creates the schema they lead to. A new database starts at
+/// 10, and one from before versioned migrations where its schema leaves off (see
+/// [`baseline`]).
+pub const MYSQL_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "circle_events_unique_version",
+        sql: include_str!("../../../../sql/migrations/mysql/0001_circle_events_unique_version.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "circle_event_outbox",
+        sql: include_str!("../../../../sql/migrations/mysql/0002_circle_event_outbox.sql"),
+    },
+    Migration {
+        version: 3,
+        description: "circle_names",
+        sql: include_str!("../../../../sql/migrations/mysql/0003_circle_names.sql"),
+    },
+    Migration {
+        version: 4,
+        description: "idempotency_keys",
+        sql: include_str!("../../../../sql/migrations/mysql/0004_idempotency_keys.sql"),
+    },
+    Migration {
+        version: 5,
+        description: "generic_event_store",
+        sql: include_str!("../../../../sql/migrations/mysql/0005_generic_event_store.sql"),
+    },
+    Migration {
+        version: 6,
+        description: "event_schema_version",
+        sql: include_str!("../../../../sql/migrations/mysql/0006_event_schema_version.sql"),
+    },
+    Migration {
+        version: 7,
+        description: "snapshot_schema_version",
+        sql: include_str!("../../../../sql/migrations/mysql/0007_snapshot_schema_version.sql"),
+    },
+    Migration {
+        version: 8,
+        description: "event_metadata",
+        sql: include_str!("../../../../sql/migrations/mysql/0008_event_metadata.sql"),
+    },
+    Migration {
+        version: 9,
+        description: "global_event_log",
+        sql: include_str!("../../../../sql/migrations/mysql/0009_global_event_log.sql"),
+    },
+    Migration {
+        version: 10,
+        description: "create_event_store",
+        sql: include_str!("../../../../sql/migrations/mysql/0010_create_event_store.sql"),
+    },
+    Migration {
+        version: 11,
+        description: "events_indexes",
+        sql: include_str!("../../../../sql/migrations/mysql/0011_events_indexes.sql"),
+    },
+    Migration {
+        version: 12,
+        description: "normalize_circle_names",
+        sql: include_str!("../../../../sql/migrations/mysql/0012_normalize_circle_names.sql"),
+    },
+];
+
+/// How a backend keeps processes that migrate at the same time from applying a
+/// migration twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationLock {
+    /// Starts the transaction the migrations run in.
+    pub begin: &'static str,
+    /// Returns 1 once the lock is held, or 0 when waiting for it timed out.
+    pub acquire: Option<&'static str>,
+    /// Releases a lock that outlives the transaction.
+    pub release: Option<&'static str>,
+}
+
+/// Tables of the schemas from before the event store was shared.
+const LEGACY_TABLES: &[&str] = &["circle_events", "event_outbox"];
+
+/// The migrations of `migrations` missing from `applied`, in order.
+///
+/// Fails when `applied` holds a version that `migrations` does not know.
+pub fn pending<'a>(migrations: &'a [Migration], applied: &[i64]) -> Result<Vec<&'a Migration>> {
+    if let Some(unknown) = applied
+        .iter()
+        .find(|version| !migrations.iter().any(|m| m.version == **version))
+    {
+        return Err(Error::msg(format!(
+            "The database has migration {} applied, which this build does not know; it was migrated by a newer version",
+            unknown
+        )));
+    }
+    Ok(migrations
+        .iter()
+        .filter(|m| !applied.contains(&m.version))
+        .collect())
+}
+
+/// The columns MySQL migrations 6 to 9 add, in order.
+const UPGRADED_COLUMNS: &[(i64, &str, &str)] = &[
+    (6, "events", "schema_version"),
+    (7, "snapshots", "schema_version"),
+    (8, "events", "metadata"),
+    (9, "events", "sequence"),
+];
+
+/// The last migration a database without recorded migrations already has, given the
+/// `(table, column)` pairs of its schema and the backend's
+/// [`LEGACY_UPGRADES`](Backend::LEGACY_UPGRADES).
+///
+/// A new database skips the legacy upgrades, as the migration after them creates the
+/// schema they lead to. A MySQL database from before versioned migrations is matched
+/// against the tables and columns each upgrade leaves behind, so `migrate` applies the
+/// rest. Other backends never had such a schema and fail on one rather than create an
+/// empty event store beside it.
+pub fn baseline(legacy_upgrades: i64, columns: &[(String, String)]) -> Result<i64> {
+    let has_table = |name: &str| columns.iter().any(|(table, _)| table == name);
+    let has_column = |name: &str, column: &str| {
+        columns
+            .iter()
+            .any(|(table, col)| table == name && col == column)
+    };
+    let legacy_table = LEGACY_TABLES.iter().find(|table| has_table(table));
+    if legacy_table.is_none() && (!has_table("events") || has_column("events", "sequence")) {
+        return Ok(legacy_upgrades);
+    }
+    if legacy_upgrades == 0 {
+        return Err(Error::msg(format!(
+            "The database has {} from before the shared event store, which only the MySQL migrations upgrade",
+            legacy_table.unwrap_or(&"an events table without a sequence column")
+        )));
+    }
+
+    if has_table("circle_events") {
+        if has_table("events") {
+            return Err(Error::msg(
+                "The database has both circle_events and events; finish sql/migrations/mysql/0005_generic_event_store.sql by hand, then run migrate",
+            ));
+        }
+        // 0001 to 0004 only add what is missing, so they run again whichever were applied
+        return Ok(0);
+    }
+    // 0005 renamed circle_events to events
+    let upgraded = UPGRADED_COLUMNS
+        .iter()
+        .take_while(|(_, table, column)| has_column(table, column))
+        .last()
+        .map_or(5, |(version, _, _)| *version);
+    if upgraded == 9 {
+        // the last statement of 0009 drops the outbox
+        return Err(Error::msg(
+            "The database still has event_outbox beside events.sequence; finish sql/migrations/mysql/0009_global_event_log.sql by hand, then run migrate",
+        ));
+    }
+    Ok(upgraded)
+}
+
+/// Versions applied to the database: those recorded in `schema_migrations`, which is
+/// created when missing, and those before them that the schema already had when
+/// recording started (see [`baseline`]).
+async fn applied<DB>(connection: &mut DB::Connection) -> Result<Vec<i64>>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Decode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<DB::Row>,
+{
+    sqlx::raw_sql(DB::MIGRATIONS_TABLE)
+        .execute(&mut *connection)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to create schema_migrations: {}", e)))?;
+    let recorded: Vec<i64> = sqlx::query_scalar("SELECT version FROM schema_migrations")
+        .fetch_all(&mut *connection)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to fetch applied migrations: {}", e)))?;
+    let baseline = match recorded.iter().min() {
+        Some(first) => first - 1,
+        None => {
+            let columns: Vec<(String, String)> = sqlx::query_as(DB::SCHEMA_COLUMNS)
+                .fetch_all(&mut *connection)
+                .await
+                .map_err(|e| Error::msg(format!("Failed to inspect the schema: {}", e)))?;
+            baseline(DB::LEGACY_UPGRADES, &columns)?
+        }
+    };
+    let before: Vec<i64> = DB::MIGRATIONS
+        .iter()
+        .map(|m| m.version)
+        .filter(|version| *version <= baseline && !recorded.contains(version))
+        .collect();
+    Ok([before, recorded].concat())
+}
+
+/// The migrations not applied to `db` yet. Creates `schema_migrations` when it is
+/// missing.
+pub async fn pending_migrations<DB>(db: &Pool<DB>) -> Result<Vec<&'static Migration>>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Decode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<DB::Row>,
+{
+    let mut connection = db.acquire().await?;
+    let applied = applied::<DB>(&mut connection).await?;
+    pending(DB::MIGRATIONS, &applied)
+}
+
+/// Applies the pending migrations in order under the backend's [`MigrationLock`] and
+/// returns them.
+///
+/// They run in one transaction, except on MySQL, which commits schema changes at once:
+/// there each migration is recorded as soon as it completes, and one that fails part
+/// way has to be finished by hand.
+pub async fn migrate<DB>(db: &Pool<DB>) -> Result<Vec<&'static Migration>>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> String: Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<DB::Row>,
+{
+    let lock = DB::MIGRATION_LOCK;
+    let mut transaction = db.begin_with(lock.begin).await?;
+    if let Some(acquire) = lock.acquire {
+        let locked: Option<i64> = sqlx::query_scalar(acquire)
+            .fetch_one(&mut *transaction)
+            .await
+            .map_err(|e| Error::msg(format!("Failed to lock schema_migrations: {}", e)))?;
+        if locked != Some(1) {
+            return Err(Error::msg(
+                "Timed out waiting for another process to finish migrating",
+            ));
+        }
+    }
+
+    let result = async {
+        let applied = applied::<DB>(&mut transaction).await?;
+        let pending = pending(DB::MIGRATIONS, &applied)?;
+        for migration in &pending {
+            sqlx::raw_sql(migration.sql)
+                .execute(&mut *transaction)
+                .await
+                .map_err(|e| {
+                    Error::msg(format!(
+                        "Failed to apply migration {}: {}",
+                        migration.version, e
+                    ))
+                })?;
+            sqlx::query(&DB::sql(
+                "INSERT INTO schema_migrations (version, description) VALUES (?, ?)",
+            ))
+            .bind(migration.version)
+            .bind(migration.description)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| Error::msg(format!("Failed to record migration: {}", e)))?;
+        }
+        Ok::<_, Error>(pending)
+    }
+    .await;
+
+    // released whether or not the migrations succeeded, as the connection goes back
+    // to the pool holding it otherwise
+    if let Some(release) = lock.release {
+        sqlx::query(release)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| Error::msg(format!("Failed to unlock schema_migrations: {}", e)))?;
+    }
+    let pending = result?;
+    transaction.commit().await?;
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{baseline, pending, Migration, MYSQL_MIGRATIONS};
+
+    const MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 1,
+            description: "first",
+            sql: "",
+        },
+        Migration {
+            version: 2,
+            description: "second",
+            sql: "",
+        },
+        Migration {
+            version: 3,
+            description: "third",
+            sql: "",
+        },
+    ];
+
+    #[test]
+    fn test_pending_keeps_the_order_of_the_list() -> anyhow::Result<()> {
+        let versions = |applied: &[i64]| -> anyhow::Result<Vec<i64>> {
+            Ok(pending(MIGRATIONS, applied)?
+                .iter()
+                .map(|m| m.version)
+                .collect())
+        };
+        assert_eq!(versions(&[])?, vec![1, 2, 3]);
+        assert_eq!(versions(&[2, 1])?, vec![3]);
+        assert_eq!(versions(&[1, 2, 3])?, Vec::<i64>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pending_refuses_versions_it_does_not_know() {
+        let error =
+            pending(MIGRATIONS, &[1, 2, 3, 4]).expect_err("a newer schema should be refused");
+        assert!(error.to_string().contains("migration 4"));
+    }
+
+    #[test]
+    fn test_mysql_migrations_are_in_order() {
+        assert!(MYSQL_MIGRATIONS
+            .windows(2)
+            .all(|pair| pair[0].version < pair[1].version));
+    }
+
+    fn columns(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(table, column)| (table.to_string(), column.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_new_databases_skip_the_legacy_upgrades() -> anyhow::Result<()> {
+        assert_eq!(baseline(9, &[])?, 9);
+        assert_eq!(baseline(0, &[])?, 0);
+        let current = columns(&[
+            ("events", "id"),
+            ("events", "sequence"),
+            ("snapshots", "id"),
+        ]);
+        assert_eq!(baseline(9, &current)?, 9);
+        assert_eq!(baseline(0, &current)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_schemas_resume_where_they_left_off() -> anyhow::Result<()> {
+        let before_shared_store = columns(&[
+            ("circle_events", "circle_id"),
+            ("circle_snapshots", "state"),
+        ]);
+        assert_eq!(baseline(9, &before_shared_store)?, 0);
+
+        let mut shared_store = columns(&[
+            ("events", "aggregate_type"),
+            ("snapshots", "aggregate_type"),
+            ("event_outbox", "aggregate_id"),
+        ]);
+        assert_eq!(baseline(9, &shared_store)?, 5);
+        shared_store.extend(columns(&[
+            ("events", "schema_version"),
+            ("snapshots", "schema_version"),
+        ]));
+        assert_eq!(baseline(9, &shared_store)?, 7);
+        shared_store.extend(columns(&[("events", "metadata")]));
+        assert_eq!(baseline(9, &shared_store)?, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_half_applied_and_foreign_legacy_schemas_are_refused() {
+        let half_renamed = columns(&[("circle_events", "circle_id"), ("events", "id")]);
+        assert!(baseline(9, &half_renamed).is_err());
+
+        let outbox_left = columns(&[
+            ("events", "schema_version"),
+            ("snapshots", "schema_version"),
+            ("events", "metadata"),
+            ("events", "sequence"),
+            ("event_outbox", "id"),
+        ]);
+        assert!(baseline(9, &outbox_left).is_err_and(|e| e.to_string().contains("0009")));
+
+        // only the MySQL migrations upgrade a legacy schema
+        let before_global_log = columns(&[("events", "id"), ("events", "version")]);
+        assert!(baseline(0, &before_global_log).is_err());
+        assert!(baseline(0, &columns(&[("circle_events", "circle_id")])).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! The event store, snapshot store and command-side tables in PostgreSQL, behind the
+//! `postgres` feature.
+//!
+//! The stores of this crate run on PostgreSQL as [`Backend`]s, e.g.
+//! `CircleRepository<Postgres>`, over the tables of `sql/migrations/postgres`. In
+//! addition every append sends `NOTIFY` on [`EVENTS_CHANNEL`] when it commits, so
+//! [`PostgresSubscription`](subscription::PostgresSubscription) workers, in this
+//! process or another, wake up as soon as there is something to project.
+
+use std::{borrow::Cow, fmt::Write};
+
+use sqlx::{postgres::PgQueryResult, Postgres};
+
+use crate::{
+    backend::Backend,
+    migration::{Migration, MigrationLock},
+};
+
+pub mod subscription;
+
+/// Channel notified with the aggregate type after every committed append.
+pub const EVENTS_CHANNEL: &str = "events";
+
+impl Backend for Postgres {
+    const NOW: &'static str = "(NOW() AT TIME ZONE 'UTC')";
+
+    // delivered to listeners when the transaction commits, and dropped if it rolls back
+    const APPENDED: Option<&'static str> = Some("SELECT pg_notify('events', ?)");
+
+    const MIGRATIONS: &'static [Migration] = MIGRATIONS;
+
+    const MIGRATIONS_TABLE: &'static str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version BIGINT NOT NULL PRIMARY KEY,
+    description VARCHAR(255) NOT NULL,
+    applied_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC')
+)";
+
+    // held until commit, so builds starting together apply each migration once
+    const MIGRATION_LOCK: MigrationLock = MigrationLock {
+        begin: "BEGIN",
+        acquire: Some(
+            "SELECT CAST(1 AS BIGINT) FROM pg_advisory_xact_lock(hashtext('schema_migrations'))",
+        ),
+        release: None,
+    };
+
+    const SCHEMA_COLUMNS: &'static str = "SELECT CAST(table_name AS TEXT) AS table_name, \
+         CAST(column_name AS TEXT) AS column_name \
+         FROM information_schema.columns WHERE table_schema = current_schema()";
+
+    /// Numbers the placeholders `$1`, `$2`, ... in order, skipping quoted text.
+    fn sql(query: &str) -> Cow<'_, str> {
+        let mut sql = String::with_capacity(query.len() + 16);
+        let mut parameters = 0;
+        let mut quoted = false;
+        for c in query.chars() {
+            match c {
+                '\'' => {
+                    quoted = !quoted;
+                    sql.push(c);
+                }
+                '?' if !quoted => {
+                    parameters += 1;
+                    let _ = write!(sql, "${}", parameters);
+                }
+                c => sql.push(c),
+            }
+        }
+        Cow::Owned(sql)
+    }
+
+    fn rows_affected(result: &PgQueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
+
+/// The PostgreSQL migrations in `sql/migrations/postgres`.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create_event_store",
+        sql: include_str!("../../../../sql/migrations/postgres/0001_create_event_store.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "events_indexes",
+        sql: include_str!("../../../../sql/migrations/postgres/0002_events_indexes.sql"),
+    },
+    Migration {
+        version: 3,
+        description: "normalize_circle_names",
+        sql: include_str!("../../../../sql/migrations/postgres/0003_normalize_circle_names.sql"),
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc, time::Duration};
+
+    use domain::{
+        aggregate::{
+            circle::{event::CircleEvent, member::Member, Circle},
+            event_sourced::Aggregate,
+            value_object::member_id::MemberId,
+        },
+        interface::command::{
+            circle_repository_interface::CircleRepositoryInterface,
+            idempotency_store_interface::{
+                IdempotencyClaim, IdempotencyStoreInterface, StoredResponse,
+            },
+        },
+        test_utils::circle_conformance,
+    };
+    use sqlx::Postgres;
+
+    use super::{subscription::PostgresSubscription, EVENTS_CHANNEL};
+    use crate::{
+        backend::Backend,
+        circle_repository::CircleRepository,
+        event_store::{to_i32, EventStore, WrongExpectedVersion},
+        idempotency_store::IdempotencyStore,
+        migration::migrate,
+        snapshot_policy::{EventsSinceLastSnapshot, EveryNEvents, Never, SnapshotPolicy},
+        subscription::EventHandler,
+    };
+
+    async fn connect() -> anyhow::Result<sqlx::PgPool> {
+        let db = sqlx::PgPool::connect(&std::env::var("DATABASE_URL")?).await?;
+        migrate(&db).await?;
+        Ok(db)
+    }
+
+    #[derive(Debug)]
+    struct Ignore;
+
+    #[async_trait::async_trait]
+    impl EventHandler<Circle> for Ignore {
+        async fn handle(&self, _event: &CircleEvent) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sql_numbers_placeholders_outside_quotes() {
+        assert_eq!(
+            Postgres::sql("UPDATE t SET a = ?, b = '?' WHERE c = ? AND d < ?"),
+            "UPDATE t SET a = $1, b = '?' WHERE c = $2 AND d < $3"
+        );
+        assert_eq!(
+            Postgres::APPENDED.map(Postgres::sql),
+            Some(format!("SELECT pg_notify('{}', $1)", EVENTS_CHANNEL).into())
+        );
+    }
+
+    // FIXME: ignore test because it requires a running PostgreSQL at DATABASE_URL
+    #[tokio::test]
+    #[ignore]
+    async fn test_append_losing_a_race_reports_the_winning_head() -> anyhow::Result<()> {
+        let db = connect().await?;
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (circle, created) = Circle::create("Race club".to_string(), 10, owner)?;
+
+        // a writer that inserts the same version without taking a position, so the
+        // append below only runs into it on the unique key
+        let mut winner = db.begin().await?;
+        sqlx::query(
+            "INSERT INTO events (id, sequence, aggregate_type, aggregate_id, version, event_type, payload) VALUES ($1, $2, $3, $4, $5, 'circle_created', '{}')",
+        )
+        .bind(circle.id.to_string().chars().rev().collect::<String>())
+        .bind(-chrono::Utc::now().timestamp_micros())
+        .bind(Circle::TYPE_NAME)
+        .bind(circle.id.to_string())
+        .bind(to_i32(circle.version)?)
+        .execute(&mut *winner)
+        .await?;
+
+        let events = EventStore::<Circle, Postgres>::new(db.clone());
+        let appending = tokio::spawn(async move {
+            let mut transaction = db.begin().await?;
+            events.append(&mut transaction, None, &[created]).await
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        winner.commit().await?;
+
+        let error = appending
+            .await?
+            .expect_err("the append should lose to the committed writer");
+        assert_eq!(
+            error.downcast_ref::<WrongExpectedVersion>(),
+            Some(&WrongExpectedVersion {
+                expected: None,
+                actual: Some(circle.version),
+            })
+        );
+        Ok(())
+    }
+
+    // FIXME: ignore test because it requires a running PostgreSQL at DATABASE_URL
+    #[tokio::test]
+    #[ignore]
+    async fn test_repository_conformance() -> anyhow::Result<()> {
+        let db = connect().await?;
+        // snapshots on every append, never, and every few events
+        let policies: [Arc<dyn SnapshotPolicy>; 3] = [
+            Arc::new(EveryNEvents(1)),
+            Arc::new(Never),
+            Arc::new(EventsSinceLastSnapshot(5)),
+        ];
+        for policy in policies {
+            let repository = CircleRepository::new(db.clone())
+                .with_snapshot_policy(policy)
+                .with_snapshot_retention(2);
+            circle_conformance::run_repository_suite(&repository).await?;
+        }
+        Ok(())
+    }
+
+    // FIXME: ignore test because it requires a running PostgreSQL at DATABASE_URL
+    #[tokio::test]
+    #[ignore]
+    async fn test_append_wakes_listeners_on_commit() -> anyhow::Result<()> {
+        let db = connect().await?;
+        let subscription =
+            PostgresSubscription::<Circle>::new(db.clone(), "wake", Arc::new(Ignore));
+        let mut listener = subscription.listen().await?;
+
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        // names stay reserved, so every run needs a new one
+        let name = format!("Wake {}", chrono::Utc::now().timestamp_micros());
+        let (_, created) = Circle::create(name, 10, owner)?;
+        CircleRepository::new(db).store(None, vec![created]).await?;
+
+        let notification = tokio::time::timeout(Duration::from_secs(1), listener.recv()).await??;
+        assert_eq!(notification.payload(), Circle::TYPE_NAME);
+        Ok(())
+    }
+
+    // FIXME: ignore test because it requires a running PostgreSQL at DATABASE_URL
+    #[tokio::test]
+    #[ignore]
+    async fn test_claim_after_completion_replays_the_response() -> anyhow::Result<()> {
+        let store = IdempotencyStore::new(connect().await?);
+        let key = format!("key-{}", chrono::Utc::now().timestamp_micros());
+        assert_eq!(
+            store.claim("circles", &key, "hash").await?,
+            IdempotencyClaim::Acquired
+        );
+        assert_eq!(
+            store.claim("circles", &key, "hash").await?,
+            IdempotencyClaim::InProgress
+        );
+
+        let response = StoredResponse {
+            status: 201,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: b"{}".to_vec(),
+        };
+        store.complete("circles", &key, &response).await?;
+        assert_eq!(
+            store.claim("circles", &key, "hash").await?,
+            IdempotencyClaim::Completed(response)
+        );
+        Ok(())
+    }
+
+    // FIXME: ignore test because it requires a running PostgreSQL at DATABASE_URL
+    #[tokio::test]
+    #[ignore]
+    async fn test_catch_up_saves_the_checkpoint() -> anyhow::Result<()> {
+        let db = connect().await?;
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let name = format!("Checkpoint {}", chrono::Utc::now().timestamp_micros());
+        let (_, created) = Circle::create(name.clone(), 10, owner)?;
+        CircleRepository::new(db.clone())
+            .store(None, vec![created])
+            .await?;
+
+        let subscription = PostgresSubscription::<Circle>::new(db, &name, Arc::new(Ignore));
+        while subscription.catch_up().await? > 0 {}
+        assert!(subscription.checkpoint().await? > 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use sqlx::{postgres::PgListener, PgPool, Postgres};
+
+use super::EVENTS_CHANNEL;
+use crate::{
+    event_store::{EventLog, EventStore},
+    subscription::{EventHandler, Subscription},
+    upcaster::EventSchema,
+};
+
+/// How long to wait for a notification before reading the log anyway, in case one
+/// was lost while the listener reconnected.
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// [`Subscription`] that sleeps on `LISTEN` [`EVENTS_CHANNEL`] between batches rather
+/// than polling, so a commit wakes it at once.
+#[derive(Debug)]
+pub struct PostgresSubscription<A: EventSchema> {
+    db: PgPool,
+    subscription: Subscription<A, Postgres>,
+    poll_interval: Duration,
+}
+
+impl<A: EventSchema> PostgresSubscription<A>
+where
+    EventStore<A, Postgres>: EventLog<A>,
+{
+    pub fn new(db: PgPool, name: &str, handler: Arc<dyn EventHandler<A>>) -> Self {
+        Self {
+            subscription: Subscription::new(db.clone(), name, handler),
+            db,
+            poll_interval: FALLBACK_POLL_INTERVAL,
+        }
+    }
+
+    /// Position of the last event handled, 0 before the first one.
+    pub async fn checkpoint(&self) -> Result<u64> {
+        self.subscription.checkpoint().await
+    }
+
+    /// See [`Subscription::catch_up`].
+    pub async fn catch_up(&self) -> Result<usize> {
+        self.subscription.catch_up().await
+    }
+
+    pub async fn start_processing(&self) {
+        let name = self.subscription.name();
+        // without a listener the subscription still catches up, only later
+        let mut listener = match self.listen().await {
+            Ok(listener) => Some(listener),
+            Err(e) => {
+                tracing::error!("Subscription {} cannot listen: {:?}", name, e);
+                None
+            }
+        };
+        loop {
+            match self.catch_up().await {
+                // keep reading while there is a backlog
+                Ok(handled) if handled == self.subscription.batch_size() as usize => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!("Subscription {} failed: {:?}", name, e),
+            }
+            self.wait_for_events(listener.as_mut()).await;
+        }
+    }
+
+    pub(super) async fn listen(&self) -> Result<PgListener> {
+        let mut listener = PgListener::connect_with(&self.db).await?;
+        listener.listen(EVENTS_CHANNEL).await?;
+        Ok(listener)
+    }
+
+    /// Returns on a notification, on a lost connection (notifications may have been
+    /// missed) or after the poll interval, whichever comes first.
+    async fn wait_for_events(&self, listener: Option<&mut PgListener>) {
+        let Some(listener) = listener else {
+            tokio::time::sleep(self.poll_interval).await;
+            return;
+        };
+        match tokio::time::timeout(self.poll_interval, listener.try_recv()).await {
+            Ok(Ok(_)) | Err(_) => {}
+            Ok(Err(e)) => {
+                tracing::error!(
+                    "Subscription {} stopped listening: {:?}",
+                    self.subscription.name(),
+                    e
+                );
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use domain::{
+    aggregate::{circle::Circle, value_object::circle_id::CircleId},
+    interface::query::projection_rebuilder_interface::{
+        ProjectionRebuilderInterface, RebuildInProgress, RebuildProgress, RebuildState,
+    },
+};
+use redis::AsyncCommands;
+use tokio::sync::Mutex;
+
+use crate::{
+    event_publisher::RedisProjectionHandler, event_store::EventLog, redis_keys::RedisKeys,
+};
+
+/// Regenerates the Redis read model from the circle streams in `events`.
+///
+/// Every stream is projected into a staging namespace first, then the staged keys
+/// replace the live ones in a single `MULTI`/`EXEC`, so readers never see a partial
+/// projection. Streams that received events while the rebuild was running are
+/// projected again after the swap.
+#[derive(Clone, Debug)]
+pub struct ProjectionRebuilder {
+    redis_client: redis::Client,
+    events: Arc<dyn EventLog<Circle>>,
+    projection: Arc<RedisProjectionHandler>,
+    progress: Arc<Mutex<RebuildProgress>>,
+}
+
+impl ProjectionRebuilder {
+    pub fn new(redis_client: redis::Client, events: Arc<dyn EventLog<Circle>>) -> Self {
+        let projection = Arc::new(RedisProjectionHandler::new(
+            redis_client.clone(),
+            events.clone(),
+        ));
+        Self {
+            redis_client,
+            events,
+            projection,
+            progress: Arc::new(Mutex::new(RebuildProgress::default())),
+        }
+    }
+
+    async fn begin(&self) -> Result<NaiveDateTime> {
+        let mut progress = self.progress.lock().await;
+        if progress.state == RebuildState::Running {
+            return Err(RebuildInProgress.into());
+        }
+        let started_at = Utc::now().naive_utc();
+        *progress = RebuildProgress {
+            state: RebuildState::Running,
+            started_at: Some(started_at),
+            ..RebuildProgress::default()
+        };
+        Ok(started_at)
+    }
+
+    async fn run(&self, started_at: NaiveDateTime) -> Result<()> {
+        let staging = RedisKeys::staging(&started_at.and_utc().timestamp_millis().to_string());
+        let result = self.rebuild_into(&staging, started_at).await;
+        if result.is_err() {
+            if let Err(e) = self.delete_namespace(&staging).await {
+                tracing::error!("Failed to clean up staged projection: {:?}", e);
+            }
+        }
+
+        let mut progress = self.progress.lock().await;
+        progress.finished_at = Some(Utc::now().naive_utc());
+        match &result {
+            Ok(()) => progress.state = RebuildState::Completed,
+            Err(e) => {
+                progress.state = RebuildState::Failed;
+                progress.error = Some(e.to_string());
+            }
+        }
+        result
+    }
+
+    async fn rebuild_into(&self, staging: &RedisKeys, started_at: NaiveDateTime) -> Result<()> {
+        let circle_ids = self.stage(staging).await?;
+        self.swap_into_live(staging, &circle_ids).await?;
+        self.catch_up(started_at).await?;
+        tracing::info!("Projection rebuild finished");
+        Ok(())
+    }
+
+    /// Projects every stream into `staging` and returns the ids of the circles.
+    async fn stage(&self, staging: &RedisKeys) -> Result<Vec<CircleId>> {
+        let circle_ids = self.events.stream_ids(None).await?;
+        self.progress.lock().await.total = circle_ids.len() as u64;
+        tracing::info!("Rebuilding projection of {} circles", circle_ids.len());
+
+        for circle_id in &circle_ids {
+            let projected = self
+                .projection
+                .rebuild_circle_from_events(circle_id)
+                .await?;
+            self.projection
+                .save_circle_to_redis(staging, &projected, None)
+                .await?;
+            self.progress.lock().await.processed += 1;
+        }
+        Ok(circle_ids)
+    }
+
+    /// Projects the streams that changed while the rebuild was running into the live
+    /// namespace again.
+    async fn catch_up(&self, started_at: NaiveDateTime) -> Result<()> {
+        let live = RedisKeys::live();
+        for circle_id in self.events.stream_ids(Some(started_at)).await? {
+            let previous = self.projection.projected_circle(&live, &circle_id).await?;
+            let projected = self
+                .projection
+                .rebuild_circle_from_events(&circle_id)
+                .await?;
+            self.projection
+                .save_circle_to_redis(&live, &projected, previous.as_ref())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn scan_keys(&self, pattern: String) -> Result<Vec<String>> {
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("Failed to connect to Redis: {}", e)))?;
+        let mut iter = conn
+            .scan_match::<_, String>(pattern)
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("Failed to scan Redis keys: {}", e)))?;
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    async fn swap_into_live(&self, staging: &RedisKeys, circle_ids: &[CircleId]) -> Result<()> {
+        let live = RedisKeys::live();
+        let staged_keys: HashSet<String> = self
+            .scan_keys(staging.all_pattern())
+            .await?
+            .into_iter()
+            .collect();
+        let rebuilt: HashSet<String> = circle_ids
+            .iter()
+            .map(|circle_id| live.circle(&circle_id.to_string()))
+            .collect();
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for key in self.scan_keys(live.circle_pattern()).await? {
+            if !rebuilt.contains(&key) {
+                pipe.del(key);
+            }
+        }
+        for circle_id in circle_ids {
+            let circle_id = circle_id.to_string();
+            pipe.rename(staging.circle(&circle_id), live.circle(&circle_id));
+        }
+        // RENAME fails on a missing source, so drop sets the rebuild did not produce
+        for (staged, current) in staging.collections().into_iter().zip(live.collections()) {
+            if staged_keys.contains(&staged) {
+                pipe.rename(staged, current);
+            } else {
+                pipe.del(current);
+            }
+        }
+
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("Failed to connect to Redis: {}", e)))?;
+        let _: () = pipe
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("Failed to swap projection: {}", e)))?;
+        Ok(())
+    }
+
+    async fn delete_namespace(&self, keys: &RedisKeys) -> Result<()> {
+        let staged_keys = self.scan_keys(keys.all_pattern()).await?;
+        if staged_keys.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self
+            .redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("Failed to connect to Redis: {}", e)))?;
+        let _: () = conn
+            .del(staged_keys)
+            .await
+            .map_err(|e| anyhow::Error::msg(format!("Failed to delete staged keys: {}", e)))?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectionRebuilderInterface for ProjectionRebuilder {
+    async fn start(&self) -> Result<()> {
+        let started_at = self.begin().await?;
+        let rebuilder = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = rebuilder.run(started_at).await {
+                tracing::error!("Projection rebuild failed: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    async fn rebuild(&self) -> Result<RebuildProgress> {
+        let started_at = self.begin().await?;
+        self.run(started_at).await?;
+        Ok(self.progress().await)
+    }
+
+    async fn progress(&self) -> RebuildProgress {
+        self.progress.lock().await.clone()
+    }
+}
+
+#[cfg(all(test, feature = "in-memory"))]
+mod tests {
+    use std::{collections::BTreeSet, str::FromStr};
+
+    use domain::{
+        aggregate::{circle::member::Member, value_object::member_id::MemberId},
+        interface::query::circle_reader_interface::CircleSortKey,
+    };
+    use redis::aio::MultiplexedConnection;
+
+    use super::*;
+    use crate::in_memory::event_store::InMemoryEventStore;
+
+    /// The tests flush the database and share its live namespace.
+    static REDIS: Mutex<()> = Mutex::const_new(());
+
+    /// `TEST_REDIS_URL` names a Redis database the tests may flush.
+    async fn connect() -> Result<(redis::Client, MultiplexedConnection)> {
+        let client = redis::Client::open(std::env::var("TEST_REDIS_URL")?)?;
+        let mut conn = client.get_multiplexed_async_connection().await?;
+        let _: () = redis::cmd("FLUSHDB").query_async(&mut conn).await?;
+        Ok((client, conn))
+    }
+
+    fn create(events: &InMemoryEventStore<Circle>, name: &str) -> Result<Circle> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (circle, created) = Circle::create(name.to_string(), 10, owner)?;
+        events.append(None, &[created])?;
+        Ok(circle)
+    }
+
+    /// A rebuilder over `music` and `book`, with a live projection that still holds a
+    /// circle the log does not.
+    async fn setup() -> Result<(ProjectionRebuilder, MultiplexedConnection, BTreeSet<String>)> {
+        let (client, mut conn) = connect().await?;
+        let events = InMemoryEventStore::new();
+        let ids = [
+            create(&events, "Music club")?,
+            create(&events, "Book club")?,
+        ]
+        .iter()
+        .map(|circle| circle.id.to_string())
+        .collect();
+        let live = RedisKeys::live();
+        let _: () = conn.set(live.circle("stale"), "{}").await?;
+        let _: () = conn.sadd(live.list(), "stale").await?;
+        Ok((
+            ProjectionRebuilder::new(client, Arc::new(events)),
+            conn,
+            ids,
+        ))
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis at TEST_REDIS_URL"]
+    async fn test_stage_leaves_the_live_projection_alone() -> Result<()> {
+        let _redis = REDIS.lock().await;
+        let (rebuilder, mut conn, ids) = setup().await?;
+        let staging = RedisKeys::staging("test");
+
+        rebuilder.stage(&staging).await?;
+        let staged: BTreeSet<String> = conn.smembers(staging.list()).await?;
+        assert_eq!(staged, ids);
+        for id in &ids {
+            let exists: bool = conn.exists(staging.circle(id)).await?;
+            assert!(exists);
+        }
+        let live: BTreeSet<String> = conn.smembers(RedisKeys::live().list()).await?;
+        assert_eq!(live, BTreeSet::from(["stale".to_string()]));
+        let progress = rebuilder.progress().await;
+        assert_eq!((progress.total, progress.processed), (2, 2));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis at TEST_REDIS_URL"]
+    async fn test_swap_replaces_the_live_projection() -> Result<()> {
+        let _redis = REDIS.lock().await;
+        let (rebuilder, mut conn, ids) = setup().await?;
+        let staging = RedisKeys::staging("test");
+        let live = RedisKeys::live();
+
+        let circle_ids = rebuilder.stage(&staging).await?;
+        rebuilder.swap_into_live(&staging, &circle_ids).await?;
+        let listed: BTreeSet<String> = conn.smembers(live.list()).await?;
+        assert_eq!(listed, ids);
+        let stale: bool = conn.exists(live.circle("stale")).await?;
+        assert!(!stale);
+        // no circle was disbanded, so the rebuild produced no archive to swap in
+        let archived: bool = conn.exists(live.archived()).await?;
+        assert!(!archived);
+        let indexed: usize = conn.zcard(live.index(CircleSortKey::Name)).await?;
+        assert_eq!(indexed, 2);
+        assert!(rebuilder.scan_keys(staging.all_pattern()).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis at TEST_REDIS_URL"]
+    async fn test_catch_up_projects_streams_changed_during_the_rebuild() -> Result<()> {
+        let _redis = REDIS.lock().await;
+        let (client, mut conn) = connect().await?;
+        let events = InMemoryEventStore::new();
+        let music = create(&events, "Music club")?;
+        let rebuilder = ProjectionRebuilder::new(client, Arc::new(events.clone()));
+        let staging = RedisKeys::staging("test");
+        let live = RedisKeys::live();
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let started_at = Utc::now().naive_utc();
+        let circle_ids = rebuilder.stage(&staging).await?;
+        // renamed after the stream was staged
+        let version = music.version;
+        let (_, renamed) = music.update(Some("Football club".to_string()), None)?;
+        events.append(Some(version), &[renamed])?;
+        rebuilder.swap_into_live(&staging, &circle_ids).await?;
+
+        rebuilder.catch_up(started_at).await?;
+        let json: String = conn.get(live.circle(&circle_ids[0].to_string())).await?;
+        let circle: Circle = serde_json::from_str(&json)?;
+        assert_eq!(circle.name, "Football club");
+        // the entry of the old name was replaced
+        let names: Vec<String> = conn.zrange(live.index(CircleSortKey::Name), 0, -1).await?;
+        assert_eq!(names.len(), 1);
+        assert!(names[0].starts_with("Football club"));
+        Ok(())
+    }
+}
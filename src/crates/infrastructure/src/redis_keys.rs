@@ -0,0 +1,62 @@
+use domain::interface::query::circle_reader_interface::CircleSortKey;
+
+use crate::circle_index::SORT_KEYS;
+
+/// Key layout of the Redis read model.
+///
+/// The live projection has no prefix. A rebuild writes under a staging prefix
+/// and renames its keys into the live namespace once every stream is projected.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct RedisKeys {
+    prefix: String,
+}
+
+impl RedisKeys {
+    pub(crate) fn live() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn staging(rebuild_id: &str) -> Self {
+        Self {
+            prefix: format!("rebuild:{}:", rebuild_id),
+        }
+    }
+
+    pub(crate) fn circle(&self, circle_id: &str) -> String {
+        format!("{}circle:{}", self.prefix, circle_id)
+    }
+
+    /// Pattern matching every circle key of this namespace, for `SCAN MATCH`.
+    pub(crate) fn circle_pattern(&self) -> String {
+        format!("{}circle:*", self.prefix)
+    }
+
+    pub(crate) fn list(&self) -> String {
+        format!("{}circles:list", self.prefix)
+    }
+
+    pub(crate) fn archived(&self) -> String {
+        format!("{}circles:archived", self.prefix)
+    }
+
+    pub(crate) fn index(&self, sort_key: CircleSortKey) -> String {
+        let name = match sort_key {
+            CircleSortKey::Capacity => "capacity",
+            CircleSortKey::CreatedAt => "created_at",
+            CircleSortKey::Name => "name",
+        };
+        format!("{}circles:index:{}", self.prefix, name)
+    }
+
+    /// Every key that aggregates several circles, as opposed to the per-circle keys.
+    pub(crate) fn collections(&self) -> Vec<String> {
+        let mut keys = vec![self.list(), self.archived()];
+        keys.extend(SORT_KEYS.iter().map(|sort_key| self.index(*sort_key)));
+        keys
+    }
+
+    /// Pattern matching every key of this namespace, for `SCAN MATCH`.
+    pub(crate) fn all_pattern(&self) -> String {
+        format!("{}*", self.prefix)
+    }
+}
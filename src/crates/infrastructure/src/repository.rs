@@ -0,0 +1,161 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use domain::aggregate::{
+    event_sourced::{Aggregate, DomainEvent},
+    value_object::{event_metadata::EventMetadata, version::Version},
+};
+use sqlx::{
+    types::Json, ColumnIndex, Database, Decode, Encode, Executor, FromRow, IntoArguments, MySql,
+    Pool, Type,
+};
+
+use crate::{
+    backend::Backend,
+    event_store::{EventLog, EventStore},
+    maria_db_schema::{SnapshotData, StoredEventData},
+    snapshot_policy::{AppendedEvents, EveryNEvents, SnapshotPolicy},
+    upcaster::EventSchema,
+};
+
+/// Writes that must commit atomically with the appended events, such as uniqueness
+/// reservations kept next to the stream.
+#[async_trait::async_trait]
+pub trait AppendHook<A: Aggregate, DB: Database = MySql>: Send + Sync + fmt::Debug {
+    async fn on_append(&self, connection: &mut DB::Connection, events: &[A::Event]) -> Result<()>;
+}
+
+/// Loads and saves aggregates of type `A` through an [`EventStore`], using snapshots
+/// to bound the number of events replayed per load.
+#[derive(Debug)]
+pub struct Repository<A: EventSchema, DB: Database = MySql> {
+    db: Pool<DB>,
+    events: EventStore<A, DB>,
+    hooks: Vec<Arc<dyn AppendHook<A, DB>>>,
+    snapshot_policy: Arc<dyn SnapshotPolicy>,
+    /// Snapshots kept per stream; `None` keeps all of them.
+    snapshot_retention: Option<u32>,
+}
+
+impl<A: EventSchema, DB: Database> Clone for Repository<A, DB> {
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+            events: self.events.clone(),
+            hooks: self.hooks.clone(),
+            snapshot_policy: self.snapshot_policy.clone(),
+            snapshot_retention: self.snapshot_retention,
+        }
+    }
+}
+
+impl<A: EventSchema, DB: Database> Repository<A, DB> {
+    pub fn new(db: Pool<DB>) -> Self {
+        Self {
+            events: EventStore::new(db.clone()),
+            db,
+            hooks: vec![],
+            snapshot_policy: Arc::new(EveryNEvents(5)),
+            snapshot_retention: None,
+        }
+    }
+
+    pub fn with_snapshot_policy(mut self, policy: Arc<dyn SnapshotPolicy>) -> Self {
+        self.snapshot_policy = policy;
+        self
+    }
+
+    /// Keeps only the `keep` newest snapshots of a stream after writing one.
+    pub fn with_snapshot_retention(mut self, keep: u32) -> Self {
+        self.snapshot_retention = Some(keep.max(1));
+        self
+    }
+
+    pub fn with_hook(mut self, hook: Arc<dyn AppendHook<A, DB>>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    pub fn events(&self) -> &EventStore<A, DB> {
+        &self.events
+    }
+}
+
+impl<A, DB> Repository<A, DB>
+where
+    A: EventSchema,
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB> + ColumnIndex<DB::Row>,
+    for<'q> String: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> NaiveDateTime: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> Json<serde_json::Value>: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<Json<EventMetadata>>: Encode<'q, DB> + Type<DB>,
+    for<'r> StoredEventData: FromRow<'r, DB::Row>,
+    for<'r> SnapshotData<A>: FromRow<'r, DB::Row>,
+    usize: ColumnIndex<DB::Row>,
+{
+    /// Rebuilds the aggregate from its latest snapshot and the events after it.
+    /// Returns `None` when the stream has no events.
+    pub async fn load(&self, id: &A::Id) -> Result<Option<A>> {
+        tracing::info!("load {} : {}", A::TYPE_NAME, id);
+
+        self.events.load_aggregate(id).await
+    }
+
+    /// Appends `events` with every hook in one transaction, then snapshots the stream
+    /// when the snapshot policy asks for it.
+    pub async fn save(&self, expected: Option<Version>, events: &[A::Event]) -> Result<()> {
+        let Some(last_event) = events.last() else {
+            tracing::info!("No events to store");
+            return Ok(());
+        };
+
+        let mut transaction = self.db.begin().await?;
+        self.events
+            .append(&mut transaction, expected, events)
+            .await?;
+        for hook in &self.hooks {
+            hook.on_append(&mut transaction, events).await?;
+        }
+        transaction.commit().await?;
+        tracing::info!("Stored {} events: {:?}", A::TYPE_NAME, events);
+
+        // the events are committed; a failed snapshot only makes later loads slower
+        let appended = AppendedEvents {
+            head: last_event.version(),
+            count: events.len() as u32,
+            occurred_at: last_event.occurred_at(),
+        };
+        if let Err(e) = self.snapshot(last_event.aggregate_id(), &appended).await {
+            tracing::error!("Failed to save snapshot: {:?}", e);
+        }
+        Ok(())
+    }
+
+    async fn snapshot(&self, id: &A::Id, appended: &AppendedEvents) -> Result<()> {
+        let last = self.events.last_snapshot(id).await?;
+        if !self
+            .snapshot_policy
+            .should_snapshot(appended, last.as_ref())
+        {
+            return Ok(());
+        }
+        let Some(state) = self.load(id).await? else {
+            return Ok(());
+        };
+        self.events.save_snapshot(&state).await?;
+
+        if let Some(keep) = self.snapshot_retention {
+            let pruned = self.events.prune_snapshots(id, keep).await?;
+            if pruned > 0 {
+                tracing::info!("Pruned {} snapshots of {} {}", pruned, A::TYPE_NAME, id);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+//! Decides when [`Repository::save`](crate::repository::Repository::save) writes a snapshot.
+//!
+//! Policies only see what an append changed and where the previous snapshot is, so
+//! they are picked per aggregate when wiring the repository.
+
+use std::fmt;
+
+use chrono::{Duration, NaiveDateTime};
+use domain::aggregate::value_object::version::Version;
+
+/// The most recent snapshot of a stream in the current shape.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LastSnapshot {
+    pub version: Version,
+    pub created_at: NaiveDateTime,
+}
+
+/// What a single append did to a stream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AppendedEvents {
+    /// Version of the last appended event.
+    pub head: Version,
+    /// Number of events in the append.
+    pub count: u32,
+    /// When the last appended event occurred.
+    pub occurred_at: NaiveDateTime,
+}
+
+impl AppendedEvents {
+    /// Version the stream was at before the append, 0 for a new stream.
+    fn previous(&self) -> u32 {
+        u32::from(self.head).saturating_sub(self.count)
+    }
+}
+
+pub trait SnapshotPolicy: Send + Sync + fmt::Debug {
+    fn should_snapshot(&self, appended: &AppendedEvents, last: Option<&LastSnapshot>) -> bool;
+}
+
+/// Snapshots whenever an append reaches or passes a multiple of `n`, so appends of
+/// several events cannot step over one.
+#[derive(Clone, Copy, Debug)]
+pub struct EveryNEvents(pub u32);
+
+impl SnapshotPolicy for EveryNEvents {
+    fn should_snapshot(&self, appended: &AppendedEvents, _: Option<&LastSnapshot>) -> bool {
+        self.0 > 0 && appended.previous() / self.0 < u32::from(appended.head) / self.0
+    }
+}
+
+/// Snapshots once `n` events have been appended since the last snapshot, or since the
+/// start of a stream that has none.
+#[derive(Clone, Copy, Debug)]
+pub struct EventsSinceLastSnapshot(pub u32);
+
+impl SnapshotPolicy for EventsSinceLastSnapshot {
+    fn should_snapshot(&self, appended: &AppendedEvents, last: Option<&LastSnapshot>) -> bool {
+        let since = last.map_or(0, |last| u32::from(last.version));
+        u32::from(appended.head).saturating_sub(since) >= self.0.max(1)
+    }
+}
+
+/// Snapshots when the last snapshot is older than `interval` at the time of the append.
+/// A stream without a snapshot gets one on its first append.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeBased(pub Duration);
+
+impl SnapshotPolicy for TimeBased {
+    fn should_snapshot(&self, appended: &AppendedEvents, last: Option<&LastSnapshot>) -> bool {
+        last.is_none_or(|last| {
+            last.version < appended.head && appended.occurred_at - last.created_at >= self.0
+        })
+    }
+}
+
+/// Never snapshots; every load replays the whole stream.
+#[derive(Clone, Copy, Debug)]
+pub struct Never;
+
+impl SnapshotPolicy for Never {
+    fn should_snapshot(&self, _: &AppendedEvents, _: Option<&LastSnapshot>) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn appended(head: u32, count: u32) -> AppendedEvents {
+        AppendedEvents {
+            head: Version::from(head),
+            count,
+            occurred_at: NaiveDateTime::default() + Duration::minutes(head.into()),
+        }
+    }
+
+    fn snapshot(version: u32) -> LastSnapshot {
+        LastSnapshot {
+            version: Version::from(version),
+            created_at: NaiveDateTime::default() + Duration::minutes(version.into()),
+        }
+    }
+
+    #[test]
+    fn test_every_n_events_catches_skipped_multiples() {
+        let policy = EveryNEvents(5);
+        assert!(policy.should_snapshot(&appended(5, 1), None));
+        assert!(!policy.should_snapshot(&appended(6, 1), None));
+        // 4 -> 6 passes over 5
+        assert!(policy.should_snapshot(&appended(6, 2), None));
+        assert!(!policy.should_snapshot(&appended(9, 3), None));
+        assert!(!EveryNEvents(0).should_snapshot(&appended(5, 1), None));
+    }
+
+    #[test]
+    fn test_events_since_last_snapshot() {
+        let policy = EventsSinceLastSnapshot(3);
+        assert!(!policy.should_snapshot(&appended(2, 2), None));
+        assert!(policy.should_snapshot(&appended(3, 1), None));
+        assert!(!policy.should_snapshot(&appended(6, 1), Some(&snapshot(4))));
+        assert!(policy.should_snapshot(&appended(7, 1), Some(&snapshot(4))));
+    }
+
+    #[test]
+    fn test_time_based() {
+        let policy = TimeBased(Duration::minutes(10));
+        assert!(policy.should_snapshot(&appended(1, 1), None));
+        assert!(!policy.should_snapshot(&appended(9, 1), Some(&snapshot(1))));
+        assert!(policy.should_snapshot(&appended(11, 1), Some(&snapshot(1))));
+    }
+
+    #[test]
+    fn test_never() {
+        assert!(!Never.should_snapshot(&appended(5, 5), None));
+    }
+}
@@ -0,0 +1,279 @@
+//! The event store, snapshot store and command-side tables in a single SQLite file,
+//! behind the `sqlite` feature.
+//!
+//! The stores of this crate run on SQLite as [`Backend`]s, e.g.
+//! `CircleRepository<Sqlite>`, over the tables of `sql/migrations/sqlite`. SQLite lets
+//! one writer in at a time, so appends serialize on the database instead of on row
+//! locks.
+
+use sqlx::{sqlite::SqliteQueryResult, Sqlite};
+
+use crate::{
+    backend::Backend,
+    migration::{Migration, MigrationLock},
+};
+
+impl Backend for Sqlite {
+    const NOW: &'static str = "CURRENT_TIMESTAMP";
+
+    const MIGRATIONS: &'static [Migration] = MIGRATIONS;
+
+    const MIGRATIONS_TABLE: &'static str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER NOT NULL PRIMARY KEY,
+    description TEXT NOT NULL,
+    applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+)";
+
+    // the write lock is taken up front, so a second process waits for this one and
+    // then finds nothing left to apply
+    const MIGRATION_LOCK: MigrationLock = MigrationLock {
+        begin: "BEGIN IMMEDIATE",
+        acquire: None,
+        release: None,
+    };
+
+    const SCHEMA_COLUMNS: &'static str = "SELECT m.name AS table_name, p.name AS column_name \
+         FROM sqlite_master AS m JOIN pragma_table_info(m.name) AS p \
+         WHERE m.type = 'table'";
+
+    fn rows_affected(result: &SqliteQueryResult) -> u64 {
+        result.rows_affected()
+    }
+}
+
+/// The SQLite migrations in `sql/migrations/sqlite`.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create_event_store",
+        sql: include_str!("../../../../sql/migrations/sqlite/0001_create_event_store.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "events_indexes",
+        sql: include_str!("../../../../sql/migrations/sqlite/0002_events_indexes.sql"),
+    },
+    Migration {
+        version: 3,
+        description: "normalize_circle_names",
+        sql: include_str!("../../../../sql/migrations/sqlite/0003_normalize_circle_names.sql"),
+    },
+];
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use domain::{
+        interface::command::idempotency_store_interface::{
+            IdempotencyClaim, IdempotencyStoreInterface, StoredResponse,
+        },
+        test_utils::circle_conformance,
+    };
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+    use super::MIGRATIONS;
+    use crate::{
+        circle_repository::CircleRepository,
+        idempotency_store::IdempotencyStore,
+        migration::{migrate, pending_migrations},
+        snapshot_policy::{EventsSinceLastSnapshot, EveryNEvents, Never, SnapshotPolicy},
+    };
+
+    /// A fresh, empty database; every connection to `sqlite::memory:` opens its own, so
+    /// the pool keeps a single one.
+    async fn empty_pool() -> anyhow::Result<sqlx::SqlitePool> {
+        Ok(SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(SqliteConnectOptions::from_str("sqlite::memory:")?)
+            .await?)
+    }
+
+    /// A fresh database with every migration applied.
+    pub(crate) async fn memory_pool() -> anyhow::Result<sqlx::SqlitePool> {
+        let db = empty_pool().await?;
+        migrate(&db).await?;
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_migrate_applies_each_migration_once() -> anyhow::Result<()> {
+        let db = memory_pool().await?;
+        assert!(migrate(&db).await?.is_empty());
+        assert!(pending_migrations(&db).await?.is_empty());
+
+        let applied: Vec<i64> =
+            sqlx::query_scalar("SELECT version FROM schema_migrations ORDER BY version")
+                .fetch_all(&db)
+                .await?;
+        let versions: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert_eq!(applied, versions);
+        let last_sequence: i64 =
+            sqlx::query_scalar("SELECT last_sequence FROM event_sequence WHERE id = 1")
+                .fetch_one(&db)
+                .await?;
+        assert_eq!(last_sequence, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_refuses_a_newer_schema() -> anyhow::Result<()> {
+        let db = memory_pool().await?;
+        sqlx::query("INSERT INTO schema_migrations (version, description) VALUES (999, 'newer')")
+            .execute(&db)
+            .await?;
+
+        assert!(pending_migrations(&db).await.is_err());
+        assert!(migrate(&db).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_refuses_a_legacy_schema() -> anyhow::Result<()> {
+        let legacy_tables = [
+            "CREATE TABLE circle_events (circle_id TEXT NOT NULL, version INTEGER NOT NULL)",
+            "CREATE TABLE events (id TEXT NOT NULL PRIMARY KEY, version INTEGER NOT NULL)",
+        ];
+        for legacy_table in legacy_tables {
+            let db = empty_pool().await?;
+            sqlx::query(legacy_table).execute(&db).await?;
+
+            assert!(pending_migrations(&db).await.is_err());
+            assert!(migrate(&db).await.is_err());
+            let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_migrations")
+                .fetch_one(&db)
+                .await?;
+            assert_eq!(applied, 0);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_versions_before_the_first_recorded_one_are_applied() -> anyhow::Result<()> {
+        let db = memory_pool().await?;
+        sqlx::query("DELETE FROM schema_migrations WHERE version = 1")
+            .execute(&db)
+            .await?;
+
+        assert!(pending_migrations(&db).await?.is_empty());
+        assert!(migrate(&db).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reserved_names_are_normalized_once() -> anyhow::Result<()> {
+        let db = memory_pool().await?;
+        sqlx::query("DELETE FROM schema_migrations WHERE version = 3")
+            .execute(&db)
+            .await?;
+        sqlx::query(
+            "INSERT INTO circle_names (name, circle_id) \
+             VALUES ('Jazz', 'b'), (' jazz', 'a'), ('Chess ', 'c')",
+        )
+        .execute(&db)
+        .await?;
+
+        migrate(&db).await?;
+        let names: Vec<(String, String)> =
+            sqlx::query_as("SELECT name, circle_id FROM circle_names ORDER BY name")
+                .fetch_all(&db)
+                .await?;
+        assert_eq!(
+            names,
+            vec![
+                ("chess".to_string(), "c".to_string()),
+                ("jazz".to_string(), "a".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_repository_conformance() -> anyhow::Result<()> {
+        // snapshots on every append, never, and every few events
+        let policies: [Arc<dyn SnapshotPolicy>; 3] = [
+            Arc::new(EveryNEvents(1)),
+            Arc::new(Never),
+            Arc::new(EventsSinceLastSnapshot(5)),
+        ];
+        for policy in policies {
+            let repository = CircleRepository::new(memory_pool().await?)
+                .with_snapshot_policy(policy)
+                .with_snapshot_retention(2);
+            circle_conformance::run_repository_suite(&repository).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_claim_after_completion_replays_the_response() -> anyhow::Result<()> {
+        let store = IdempotencyStore::new(memory_pool().await?);
+        assert_eq!(
+            store.claim("circles", "key", "hash").await?,
+            IdempotencyClaim::Acquired
+        );
+        assert_eq!(
+            store.claim("circles", "key", "hash").await?,
+            IdempotencyClaim::InProgress
+        );
+        assert_eq!(
+            store.claim("circles", "key", "other").await?,
+            IdempotencyClaim::Mismatch
+        );
+
+        let response = StoredResponse {
+            status: 201,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: b"{}".to_vec(),
+        };
+        store.complete("circles", "key", &response).await?;
+        assert_eq!(
+            store.claim("circles", "key", "hash").await?,
+            IdempotencyClaim::Completed(response)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_within_the_lease_does_not_run_again() -> anyhow::Result<()> {
+        let db = memory_pool().await?;
+        let store = IdempotencyStore::new(db.clone());
+        assert_eq!(
+            store.claim("circles", "key", "hash").await?,
+            IdempotencyClaim::Acquired
+        );
+        // a slow request, still short of the 60 second lease
+        sqlx::query("UPDATE idempotency_keys SET created_at = datetime('now', '-50 seconds')")
+            .execute(&db)
+            .await?;
+
+        assert_eq!(
+            store.claim("circles", "key", "hash").await?,
+            IdempotencyClaim::InProgress
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_claim_takes_over_an_abandoned_claim() -> anyhow::Result<()> {
+        let db = memory_pool().await?;
+        let store = IdempotencyStore::new(db.clone());
+        assert_eq!(
+            store.claim("circles", "key", "hash").await?,
+            IdempotencyClaim::Acquired
+        );
+        sqlx::query("UPDATE idempotency_keys SET created_at = datetime('now', '-61 seconds')")
+            .execute(&db)
+            .await?;
+
+        assert_eq!(
+            store.claim("circles", "key", "hash").await?,
+            IdempotencyClaim::Acquired
+        );
+        assert_eq!(
+            store.claim("circles", "key", "hash").await?,
+            IdempotencyClaim::InProgress
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,375 @@
+//! Catch-up subscriptions over the global event log.
+//!
+//! Every appended event gets the next position (`events.sequence`) of a single log.
+//! Positions are handed out by the one `event_sequence` row, so every append, of any
+//! aggregate, serializes on that row until it commits. Write throughput is bounded by
+//! it in exchange for a total order that a checkpoint can point into.
+//!
+//! A subscription reads the events of one aggregate type after its checkpoint, hands
+//! them to an [`EventHandler`] in log order and keeps polling for new ones. It catches
+//! up after a restart and then tails live events.
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use anyhow::{Error, Result};
+use sqlx::{ColumnIndex, Database, Decode, Encode, Executor, IntoArguments, MySql, Pool, Type};
+
+use crate::{
+    backend::Backend,
+    event_store::{EventLog, EventStore},
+    upcaster::EventSchema,
+};
+
+pub(crate) const DEFAULT_BATCH_SIZE: u32 = 100;
+pub(crate) const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[async_trait::async_trait]
+pub trait EventHandler<A: EventSchema>: Send + Sync + fmt::Debug {
+    async fn handle(&self, event: &A::Event) -> Result<()>;
+}
+
+/// A named consumer of the log with a checkpoint in `subscription_checkpoints`.
+///
+/// Delivery is at-least-once: the checkpoint is moved only after the handler
+/// succeeded, so handlers must be idempotent. Nothing is locked while the handler
+/// runs; the checkpoint only ever moves forward, so instances sharing a name may
+/// handle a batch twice but never skip one.
+#[derive(Debug)]
+pub struct Subscription<A: EventSchema, DB: Database = MySql> {
+    db: Pool<DB>,
+    name: String,
+    events: EventStore<A, DB>,
+    handler: Arc<dyn EventHandler<A>>,
+    batch_size: u32,
+    poll_interval: Duration,
+}
+
+impl<A: EventSchema, DB: Database> Subscription<A, DB> {
+    pub fn new(db: Pool<DB>, name: &str, handler: Arc<dyn EventHandler<A>>) -> Self {
+        Self {
+            events: EventStore::new(db.clone()),
+            db,
+            name: name.to_string(),
+            handler,
+            batch_size: DEFAULT_BATCH_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Most events handled per [`catch_up`](Self::catch_up).
+    pub fn batch_size(&self) -> u32 {
+        self.batch_size
+    }
+}
+
+impl<A, DB> Subscription<A, DB>
+where
+    A: EventSchema,
+    DB: Backend,
+    EventStore<A, DB>: EventLog<A>,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<DB::Row>,
+{
+    /// Position of the last event handled, 0 before the first one.
+    pub async fn checkpoint(&self) -> Result<u64> {
+        let position: Option<i64> = sqlx::query_scalar(&DB::sql(
+            "SELECT position FROM subscription_checkpoints WHERE name = ?",
+        ))
+        .bind(self.name.as_str())
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::msg(format!("Failed to fetch checkpoint: {}", e)))?;
+        Ok(position.unwrap_or(0) as u64)
+    }
+
+    pub async fn start_processing(&self) {
+        loop {
+            match self.catch_up().await {
+                // keep reading while there is a backlog
+                Ok(handled) if handled == self.batch_size as usize => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!("Subscription {} failed: {:?}", self.name, e),
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Handles one batch of events after the checkpoint and returns how many were
+    /// handled. Stops at the first failure so that the event is retried next time.
+    pub async fn catch_up(&self) -> Result<usize> {
+        let position = self.checkpoint().await?;
+        let events = self.events.read_all(position, self.batch_size).await?;
+
+        let mut handled = 0;
+        let mut checkpoint = position;
+        for (sequence, event) in &events {
+            if let Err(e) = self.handler.handle(event).await {
+                tracing::error!(
+                    "Subscription {} failed at position {}: {:?}",
+                    self.name,
+                    sequence,
+                    e
+                );
+                break;
+            }
+            checkpoint = *sequence;
+            handled += 1;
+        }
+
+        if handled > 0 {
+            self.save_checkpoint(checkpoint).await?;
+        }
+        Ok(handled)
+    }
+
+    /// Moves the checkpoint to `position`, unless an instance with the same name has
+    /// already moved it further.
+    async fn save_checkpoint(&self, position: u64) -> Result<()> {
+        let position = position as i64;
+        // the first save of a subscription creates the row; when another instance
+        // creates it first, the update is tried once more against that row
+        for _ in 0..2 {
+            let moved = sqlx::query(&DB::sql(&format!(
+                "UPDATE subscription_checkpoints SET position = ?, updated_at = {} \
+                 WHERE name = ? AND position < ?",
+                DB::NOW
+            )))
+            .bind(position)
+            .bind(self.name.as_str())
+            .bind(position)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::msg(format!("Failed to save checkpoint: {}", e)))?;
+            if DB::rows_affected(&moved) > 0 {
+                return Ok(());
+            }
+
+            let created = sqlx::query(&DB::sql(
+                "INSERT INTO subscription_checkpoints (name, position) VALUES (?, ?)",
+            ))
+            .bind(self.name.as_str())
+            .bind(position)
+            .execute(&self.db)
+            .await;
+            match created {
+                Ok(_) => return Ok(()),
+                Err(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {}
+                Err(e) => return Err(Error::msg(format!("Failed to save checkpoint: {}", e))),
+            }
+        }
+        // the row exists and is at or past `position`
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::{str::FromStr, sync::Mutex};
+
+    use domain::{
+        aggregate::{
+            circle::{event::CircleEvent, member::Member, Circle},
+            value_object::{circle_id::CircleId, member_id::MemberId},
+        },
+        interface::command::circle_repository_interface::CircleRepositoryInterface,
+    };
+
+    use super::*;
+    use crate::{circle_repository::CircleRepository, sqlite::tests::memory_pool};
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        handled: Mutex<Vec<CircleEvent>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EventHandler<Circle> for Recorder {
+        async fn handle(&self, event: &CircleEvent) -> Result<()> {
+            self.handled
+                .lock()
+                .map_err(|_| Error::msg("poisoned"))?
+                .push(event.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_resumes_after_the_checkpoint() -> Result<()> {
+        let db = memory_pool().await?;
+        let repository = CircleRepository::new(db.clone());
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (music, music_created) = Circle::create("Music club".to_string(), 10, owner.clone())?;
+        repository.store(None, vec![music_created]).await?;
+
+        let recorder = Arc::new(Recorder::default());
+        let subscription = Subscription::new(db, "recorder", recorder.clone());
+        assert_eq!(subscription.catch_up().await?, 1);
+        assert_eq!(subscription.checkpoint().await?, 1);
+
+        let (_, book_created) = Circle::create("Book club".to_string(), 10, owner)?;
+        repository.store(None, vec![book_created]).await?;
+        assert_eq!(subscription.catch_up().await?, 1);
+        assert_eq!(subscription.catch_up().await?, 0);
+
+        let handled = recorder
+            .handled
+            .lock()
+            .map_err(|_| Error::msg("poisoned"))?
+            .iter()
+            .map(|event| event.circle_id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(handled.len(), 2);
+        assert_eq!(handled[0], music.id);
+        Ok(())
+    }
+
+    /// Reads the log while it handles an event, which needs the only connection of the
+    /// pool; a subscription holding a transaction over the batch would never finish.
+    #[derive(Debug)]
+    struct ReadsTheLog {
+        events: EventStore<Circle, sqlx::Sqlite>,
+    }
+
+    #[async_trait::async_trait]
+    impl EventHandler<Circle> for ReadsTheLog {
+        async fn handle(&self, event: &CircleEvent) -> Result<()> {
+            self.events.load(&event.circle_id, None, None).await?;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_holds_no_connection_while_handling() -> Result<()> {
+        let db = memory_pool().await?;
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (_, created) = Circle::create("Music club".to_string(), 10, owner)?;
+        CircleRepository::new(db.clone())
+            .store(None, vec![created])
+            .await?;
+
+        let handler = Arc::new(ReadsTheLog {
+            events: EventStore::new(db.clone()),
+        });
+        let subscription = Subscription::new(db, "reader", handler);
+        let handled =
+            tokio::time::timeout(Duration::from_secs(5), subscription.catch_up()).await??;
+        assert_eq!(handled, 1);
+        assert_eq!(subscription.checkpoint().await?, 1);
+        Ok(())
+    }
+
+    /// Fails on the events of one circle until told otherwise.
+    #[derive(Debug, Default)]
+    struct FailsOn {
+        circle_id: Mutex<Option<CircleId>>,
+        handled: Mutex<Vec<CircleId>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EventHandler<Circle> for FailsOn {
+        async fn handle(&self, event: &CircleEvent) -> Result<()> {
+            let failing = self.circle_id.lock().map_err(|_| Error::msg("poisoned"))?;
+            if failing.as_ref() == Some(&event.circle_id) {
+                return Err(Error::msg("handler failed"));
+            }
+            self.handled
+                .lock()
+                .map_err(|_| Error::msg("poisoned"))?
+                .push(event.circle_id.clone());
+            Ok(())
+        }
+    }
+
+    async fn create_circles(db: &sqlx::SqlitePool, names: &[&str]) -> Result<Vec<CircleId>> {
+        let repository = CircleRepository::new(db.clone());
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let mut ids = Vec::new();
+        for name in names {
+            let (circle, created) = Circle::create(name.to_string(), 10, owner.clone())?;
+            repository.store(None, vec![created]).await?;
+            ids.push(circle.id);
+        }
+        Ok(ids)
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_stops_at_the_first_failure() -> Result<()> {
+        let db = memory_pool().await?;
+        let ids = create_circles(&db, &["Music club", "Book club", "Chess club"]).await?;
+
+        let handler = Arc::new(FailsOn {
+            circle_id: Mutex::new(Some(ids[1].clone())),
+            ..FailsOn::default()
+        });
+        let subscription = Subscription::new(db, "failing", handler.clone());
+        assert_eq!(subscription.catch_up().await?, 1);
+        assert_eq!(subscription.checkpoint().await?, 1);
+        // the failed event is retried, and the ones after it wait for it
+        assert_eq!(subscription.catch_up().await?, 0);
+        assert_eq!(subscription.checkpoint().await?, 1);
+
+        *handler
+            .circle_id
+            .lock()
+            .map_err(|_| Error::msg("poisoned"))? = None;
+        assert_eq!(subscription.catch_up().await?, 2);
+        assert_eq!(subscription.checkpoint().await?, 3);
+        assert_eq!(
+            *handler.handled.lock().map_err(|_| Error::msg("poisoned"))?,
+            ids
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_instances_with_the_same_name_share_the_checkpoint() -> Result<()> {
+        let db = memory_pool().await?;
+        create_circles(&db, &["Music club", "Book club"]).await?;
+
+        let first = Arc::new(Recorder::default());
+        let second = Arc::new(Recorder::default());
+        let first_instance = Subscription::new(db.clone(), "shared", first.clone());
+        let second_instance = Subscription::new(db.clone(), "shared", second.clone());
+        assert_eq!(first_instance.catch_up().await?, 2);
+        assert_eq!(second_instance.catch_up().await?, 0);
+
+        create_circles(&db, &["Chess club"]).await?;
+        assert_eq!(second_instance.catch_up().await?, 1);
+        assert_eq!(first_instance.catch_up().await?, 0);
+        assert_eq!(first_instance.checkpoint().await?, 3);
+        assert_eq!(second_instance.checkpoint().await?, 3);
+        let handled = |recorder: &Recorder| -> Result<usize> {
+            Ok(recorder
+                .handled
+                .lock()
+                .map_err(|_| Error::msg("poisoned"))?
+                .len())
+        };
+        assert_eq!(handled(&first)?, 2);
+        assert_eq!(handled(&second)?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_only_moves_forward() -> Result<()> {
+        let db = memory_pool().await?;
+        let subscription =
+            Subscription::<Circle, _>::new(db, "recorder", Arc::new(Recorder::default()));
+
+        subscription.save_checkpoint(5).await?;
+        assert_eq!(subscription.checkpoint().await?, 5);
+        // an instance that read the log before the save above finishes later
+        subscription.save_checkpoint(3).await?;
+        assert_eq!(subscription.checkpoint().await?, 5);
+        subscription.save_checkpoint(8).await?;
+        assert_eq!(subscription.checkpoint().await?, 8);
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+//! Upgrades stored event payloads to the shape the current code deserialises.
+//!
+//! Every row of `events` records the `schema_version` its payload was written with.
+//! When an event type changes shape, bump the schema by appending an [`Upcaster`]
+//! that rewrites payloads of the previous version, and add a fixture of the old
+//! shape under `fixtures/` so that replaying it stays covered.
+
+use anyhow::{Error, Result};
+use domain::aggregate::{circle::Circle, event_sourced::Aggregate};
+use serde_json::Value;
+
+/// Rewrites a payload of one schema version into the next one.
+pub type Upcaster = fn(event_type: &str, payload: Value) -> Result<Value>;
+
+/// Storage history of an aggregate: the shapes of its event payloads and snapshots.
+pub trait EventSchema: Aggregate {
+    /// `UPCASTERS[i]` turns a payload of schema version `i + 1` into version `i + 2`.
+    const UPCASTERS: &'static [Upcaster];
+
+    /// Shape of the serialised aggregate in `snapshots`. Bump it whenever a field is
+    /// added to or changes meaning in the aggregate; older snapshots are then ignored.
+    const SNAPSHOT_VERSION: i32;
+
+    /// Schema version new events are written with.
+    fn current_schema_version() -> i32 {
+        Self::UPCASTERS.len() as i32 + 1
+    }
+}
+
+/// Runs `payload` through every upcaster from `schema_version` to the current one.
+pub fn upcast<A: EventSchema>(
+    event_type: &str,
+    schema_version: i32,
+    payload: Value,
+) -> Result<Value> {
+    let current = A::current_schema_version();
+    if !(1..=current).contains(&schema_version) {
+        // written by a newer release; guessing its meaning could corrupt the state
+        return Err(Error::msg(format!(
+            "Unsupported {} event schema version {} (current is {})",
+            A::TYPE_NAME,
+            schema_version,
+            current
+        )));
+    }
+    A::UPCASTERS[(schema_version - 1) as usize..]
+        .iter()
+        .try_fold(payload, |payload, upcaster| upcaster(event_type, payload))
+}
+
+fn fields(payload: &mut Value) -> Result<&mut serde_json::Map<String, Value>> {
+    payload
+        .as_object_mut()
+        .ok_or_else(|| Error::msg("Event payload is not a JSON object"))
+}
+
+impl EventSchema for Circle {
+    const UPCASTERS: &'static [Upcaster] = &[circle_v1_to_v2];
+
+    // 2: members, owner and status; snapshots of 1 may lack them
+    const SNAPSHOT_VERSION: i32 = 2;
+}
+
+/// v2 added the owner to `circle_created`; circles created before have none.
+fn circle_v1_to_v2(event_type: &str, mut payload: Value) -> Result<Value> {
+    if event_type == "circle_created" {
+        fields(&mut payload)?.entry("owner").or_insert(Value::Null);
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::NaiveDateTime;
+    use domain::aggregate::{
+        circle::{
+            event::{CircleEvent, EventData},
+            status::CircleStatus,
+        },
+        event_sourced::DomainEvent,
+        value_object::{
+            circle_id::CircleId, event_id::EventId, member_id::MemberId, version::Version,
+        },
+    };
+
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    struct Fixture {
+        event_type: String,
+        schema_version: i32,
+        payload: Value,
+    }
+
+    fn replay_fixture(json: &str) -> Result<Circle> {
+        let fixtures: Vec<Fixture> = serde_json::from_str(json)?;
+        let events = fixtures
+            .into_iter()
+            .zip(1..)
+            .map(|(fixture, version)| {
+                let payload =
+                    upcast::<Circle>(&fixture.event_type, fixture.schema_version, fixture.payload)?;
+                let data: EventData = serde_json::from_value(payload)?;
+                assert_eq!(data.event_type(), fixture.event_type);
+                Ok(CircleEvent::from_stored(
+                    EventId::from(version as i16),
+                    CircleId::from_str("circle")?,
+                    Version::from(version),
+                    NaiveDateTime::default(),
+                    data,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Circle::replay(events).ok_or_else(|| Error::msg("fixture does not start a stream"))
+    }
+
+    #[test]
+    fn test_replay_schema_v1() -> Result<()> {
+        let circle = replay_fixture(include_str!("../fixtures/circle_events/schema_v1.json"))?;
+        assert_eq!(circle.name, "Jazz club");
+        assert_eq!(circle.capacity, 10);
+        assert_eq!(circle.owner_id, None);
+        assert_eq!(circle.members.len(), 1);
+        assert!(circle.is_member(&MemberId::from_str("member-2")?));
+        assert_eq!(circle.version, Version::from(5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_schema_v2() -> Result<()> {
+        let circle = replay_fixture(include_str!("../fixtures/circle_events/schema_v2.json"))?;
+        assert_eq!(circle.name, "Music club");
+        assert_eq!(circle.capacity, 5);
+        assert!(circle.is_owner(&MemberId::from_str("member-1")?));
+        assert!(!circle.is_member(&MemberId::from_str("owner")?));
+        assert_eq!(circle.status, CircleStatus::Disbanded);
+        assert_eq!(circle.version, Version::from(6));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixture_of_every_schema_version() {
+        // add a fixture when a new schema version is introduced
+        let fixtures = [
+            include_str!("../fixtures/circle_events/schema_v1.json"),
+            include_str!("../fixtures/circle_events/schema_v2.json"),
+        ];
+        assert_eq!(fixtures.len() as i32, Circle::current_schema_version());
+    }
+
+    #[test]
+    fn test_rejects_unknown_schema_version() {
+        let payload = serde_json::json!({ "type": "circle_disbanded" });
+        assert!(upcast::<Circle>("circle_disbanded", 0, payload.clone()).is_err());
+        assert!(upcast::<Circle>("circle_disbanded", 3, payload).is_err());
+    }
+}
@@ -1,39 +1,100 @@
 use std::sync::Arc;
 
 use api::{app_state::AppState, router::router};
-use infrastructure::event_publisher::{EventPublisher, InMemoryEventPublisher, RedisProjectionHandler};
+use domain::aggregate::circle::Circle;
+use domain::interface::query::projection_rebuilder_interface::ProjectionRebuilderInterface;
+#[cfg(feature = "postgres")]
+use infrastructure::postgres::subscription::PostgresSubscription;
+use infrastructure::{
+    event_publisher::RedisProjectionHandler, event_store::EventStore,
+    projection_rebuilder::ProjectionRebuilder, subscription::Subscription,
+};
 
 use crate::{
-    config::{connect::connect as mysql_connect, redis_connect::connect as redis_connect},
+    config::{
+        admin_token::admin_token,
+        database::{connect as database_connect, migrate_on_start, Database},
+        redis_connect::connect as redis_connect,
+        trusted_proxies::trusted_proxies,
+    },
     injectors::{
         build_command_handler::build_command_handler, build_query_handler::build_query_handler,
     },
 };
 
-async fn setup_event_system(
-    redis_client: redis::Client,
-    db: sqlx::MySqlPool,
-) -> Arc<dyn EventPublisher> {
-    let (event_publisher, event_receiver) = InMemoryEventPublisher::new();
-    let redis_handler = RedisProjectionHandler::new(redis_client, db);
-    
-    tokio::spawn(async move {
-        redis_handler.start_processing(event_receiver).await;
-    });
-    
-    Arc::new(event_publisher)
+fn setup_event_system(redis_client: redis::Client, database: &Database) {
+    let redis_handler = Arc::new(RedisProjectionHandler::new(
+        redis_client,
+        database.circle_events(),
+    ));
+    match database.clone() {
+        Database::MySql(db) => {
+            let projection = Subscription::<Circle>::new(db, "redis_projection", redis_handler);
+            tokio::spawn(async move {
+                projection.start_processing().await;
+            });
+        }
+        #[cfg(feature = "sqlite")]
+        Database::Sqlite(db) => {
+            let projection = Subscription::<Circle, _>::new(db, "redis_projection", redis_handler);
+            tokio::spawn(async move {
+                projection.start_processing().await;
+            });
+        }
+        #[cfg(feature = "postgres")]
+        Database::Postgres(db) => {
+            let projection =
+                PostgresSubscription::<Circle>::new(db, "redis_projection", redis_handler);
+            tokio::spawn(async move {
+                projection.start_processing().await;
+            });
+        }
+    }
+}
+
+/// Applies the pending migrations with `MIGRATE_ON_START=true`, and otherwise checks
+/// that there are none, so the server never runs against a schema it was not built for.
+async fn prepare_schema(database: &Database) -> Result<(), anyhow::Error> {
+    if migrate_on_start() {
+        for migration in database.migrate().await? {
+            println!(
+                "Applied migration {:04} {}",
+                migration.version, migration.description
+            );
+        }
+        return Ok(());
+    }
+    let pending = database.pending_migrations().await?;
+    if !pending.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "{} migrations are pending; run `main migrate` or set MIGRATE_ON_START=true",
+            pending.len()
+        )));
+    }
+    Ok(())
 }
 
 pub async fn run() -> Result<(), ()> {
     tracing_subscriber::fmt().init();
 
-    let mysql_pool = mysql_connect().await.expect("MySQL should connect");
+    let database = database_connect().await.expect("database should connect");
+    prepare_schema(&database)
+        .await
+        .expect("database schema should be up to date");
     let redis_client = redis_connect().expect("Redis should connect");
 
-    let event_publisher = setup_event_system(redis_client.clone(), mysql_pool.clone()).await;
-    let command_handler = build_command_handler(mysql_pool, event_publisher);
-    let query_handler = build_query_handler(redis_client);
-    let state = AppState::new(Arc::new(command_handler), Arc::new(query_handler));
+    setup_event_system(redis_client.clone(), &database);
+    let command_handler = build_command_handler(&database);
+    let projection_rebuilder =
+        ProjectionRebuilder::new(redis_client.clone(), database.circle_events());
+    let query_handler = build_query_handler(redis_client, &database);
+    let state = AppState::new(
+        Arc::new(command_handler),
+        Arc::new(query_handler),
+        Arc::new(projection_rebuilder),
+    )
+    .with_trusted_proxies(trusted_proxies())
+    .with_admin_token(admin_token());
 
     let app = router().with_state(state);
 
@@ -44,45 +105,113 @@ pub async fn run() -> Result<(), ()> {
         "Listening on: {}",
         listener.local_addr().expect("server should bind to port")
     );
-    axum::serve(listener, app).await.expect("server should run");
+    // the peer address is the client IP of events, unless it is a trusted proxy
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await
+    .expect("server should run");
+    Ok(())
+}
+
+/// Regenerates the Redis read model from the event store and swaps it in.
+pub async fn rebuild_projection() -> Result<(), anyhow::Error> {
+    tracing_subscriber::fmt().init();
+
+    let database = database_connect().await?;
+    let redis_client = redis_connect()?;
+    let rebuilder = ProjectionRebuilder::new(redis_client, database.circle_events());
+
+    let reporter = {
+        let rebuilder = rebuilder.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                let progress = rebuilder.progress().await;
+                println!(
+                    "Rebuilding projection: {}/{} circles",
+                    progress.processed, progress.total
+                );
+            }
+        })
+    };
+    let result = rebuilder.rebuild().await;
+    reporter.abort();
+
+    let progress = result?;
+    println!("Projection rebuilt: {} circles", progress.processed);
+    Ok(())
+}
+
+/// Applies the embedded migrations the database lacks.
+pub async fn migrate() -> Result<(), anyhow::Error> {
+    tracing_subscriber::fmt().init();
+
+    let database = database_connect().await?;
+    let applied = database.migrate().await?;
+    for migration in &applied {
+        println!(
+            "Applied migration {:04} {}",
+            migration.version, migration.description
+        );
+    }
+    println!("Schema up to date: {} migrations applied", applied.len());
+    Ok(())
+}
+
+/// Replaces snapshots written with an older state shape, after a change to `Circle`.
+pub async fn regenerate_snapshots() -> Result<(), anyhow::Error> {
+    tracing_subscriber::fmt().init();
+
+    let regenerated = match database_connect().await? {
+        Database::MySql(db) => EventStore::<Circle>::new(db).regenerate_snapshots().await?,
+        #[cfg(feature = "sqlite")]
+        Database::Sqlite(db) => {
+            EventStore::<Circle, _>::new(db)
+                .regenerate_snapshots()
+                .await?
+        }
+        #[cfg(feature = "postgres")]
+        Database::Postgres(db) => {
+            EventStore::<Circle, _>::new(db)
+                .regenerate_snapshots()
+                .await?
+        }
+    };
+    println!("Snapshots regenerated: {} circles", regenerated);
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
-
-    use crate::config::{connect::connect_test, redis_connect::connect_test as redis_connect_test};
     use api::{
-        app_state::AppState,
-        handler::{CreateCircleRequestBody, CreateCircleResponseBody, UpdateCircleRequestBody},
-        router::router,
+        error::ProblemDetails,
+        handler::{
+            CreateCircleRequestBody, CreateCircleResponseBody, FetcheCircleResponseBody,
+            ListCirclesResponseBody, UpdateCircleRequestBody,
+        },
     };
     use axum::{
         http::{header::CONTENT_TYPE, StatusCode},
         Router,
     };
-    use domain::aggregate::value_object::circle_id::CircleId;
+    use domain::{
+        interface::{
+            command::circle_repository_interface::CircleRepositoryInterface,
+            query::circle_reader_interface::CircleReaderInterface,
+        },
+        test_utils::circle_conformance::{self, CircleBackend},
+    };
+    use infrastructure::{circle_reader::CircleReader, circle_repository::CircleRepository};
     use tower::ServiceExt;
 
     use super::*;
+    use crate::injectors::build_in_memory_app::build_in_memory_app;
 
-    async fn setup_test_dependencies() -> (Arc<dyn EventPublisher>, sqlx::MySqlPool, redis::Client) {
-        let mysql_pool = connect_test().await.expect("database should connect");
-        let redis_client = redis_connect_test().expect("Redis should connect");
-        let event_publisher = setup_event_system(redis_client.clone(), mysql_pool.clone()).await;
-        (event_publisher, mysql_pool, redis_client)
-    }
-
-    // FIXME: ignore test because it requires a running database
     #[tokio::test]
-    #[ignore]
     async fn test_version() -> anyhow::Result<()> {
-        let (event_publisher, mysql_pool, redis_client) = setup_test_dependencies().await;
-        let command_handler = build_command_handler(mysql_pool, event_publisher);
-        let query_handler = build_query_handler(redis_client);
-        let state = AppState::new(Arc::new(command_handler), Arc::new(query_handler));
-        let app = router().with_state(state);
+        let app = build_in_memory_app();
         let response = app
             .oneshot(
                 axum::http::Request::builder()
@@ -97,7 +226,7 @@ mod tests {
                 .await?
                 .to_vec(),
         )?;
-        assert_eq!(response_body, "0.1.0-rc.1");
+        assert_eq!(response_body, "0.1.0");
         Ok(())
     }
 
@@ -157,13 +286,8 @@ mod tests {
     // }
 
     #[tokio::test]
-    #[ignore]
     async fn test_fetch_circle() -> anyhow::Result<()> {
-        let (event_publisher, mysql_pool, redis_client) = setup_test_dependencies().await;
-        let command_handler = build_command_handler(mysql_pool, event_publisher);
-        let query_handler = build_query_handler(redis_client);
-        let state = AppState::new(Arc::new(command_handler), Arc::new(query_handler));
-        let app = router().with_state(state);
+        let app = build_in_memory_app();
         let unexist_circle_id = 0;
         let response = app
             .clone()
@@ -174,71 +298,176 @@ mod tests {
                     .body(axum::body::Body::empty())?,
             )
             .await?;
-        assert_eq!(response.status(), StatusCode::OK);
-        let response_body = String::from_utf8(
-            axum::body::to_bytes(response.into_body(), usize::MAX)
-                .await?
-                .to_vec(),
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let problem: ProblemDetails = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX).await?,
         )?;
-        assert_eq!(response_body, "Circle not found");
+        assert_eq!(problem.code, "circle_not_found");
 
         let circle_id = build_circle(&app).await?;
 
-        let fetched_response = app
-            .oneshot(
-                axum::http::Request::builder()
-                    .method("GET")
-                    .uri(format!("/circle/{}", circle_id))
-                    .body(axum::body::Body::empty())?,
-            )
-            .await?;
-        assert_eq!(fetched_response.status(), StatusCode::OK);
-        let _fetched_response_body = String::from_utf8(
-            axum::body::to_bytes(fetched_response.into_body(), usize::MAX)
-                .await?
-                .to_vec(),
-        )?;
+        let fetched = fetch_circle(&app, &circle_id).await?;
+        assert_eq!(fetched.circle_id, circle_id);
+        assert_eq!(fetched.circle_name, "Music club");
+        assert_eq!(fetched.capacity, 10);
+        assert_eq!(fetched.owner_id, Some("student-0001".to_string()));
 
         Ok(())
     }
 
     #[tokio::test]
-    #[ignore]
     async fn test_update_circle() -> anyhow::Result<()> {
-        let (event_publisher, mysql_pool, redis_client) = setup_test_dependencies().await;
-        let command_handler = build_command_handler(mysql_pool, event_publisher);
-        let query_handler = build_query_handler(redis_client);
-        let state = AppState::new(Arc::new(command_handler), Arc::new(query_handler));
-        let app = router().with_state(state.clone());
+        let app = build_in_memory_app();
         let circle_id = build_circle(&app).await?;
-        let update_response = app
+        let update = |version| -> anyhow::Result<_> {
+            Ok(axum::http::Request::builder()
+                .method("PUT")
+                .uri(format!("/circle/{}", circle_id))
+                .header(CONTENT_TYPE, "application/json")
+                .body(axum::body::Body::new(serde_json::to_string(
+                    &UpdateCircleRequestBody {
+                        circle_name: Some("Football club".to_string()),
+                        capacity: Some(20),
+                        version: Some(version),
+                    },
+                )?))?)
+        };
+        let update_response = app.clone().oneshot(update(1)?).await?;
+        assert_eq!(update_response.status(), StatusCode::OK);
+
+        let updated_circle = fetch_circle(&app, &circle_id).await?;
+        assert_eq!(updated_circle.circle_name, "Football club");
+        assert_eq!(updated_circle.capacity, 20);
+
+        // the circle is at version 2 now
+        let stale_response = app.oneshot(update(1)?).await?;
+        assert_eq!(stale_response.status(), StatusCode::CONFLICT);
+        let problem: ProblemDetails = serde_json::from_slice(
+            &axum::body::to_bytes(stale_response.into_body(), usize::MAX).await?,
+        )?;
+        assert_eq!(problem.code, "version_mismatch");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_circle_with_taken_name() -> anyhow::Result<()> {
+        let app = build_in_memory_app();
+        build_circle(&app).await?;
+
+        let response = app
             .oneshot(
                 axum::http::Request::builder()
-                    .method("PUT")
-                    .uri(format!("/circle/{}", circle_id))
+                    .method("POST")
+                    .uri("/circle")
                     .header(CONTENT_TYPE, "application/json")
                     .body(axum::body::Body::new(serde_json::to_string(
-                        &UpdateCircleRequestBody {
-                            circle_name: Some("Football club".to_string()),
-                            capacity: Some(20),
-                            version: 1,
+                        &CreateCircleRequestBody {
+                            circle_name: "Music club".to_string(),
+                            capacity: 5,
+                            owner_id: "student-0002".to_string(),
+                            owner_name: "Paul McCartney".to_string(),
                         },
                     )?))?,
             )
             .await?;
-        assert_eq!(update_response.status(), StatusCode::OK);
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let problem: ProblemDetails = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX).await?,
+        )?;
+        assert_eq!(problem.code, "duplicate_circle_name");
+        Ok(())
+    }
 
-        let updated_circle = state
-            .command_handler
-            .circle_repository()
-            .find_by_id(&CircleId::from_str(&circle_id)?)
-            .await?;
-        assert_eq!(updated_circle.name, "Football club");
-        assert_eq!(updated_circle.capacity, 20);
+    #[tokio::test]
+    async fn test_list_circles() -> anyhow::Result<()> {
+        let app = build_in_memory_app();
+        let circle_id = build_circle(&app).await?;
 
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("GET")
+                    .uri("/circle?name_prefix=Music")
+                    .body(axum::body::Body::empty())?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let listed: ListCirclesResponseBody = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX).await?,
+        )?;
+        assert_eq!(listed.circles.len(), 1);
+        assert_eq!(listed.circles[0].circle_id, circle_id);
+        assert_eq!(listed.next_cursor, None);
         Ok(())
     }
 
+    async fn fetch_circle(
+        app: &Router,
+        circle_id: &str,
+    ) -> anyhow::Result<FetcheCircleResponseBody> {
+        let response = app
+            .clone()
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("GET")
+                    .uri(format!("/circle/{}", circle_id))
+                    .body(axum::body::Body::empty())?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut fetched: Vec<FetcheCircleResponseBody> = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX).await?,
+        )?;
+        assert_eq!(fetched.len(), 1);
+        Ok(fetched.remove(0))
+    }
+
+    struct LiveBackend {
+        repository: Arc<CircleRepository>,
+        reader: Arc<CircleReader>,
+        projection: Subscription<Circle>,
+    }
+
+    #[async_trait::async_trait]
+    impl CircleBackend for LiveBackend {
+        fn repository(&self) -> Arc<dyn CircleRepositoryInterface + Send + Sync> {
+            self.repository.clone()
+        }
+
+        fn reader(&self) -> Arc<dyn CircleReaderInterface + Send + Sync> {
+            self.reader.clone()
+        }
+
+        async fn settle(&self) -> anyhow::Result<()> {
+            while self.projection.catch_up().await? > 0 {}
+            Ok(())
+        }
+    }
+
+    // FIXME: ignore test because it requires a running database and Redis
+    #[tokio::test]
+    #[ignore]
+    async fn test_live_backend_conformance() -> anyhow::Result<()> {
+        let mysql_pool = crate::config::connect::connect().await?;
+        infrastructure::migration::migrate(&mysql_pool).await?;
+        let redis_client = redis_connect()?;
+        let backend = LiveBackend {
+            repository: Arc::new(CircleRepository::new(mysql_pool.clone())),
+            reader: Arc::new(CircleReader::new(redis_client.clone())),
+            projection: Subscription::new(
+                mysql_pool.clone(),
+                "redis_projection",
+                Arc::new(RedisProjectionHandler::new(
+                    redis_client,
+                    Arc::new(EventStore::new(mysql_pool)),
+                )),
+            ),
+        };
+        circle_conformance::run_repository_suite(&*backend.repository).await?;
+        circle_conformance::run_reader_suite(&backend).await
+    }
+
     async fn build_circle(app: &Router) -> anyhow::Result<String> {
         let create_response = app
             .clone()
@@ -251,6 +480,8 @@ mod tests {
                         &CreateCircleRequestBody {
                             circle_name: "Music club".to_string(),
                             capacity: 10,
+                            owner_id: "student-0001".to_string(),
+                            owner_name: "John Lennon".to_string(),
                         },
                     )?))?,
             )
@@ -1,2 +1,9 @@
+pub mod admin_token;
 pub mod connect;
+pub mod database;
+#[cfg(feature = "postgres")]
+pub mod postgres_connect;
 pub mod redis_connect;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_connect;
+pub mod trusted_proxies;
@@ -0,0 +1,11 @@
+use std::env;
+
+use api::admin::AdminToken;
+use dotenv::dotenv;
+
+/// Token the `/admin` endpoints require, set with `ADMIN_TOKEN`. Without it they are
+/// disabled.
+pub fn admin_token() -> AdminToken {
+    dotenv().ok();
+    AdminToken::new(env::var("ADMIN_TOKEN").ok())
+}
@@ -45,20 +45,13 @@ impl DbConfig {
 
 pub async fn connect() -> Result<sqlx::MySqlPool, sqlx::Error> {
     let config = DbConfig::from_env();
-    let pool = MySqlPoolOptions::new()
-        .max_connections(5)
-        .connect(&config.connection())
-        .await?;
-    Ok(pool)
+    connect_to(&config.connection()).await
 }
 
-#[cfg(test)]
-pub async fn connect_test() -> Result<sqlx::MySqlPool, sqlx::Error> {
-    // TODO: build a db connection for testing
-    let config = DbConfig::from_env();
+pub async fn connect_to(url: &str) -> Result<sqlx::MySqlPool, sqlx::Error> {
     let pool = MySqlPoolOptions::new()
         .max_connections(5)
-        .connect(&config.connection())
+        .connect(url)
         .await?;
     Ok(pool)
 }
@@ -0,0 +1,102 @@
+use std::{env, sync::Arc};
+
+use anyhow::Error;
+use domain::aggregate::circle::Circle;
+use dotenv::dotenv;
+use infrastructure::{
+    event_store::{EventLog, EventStore},
+    migration::{self, Migration},
+};
+
+use super::connect;
+
+/// The database holding the event store, picked by the scheme of `DATABASE_URL`:
+/// `mysql://...`, with the `sqlite` feature `sqlite://path/to/file.db`, or with the
+/// `postgres` feature `postgres://...`.
+///
+/// Without `DATABASE_URL` MySQL is reached through the `MYSQL_*` variables.
+#[derive(Clone, Debug)]
+pub enum Database {
+    MySql(sqlx::MySqlPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::SqlitePool),
+    #[cfg(feature = "postgres")]
+    Postgres(sqlx::PgPool),
+}
+
+impl Database {
+    /// The circle streams, for the readers and the projection.
+    pub fn circle_events(&self) -> Arc<dyn EventLog<Circle>> {
+        match self {
+            Database::MySql(db) => Arc::new(EventStore::new(db.clone())),
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(db) => Arc::new(EventStore::new(db.clone())),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => Arc::new(EventStore::new(db.clone())),
+        }
+    }
+
+    /// Applies the embedded migrations the database lacks and returns them.
+    pub async fn migrate(&self) -> Result<Vec<&'static Migration>, Error> {
+        match self {
+            Database::MySql(db) => migration::migrate(db).await,
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(db) => migration::migrate(db).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => migration::migrate(db).await,
+        }
+    }
+
+    /// The embedded migrations the database lacks. Fails when a newer build migrated it.
+    pub async fn pending_migrations(&self) -> Result<Vec<&'static Migration>, Error> {
+        match self {
+            Database::MySql(db) => migration::pending_migrations(db).await,
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(db) => migration::pending_migrations(db).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => migration::pending_migrations(db).await,
+        }
+    }
+}
+
+/// Whether `serve` applies pending migrations itself, set with `MIGRATE_ON_START=true`.
+/// Otherwise it refuses to start until `migrate` has been run.
+pub fn migrate_on_start() -> bool {
+    dotenv().ok();
+    env::var("MIGRATE_ON_START").is_ok_and(|value| value == "true")
+}
+
+pub async fn connect() -> Result<Database, Error> {
+    dotenv().ok();
+    let Ok(url) = env::var("DATABASE_URL") else {
+        return Ok(Database::MySql(connect::connect().await?));
+    };
+
+    // only the scheme is reported, as the rest may hold a password
+    let scheme = url
+        .split_once(':')
+        .map_or(url.as_str(), |(scheme, _)| scheme);
+    match scheme {
+        "mysql" => Ok(Database::MySql(connect::connect_to(&url).await?)),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Database::Sqlite(
+            super::sqlite_connect::connect(&url).await?,
+        )),
+        #[cfg(feature = "postgres")]
+        "postgres" | "postgresql" => Ok(Database::Postgres(
+            super::postgres_connect::connect(&url).await?,
+        )),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => Err(Error::msg(
+            "DATABASE_URL points to SQLite, but the app was built without the sqlite feature",
+        )),
+        #[cfg(not(feature = "postgres"))]
+        "postgres" | "postgresql" => Err(Error::msg(
+            "DATABASE_URL points to PostgreSQL, but the app was built without the postgres feature",
+        )),
+        scheme => Err(Error::msg(format!(
+            "Unsupported DATABASE_URL scheme: {}",
+            scheme
+        ))),
+    }
+}
@@ -0,0 +1,7 @@
+use sqlx::postgres::PgPoolOptions;
+
+/// Connects to the PostgreSQL database of `url`.
+pub async fn connect(url: &str) -> Result<sqlx::PgPool, anyhow::Error> {
+    let pool = PgPoolOptions::new().max_connections(5).connect(url).await?;
+    Ok(pool)
+}
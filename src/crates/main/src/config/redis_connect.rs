@@ -22,11 +22,3 @@ pub fn connect() -> Result<Client, redis::RedisError> {
     let client = Client::open(config.redis_url)?;
     Ok(client)
 }
-
-#[cfg(test)]
-pub fn connect_test() -> Result<Client, redis::RedisError> {
-    // TODO: build a Redis connection for testing
-    let config = RedisConfig::from_env();
-    let client = Client::open(config.redis_url)?;
-    Ok(client)
-}
\ No newline at end of file
@@ -0,0 +1,18 @@
+use std::{str::FromStr, time::Duration};
+
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+
+/// Opens the SQLite file of `url`, creating it when missing.
+pub async fn connect(url: &str) -> Result<sqlx::SqlitePool, anyhow::Error> {
+    println!("Opening SQLite database: {}", url);
+    // WAL lets the projection read while a command writes
+    let options = SqliteConnectOptions::from_str(url)?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(Duration::from_secs(5));
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await?;
+    Ok(pool)
+}
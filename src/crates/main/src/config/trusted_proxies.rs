@@ -0,0 +1,17 @@
+use std::env;
+
+use api::metadata::TrustedProxies;
+use dotenv::dotenv;
+
+/// Proxies whose `X-Forwarded-For` is recorded as the client IP of events, set with
+/// `TRUSTED_PROXIES` as comma-separated IP addresses. None by default.
+pub fn trusted_proxies() -> TrustedProxies {
+    dotenv().ok();
+    env::var("TRUSTED_PROXIES")
+        .map(|proxies| {
+            proxies
+                .parse()
+                .expect("TRUSTED_PROXIES must be comma-separated IP addresses")
+        })
+        .unwrap_or_default()
+}
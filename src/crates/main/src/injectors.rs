@@ -1,4 +1,6 @@
 pub mod build_command_handler;
+#[cfg(any(test, feature = "in-memory"))]
+pub mod build_in_memory_app;
 pub mod build_query_handler;
 pub mod command_handler_impl;
 pub mod query_handler_impl;
@@ -1,25 +1,51 @@
 use std::sync::Arc;
 
+use domain::interface::command::{
+    circle_duplicate_checker_interface::CircleDuplicateCheckerInterface,
+    circle_repository_interface::CircleRepositoryInterface,
+    idempotency_store_interface::IdempotencyStoreInterface,
+};
 use infrastructure::{
-    circle_duplicate_checker::CircleDuplicateChecker, 
-    circle_repository::CircleRepository,
-    event_publisher::EventPublisher,
+    backend::Backend, circle_duplicate_checker::CircleDuplicateChecker,
+    circle_repository::CircleRepository, idempotency_store::IdempotencyStore,
+    snapshot_policy::EventsSinceLastSnapshot,
 };
+use sqlx::{Encode, Executor, IntoArguments, Pool, Type};
 
 use super::command_handler_impl::CommandHandlerImpl;
+use crate::config::database::Database;
+
+pub fn build_command_handler(database: &Database) -> CommandHandlerImpl {
+    match database {
+        Database::MySql(db) => build_command_handler_on(db),
+        #[cfg(feature = "sqlite")]
+        Database::Sqlite(db) => build_command_handler_on(db),
+        #[cfg(feature = "postgres")]
+        Database::Postgres(db) => build_command_handler_on(db),
+    }
+}
 
-pub fn build_command_handler(
-    db: sqlx::MySqlPool,
-    event_publisher: Arc<dyn EventPublisher>,
-) -> CommandHandlerImpl {
-    let circle_repository = Arc::new(CircleRepository::new(
-        db.clone(), 
-        event_publisher
-    ));
+fn build_command_handler_on<DB>(db: &Pool<DB>) -> CommandHandlerImpl
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    CircleRepository<DB>: CircleRepositoryInterface + 'static,
+    CircleDuplicateChecker<DB>: CircleDuplicateCheckerInterface + 'static,
+    IdempotencyStore<DB>: IdempotencyStoreInterface + 'static,
+{
+    let circle_repository = Arc::new(
+        CircleRepository::new(db.clone())
+            .with_snapshot_policy(Arc::new(EventsSinceLastSnapshot(5)))
+            .with_snapshot_retention(3),
+    );
     let circle_duplicate_checker = Arc::new(CircleDuplicateChecker::new(db.clone()));
+    let idempotency_store = Arc::new(IdempotencyStore::new(db.clone()));
 
     CommandHandlerImpl {
         circle_repository,
         circle_duplicate_checker,
+        idempotency_store,
     }
 }
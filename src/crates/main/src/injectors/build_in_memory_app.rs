@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use api::{app_state::AppState, router::router};
+use axum::Router;
+use infrastructure::{
+    in_memory::{
+        circle_projection::InMemoryCircleProjection, circle_repository::InMemoryCircleRepository,
+        event_store::InMemoryEventStore, idempotency_store::InMemoryIdempotencyStore,
+    },
+    snapshot_policy::EventsSinceLastSnapshot,
+};
+
+use super::{command_handler_impl::CommandHandlerImpl, query_handler_impl::QueryHandlerImpl};
+
+/// The full router over fresh in-memory infrastructure, wired like [`crate::app::run`],
+/// so requests can be tested end to end without MySQL or Redis.
+pub fn build_in_memory_app() -> Router {
+    let events = InMemoryEventStore::new();
+    let projection = InMemoryCircleProjection::subscribed_to(events.clone());
+    let circle_repository = Arc::new(
+        InMemoryCircleRepository::new(events.clone())
+            .with_snapshot_policy(Arc::new(EventsSinceLastSnapshot(5)))
+            .with_snapshot_retention(3),
+    );
+
+    let command_handler = CommandHandlerImpl {
+        circle_repository: circle_repository.clone(),
+        circle_duplicate_checker: circle_repository,
+        idempotency_store: Arc::new(InMemoryIdempotencyStore::new()),
+    };
+    let query_handler = QueryHandlerImpl {
+        circle_reader: projection.clone(),
+        circle_event_reader: Arc::new(events),
+    };
+    let state = AppState::new(
+        Arc::new(command_handler),
+        Arc::new(query_handler),
+        projection,
+    );
+
+    router().with_state(state)
+}
@@ -1,10 +1,15 @@
 use std::sync::Arc;
 
-use infrastructure::circle_reader::CircleReader;
+use infrastructure::{circle_event_reader::CircleEventReader, circle_reader::CircleReader};
 
 use super::query_handler_impl::QueryHandlerImpl;
+use crate::config::database::Database;
 
-pub fn build_query_handler(redis_client: redis::Client) -> QueryHandlerImpl {
+pub fn build_query_handler(redis_client: redis::Client, database: &Database) -> QueryHandlerImpl {
     let circle_reader = Arc::new(CircleReader::new(redis_client));
-    QueryHandlerImpl { circle_reader }
+    let circle_event_reader = Arc::new(CircleEventReader::new(database.circle_events()));
+    QueryHandlerImpl {
+        circle_reader,
+        circle_event_reader,
+    }
 }
@@ -8,11 +8,15 @@ use domain::interface::{
     command::circle_repository_interface::{
         CircleRepositoryInterface, HasCircleRepositoryInterface,
     },
+    command::idempotency_store_interface::{
+        HasIdempotencyStoreInterface, IdempotencyStoreInterface,
+    },
 };
 
 pub(crate) struct CommandHandlerImpl {
     pub(crate) circle_repository: Arc<dyn CircleRepositoryInterface + Send + Sync>,
     pub(crate) circle_duplicate_checker: Arc<dyn CircleDuplicateCheckerInterface + Send + Sync>,
+    pub(crate) idempotency_store: Arc<dyn IdempotencyStoreInterface + Send + Sync>,
 }
 
 impl HasCircleDuplicateCheckerInterface for CommandHandlerImpl {
@@ -27,4 +31,10 @@ impl HasCircleRepositoryInterface for CommandHandlerImpl {
     }
 }
 
+impl HasIdempotencyStoreInterface for CommandHandlerImpl {
+    fn idempotency_store(&self) -> Arc<dyn IdempotencyStoreInterface + Send + Sync> {
+        self.idempotency_store.clone()
+    }
+}
+
 impl CommandHandler for CommandHandlerImpl {}
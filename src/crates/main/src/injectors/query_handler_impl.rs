@@ -1,9 +1,13 @@
-use domain::interface::query::circle_reader_interface::{CircleReaderInterface, HasCircleReader};
+use domain::interface::query::{
+    circle_event_reader_interface::{CircleEventReaderInterface, HasCircleEventReader},
+    circle_reader_interface::{CircleReaderInterface, HasCircleReader},
+};
 use query::query_handler::QueryHandler;
 use std::sync::Arc;
 
 pub(crate) struct QueryHandlerImpl {
     pub(crate) circle_reader: Arc<dyn CircleReaderInterface + Send + Sync>,
+    pub(crate) circle_event_reader: Arc<dyn CircleEventReaderInterface + Send + Sync>,
 }
 
 impl HasCircleReader for QueryHandlerImpl {
@@ -12,4 +16,10 @@ impl HasCircleReader for QueryHandlerImpl {
     }
 }
 
+impl HasCircleEventReader for QueryHandlerImpl {
+    fn circle_event_reader(&self) -> Arc<dyn CircleEventReaderInterface + Send + Sync> {
+        self.circle_event_reader.clone()
+    }
+}
+
 impl QueryHandler for QueryHandlerImpl {}
@@ -1,3 +1,6 @@
 pub mod app;
 pub(crate) mod config;
 pub(crate) mod injectors;
+
+#[cfg(feature = "in-memory")]
+pub use injectors::build_in_memory_app::build_in_memory_app;
@@ -1,2 +1,4 @@
 pub mod get_circle;
+pub mod get_circle_as_of;
+pub mod list_circle_events;
 pub mod list_circles;
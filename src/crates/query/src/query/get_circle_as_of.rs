@@ -0,0 +1,25 @@
+use std::{str::FromStr, sync::Arc};
+
+use domain::{
+    aggregate::{circle::Circle, value_object::circle_id::CircleId},
+    interface::query::circle_event_reader_interface::{AsOf, CircleEventReaderInterface},
+};
+
+pub struct Input {
+    pub circle_id: String,
+    pub as_of: AsOf,
+}
+
+pub struct Output(pub Option<Circle>);
+
+pub async fn handle(
+    circle_event_reader: Arc<dyn CircleEventReaderInterface + Send + Sync>,
+    Input { circle_id, as_of }: Input,
+) -> Result<Output, anyhow::Error> {
+    let circle_id = CircleId::from_str(circle_id.as_str())?;
+    let circle = circle_event_reader
+        .get_circle_as_of(&circle_id, as_of)
+        .await
+        .map_err(|e| anyhow::Error::msg(e.to_string()))?;
+    Ok(Output(circle))
+}
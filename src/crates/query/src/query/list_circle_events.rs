@@ -0,0 +1,163 @@
+use std::{str::FromStr, sync::Arc};
+
+use domain::{
+    aggregate::{
+        circle::event::CircleEvent,
+        value_object::{circle_id::CircleId, version::Version},
+    },
+    interface::{
+        command::circle_repository_interface::CircleNotFound,
+        query::circle_event_reader_interface::CircleEventReaderInterface,
+    },
+};
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+
+pub struct Input {
+    pub circle_id: String,
+    pub from_version: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+pub struct Output {
+    pub events: Vec<CircleEvent>,
+    /// Version to pass as `from_version` to fetch the next page, if there may be one.
+    pub next_from_version: Option<u32>,
+}
+
+/// Fails with [`CircleNotFound`] when the circle has no events at all.
+pub async fn handle(
+    circle_event_reader: Arc<dyn CircleEventReaderInterface + Send + Sync>,
+    Input {
+        circle_id,
+        from_version,
+        limit,
+    }: Input,
+) -> Result<Output, anyhow::Error> {
+    let circle_id = CircleId::from_str(circle_id.as_str())?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let events = circle_event_reader
+        .list_events(&circle_id, from_version.map(Version::from), limit)
+        .await
+        .map_err(|e| anyhow::Error::msg(e.to_string()))?;
+    // an empty page is also what a `from_version` past the head returns, so only a
+    // stream without a first event is missing
+    if events.is_empty() {
+        let exists = from_version.is_some()
+            && !circle_event_reader
+                .list_events(&circle_id, None, 1)
+                .await
+                .map_err(|e| anyhow::Error::msg(e.to_string()))?
+                .is_empty();
+        if !exists {
+            return Err(CircleNotFound { circle_id }.into());
+        }
+    }
+
+    let next_from_version = match events.last() {
+        Some(last) if events.len() as u32 == limit => Some(u32::from(last.version.next())),
+        _ => None,
+    };
+    Ok(Output {
+        events,
+        next_from_version,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use domain::{
+        aggregate::{
+            circle::{member::Member, Circle},
+            value_object::member_id::MemberId,
+        },
+        interface::query::circle_event_reader_interface::MockCircleEventReaderInterface,
+    };
+
+    use super::*;
+
+    /// `count` events of one circle, from version 1 on.
+    fn events(count: u32) -> anyhow::Result<(CircleId, Vec<CircleEvent>)> {
+        let owner = Member::new(MemberId::from_str("owner")?, "owner".to_string())?;
+        let (circle, created) = Circle::create("Music club".to_string(), 10, owner)?;
+        let mut events = vec![created];
+        // the builder takes the version before the event
+        for version in 1..count {
+            events.push(
+                CircleEvent::build(circle.id.clone(), Version::from(version))
+                    .circle_updated(None, Some(20)),
+            );
+        }
+        Ok((circle.id, events))
+    }
+
+    fn reader(events: Vec<CircleEvent>) -> Arc<MockCircleEventReaderInterface> {
+        let mut reader = MockCircleEventReaderInterface::new();
+        reader
+            .expect_list_events()
+            .returning(move |_, from_version, limit| {
+                let from = from_version.map_or(1, u32::from);
+                Ok(events
+                    .iter()
+                    .filter(|event| u32::from(event.version) >= from)
+                    .take(limit as usize)
+                    .cloned()
+                    .collect())
+            });
+        Arc::new(reader)
+    }
+
+    fn input(circle_id: &CircleId, from_version: Option<u32>, limit: Option<u32>) -> Input {
+        Input {
+            circle_id: circle_id.to_string(),
+            from_version,
+            limit,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limit_is_defaulted_and_clamped() -> anyhow::Result<()> {
+        let (circle_id, events) = events(MAX_LIMIT + 1)?;
+        let reader = reader(events);
+
+        let output = handle(reader.clone(), input(&circle_id, None, None)).await?;
+        assert_eq!(output.events.len(), DEFAULT_LIMIT as usize);
+        let output = handle(reader.clone(), input(&circle_id, None, Some(MAX_LIMIT + 1))).await?;
+        assert_eq!(output.events.len(), MAX_LIMIT as usize);
+        let output = handle(reader, input(&circle_id, None, Some(0))).await?;
+        assert_eq!(output.events.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_next_from_version_is_set_on_full_pages_only() -> anyhow::Result<()> {
+        let (circle_id, events) = events(5)?;
+        let reader = reader(events);
+
+        let output = handle(reader.clone(), input(&circle_id, None, Some(2))).await?;
+        assert_eq!(output.next_from_version, Some(3));
+        let output = handle(reader.clone(), input(&circle_id, Some(3), Some(2))).await?;
+        assert_eq!(output.next_from_version, Some(5));
+        let output = handle(reader.clone(), input(&circle_id, Some(5), Some(2))).await?;
+        assert_eq!(output.events.len(), 1);
+        assert_eq!(output.next_from_version, None);
+        // past the head of an existing circle
+        let output = handle(reader, input(&circle_id, Some(6), Some(2))).await?;
+        assert!(output.events.is_empty());
+        assert_eq!(output.next_from_version, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unknown_circle_is_not_found() -> anyhow::Result<()> {
+        let (circle_id, _) = events(1)?;
+        let reader = reader(Vec::new());
+
+        for from_version in [None, Some(3)] {
+            let result = handle(reader.clone(), input(&circle_id, from_version, None)).await;
+            assert!(result.is_err_and(|e| e.is::<CircleNotFound>()));
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,56 @@
 use std::sync::Arc;
 
 use domain::{
-    aggregate::circle::Circle, interface::query::circle_reader_interface::CircleReaderInterface,
+    aggregate::circle::Circle,
+    interface::query::circle_reader_interface::{
+        CircleListQuery, CircleReaderInterface, CircleSortKey, SortOrder,
+    },
 };
 
-pub struct Input;
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
 
-pub struct Output(pub Vec<Circle>);
+#[derive(Default)]
+pub struct Input {
+    pub sort_key: CircleSortKey,
+    pub order: SortOrder,
+    pub name_prefix: Option<String>,
+    pub min_capacity: Option<i16>,
+    pub max_capacity: Option<i16>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+pub struct Output {
+    pub circles: Vec<Circle>,
+    /// Cursor to pass as `cursor` to fetch the next page, if there may be one.
+    pub next_cursor: Option<String>,
+}
 
 pub async fn handle(
     circle_reader: Arc<dyn CircleReaderInterface + Send + Sync>,
-    Input {}: Input,
+    Input {
+        sort_key,
+        order,
+        name_prefix,
+        min_capacity,
+        max_capacity,
+        limit,
+        cursor,
+    }: Input,
 ) -> Result<Output, anyhow::Error> {
-    let circles = circle_reader
-        .list_circles()
-        .await
-        .map_err(|e| anyhow::Error::msg(e.to_string()))?;
-    Ok(Output(circles))
+    let query = CircleListQuery {
+        sort_key,
+        order,
+        name_prefix: name_prefix.filter(|prefix| !prefix.is_empty()),
+        min_capacity,
+        max_capacity,
+        limit: limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+        cursor,
+    };
+    let page = circle_reader.list_circles(&query).await?;
+    Ok(Output {
+        circles: page.circles,
+        next_cursor: page.next_cursor,
+    })
 }
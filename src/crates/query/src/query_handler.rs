@@ -1,14 +1,18 @@
 use std::sync::Arc;
 
-use domain::interface::query::circle_reader_interface::HasCircleReader;
+use domain::interface::query::{
+    circle_event_reader_interface::HasCircleEventReader, circle_reader_interface::HasCircleReader,
+};
 
 use crate::query::{
     get_circle::{self},
+    get_circle_as_of::{self},
+    list_circle_events::{self},
     list_circles::{self},
 };
 
 #[async_trait::async_trait]
-pub trait QueryHandler: HasCircleReader {
+pub trait QueryHandler: HasCircleReader + HasCircleEventReader {
     async fn get_circle(
         &self,
         input: get_circle::Input,
@@ -16,8 +20,25 @@ pub trait QueryHandler: HasCircleReader {
         get_circle::handle(self.circle_reader(), input).await
     }
 
-    async fn list_circles(&self) -> Result<list_circles::Output, anyhow::Error> {
-        list_circles::handle(self.circle_reader(), list_circles::Input {}).await
+    async fn get_circle_as_of(
+        &self,
+        input: get_circle_as_of::Input,
+    ) -> Result<get_circle_as_of::Output, anyhow::Error> {
+        get_circle_as_of::handle(self.circle_event_reader(), input).await
+    }
+
+    async fn list_circles(
+        &self,
+        input: list_circles::Input,
+    ) -> Result<list_circles::Output, anyhow::Error> {
+        list_circles::handle(self.circle_reader(), input).await
+    }
+
+    async fn list_circle_events(
+        &self,
+        input: list_circle_events::Input,
+    ) -> Result<list_circle_events::Output, anyhow::Error> {
+        list_circle_events::handle(self.circle_event_reader(), input).await
     }
 }
 